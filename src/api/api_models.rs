@@ -1,8 +1,9 @@
 use form_urlencoded::Serializer;
 use regex::Regex;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 use std::{
     convert::{Into, TryFrom, TryInto},
+    time::{Duration, SystemTime},
     vec::IntoIter,
 };
 
@@ -14,13 +15,120 @@ pub struct Uris {
 }
 
 #[derive(Serialize)]
-pub struct Ids {
-    pub ids: Vec<String>,
+pub struct TrackUri {
+    pub uri: String,
+}
+
+#[derive(Serialize)]
+pub struct Tracks {
+    pub tracks: Vec<TrackUri>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub snapshot_id: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct PlaylistReorder {
+    pub range_start: usize,
+    pub insert_before: usize,
+    pub range_length: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub snapshot_id: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct SnapshotResponse {
+    pub snapshot_id: String,
+}
+
+// Body of failed requests, {"error": {"status": 404, "message": "..."}}
+#[derive(Deserialize, Debug, Clone)]
+pub struct SpotifyApiErrorBody {
+    pub error: SpotifyApiErrorDetails,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct SpotifyApiErrorDetails {
+    pub message: Option<String>,
+}
+
+impl SpotifyApiErrorBody {
+    // The message from a JSON error body, none if the body is something else
+    pub fn message_from(body: &str) -> Option<String> {
+        serde_json::from_str::<Self>(body)
+            .ok()
+            .and_then(|body| body.error.message)
+            .filter(|message| !message.is_empty())
+    }
+}
+
+#[derive(Serialize)]
+pub struct PlaybackTransfer {
+    pub device_ids: Vec<String>,
+    pub play: bool,
+}
+
+// What remote playback starts with: a context such as an album or playlist, optionally
+// from a given position, or a list of tracks. The API rejects bodies with both.
+#[derive(Clone, Debug)]
+pub enum PlaybackTarget {
+    Context { uri: String, offset: Option<usize> },
+    Tracks(Vec<String>),
+}
+
+#[derive(Serialize, Default)]
+pub struct StartPlayback {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    context_uri: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    uris: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    offset: Option<PlaybackOffset>,
+}
+
+#[derive(Serialize)]
+struct PlaybackOffset {
+    position: usize,
 }
 
+impl From<PlaybackTarget> for StartPlayback {
+    fn from(target: PlaybackTarget) -> Self {
+        match target {
+            PlaybackTarget::Context { uri, offset } => Self {
+                context_uri: Some(uri),
+                offset: offset.map(|position| PlaybackOffset { position }),
+                ..Default::default()
+            },
+            PlaybackTarget::Tracks(uris) => Self {
+                uris: Some(uris),
+                ..Default::default()
+            },
+        }
+    }
+}
+
+#[derive(Serialize, Default)]
+pub struct PlaylistDetails {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub public: Option<bool>,
+}
+
+#[derive(Serialize)]
+pub struct PlaylistFollow {
+    pub public: bool,
+}
+
+#[derive(Clone, Copy, Debug)]
 pub enum SearchType {
     Artist,
     Album,
+    Playlist,
+    Track,
+    Show,
+    Episode,
 }
 
 impl SearchType {
@@ -28,6 +136,10 @@ impl SearchType {
         match self {
             Self::Artist => "artist",
             Self::Album => "album",
+            Self::Playlist => "playlist",
+            Self::Track => "track",
+            Self::Show => "show",
+            Self::Episode => "episode",
         }
     }
 }
@@ -41,6 +153,10 @@ pub struct SearchQuery {
 
 impl SearchQuery {
     pub fn into_query_string(self) -> String {
+        self.into_query_string_for_market("from_token")
+    }
+
+    pub fn into_query_string_for_market(self, market: &str) -> String {
         let mut types = self
             .types
             .into_iter()
@@ -54,19 +170,130 @@ impl SearchQuery {
             .append_pair("q", query.as_ref())
             .append_pair("offset", &self.offset.to_string()[..])
             .append_pair("limit", &self.limit.to_string()[..])
-            .append_pair("market", "from_token")
+            .append_pair("market", market)
             .finish();
 
         format!("type={}&{}", types, serialized)
     }
 }
 
+#[derive(Clone, Copy, Debug)]
+pub enum TimeRange {
+    ShortTerm,
+    MediumTerm,
+    LongTerm,
+}
+
+impl TimeRange {
+    pub fn into_string(self) -> &'static str {
+        match self {
+            Self::ShortTerm => "short_term",
+            Self::MediumTerm => "medium_term",
+            Self::LongTerm => "long_term",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AlbumType {
+    Album,
+    Single,
+    AppearsOn,
+    Compilation,
+}
+
+impl AlbumType {
+    // What an artist page lists as their own releases
+    pub const DEFAULT_GROUPS: &'static [AlbumType] = &[Self::Album, Self::Single];
+
+    pub fn into_string(self) -> &'static str {
+        match self {
+            Self::Album => "album",
+            Self::Single => "single",
+            Self::AppearsOn => "appears_on",
+            Self::Compilation => "compilation",
+        }
+    }
+
+    // Releases other artists put out, these often get listed once per market they're available in
+    pub fn is_listed_per_market(self) -> bool {
+        matches!(self, Self::AppearsOn | Self::Compilation)
+    }
+}
+
+// Audio attributes recommendations can be tuned with, unset ones are left to Spotify
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TrackAttributes {
+    pub acousticness: Option<f32>,
+    pub danceability: Option<f32>,
+    pub energy: Option<f32>,
+    pub instrumentalness: Option<f32>,
+    pub liveness: Option<f32>,
+    pub loudness: Option<f32>,
+    pub speechiness: Option<f32>,
+    pub tempo: Option<f32>,
+    pub valence: Option<f32>,
+    pub popularity: Option<u32>,
+}
+
+impl TrackAttributes {
+    // Values are clamped to the ranges documented by Spotify, anything outside of them is a 400
+    fn query_pairs(&self) -> Vec<(&'static str, String)> {
+        let unit = |value: Option<f32>| value.map(|v| v.clamp(0.0, 1.0));
+        let attributes = vec![
+            ("acousticness", unit(self.acousticness)),
+            ("danceability", unit(self.danceability)),
+            ("energy", unit(self.energy)),
+            ("instrumentalness", unit(self.instrumentalness)),
+            ("liveness", unit(self.liveness)),
+            ("loudness", self.loudness.map(|v| v.clamp(-60.0, 0.0))),
+            ("speechiness", unit(self.speechiness)),
+            ("tempo", self.tempo.map(|v| v.max(0.0))),
+            ("valence", unit(self.valence)),
+        ];
+        attributes
+            .into_iter()
+            .filter_map(|(name, value)| Some((name, value?.to_string())))
+            .chain(
+                self.popularity
+                    .map(|popularity| ("popularity", u32::min(popularity, 100).to_string())),
+            )
+            .collect()
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct RecommendationParams {
+    pub target: TrackAttributes,
+    pub min: TrackAttributes,
+    pub max: TrackAttributes,
+}
+
+impl RecommendationParams {
+    pub fn query_pairs(&self) -> Vec<(String, String)> {
+        [
+            ("target", &self.target),
+            ("min", &self.min),
+            ("max", &self.max),
+        ]
+        .iter()
+        .flat_map(|(prefix, attributes)| {
+            attributes
+                .query_pairs()
+                .into_iter()
+                .map(move |(name, value)| (format!("{}_{}", prefix, name), value))
+        })
+        .collect()
+    }
+}
+
 #[derive(Deserialize, Debug, Clone)]
 pub struct Page<T> {
     items: Option<Vec<T>>,
     offset: Option<usize>,
     limit: Option<usize>,
     total: usize,
+    next: Option<String>,
 }
 
 impl<T> Page<T> {
@@ -77,6 +304,7 @@ impl<T> Page<T> {
             items: Some(items),
             offset: Some(0),
             limit: Some(l),
+            next: None,
         }
     }
 
@@ -89,12 +317,14 @@ impl<T> Page<T> {
             offset,
             limit,
             total,
+            next,
         } = self;
         Page {
             items: items.map(|item| item.into_iter().map(mapper).collect()),
             offset,
             limit,
             total,
+            next,
         }
     }
 
@@ -112,6 +342,15 @@ impl<T> Page<T> {
     pub fn offset(&self) -> usize {
         self.offset.unwrap_or(0)
     }
+
+    // None once the last page has been reached
+    pub fn next_offset(&self) -> Option<usize> {
+        let count = self.items.as_ref().map(|items| items.len()).unwrap_or(0);
+        self.next
+            .as_ref()
+            .filter(|_| count > 0)
+            .map(|_| self.offset() + count)
+    }
 }
 
 impl<T> IntoIterator for Page<T> {
@@ -130,10 +369,43 @@ impl<T> Default for Page<T> {
             total: 0,
             offset: Some(0),
             limit: Some(0),
+            next: None,
         }
     }
 }
 
+#[derive(Deserialize, Debug, Clone)]
+pub struct Cursors {
+    pub after: Option<String>,
+    pub before: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct CursorPage<T> {
+    items: Option<Vec<T>>,
+    pub next: Option<String>,
+    pub cursors: Option<Cursors>,
+}
+
+impl<T> CursorPage<T> {
+    pub fn after(&self) -> Option<&str> {
+        self.cursors.as_ref()?.after.as_deref()
+    }
+
+    pub fn before(&self) -> Option<&str> {
+        self.cursors.as_ref()?.before.as_deref()
+    }
+}
+
+impl<T> IntoIterator for CursorPage<T> {
+    type Item = T;
+    type IntoIter = IntoIter<Self::Item>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.items.unwrap_or_default().into_iter()
+    }
+}
+
 trait WithImages {
     fn images(&self) -> &[Image];
 
@@ -160,6 +432,16 @@ pub struct Playlist {
     pub images: Vec<Image>,
     pub tracks: Page<PlaylistTrack>,
     pub owner: PlaylistOwner,
+    #[serde(default)]
+    pub snapshot_id: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_followers")]
+    pub followers: u64,
+    #[serde(
+        default,
+        rename = "external_urls",
+        deserialize_with = "deserialize_external_url"
+    )]
+    pub external_url: Option<String>,
 }
 
 #[derive(Deserialize, Debug, Clone)]
@@ -177,15 +459,84 @@ impl WithImages for Playlist {
 #[derive(Deserialize, Debug, Clone)]
 pub struct PlaylistTrack {
     pub is_local: bool,
-    pub track: Option<FailibleTrackItem>,
+    pub track: Option<PlaylistItem>,
+    // missing for very old playlists
+    #[serde(default, deserialize_with = "deserialize_timestamp")]
+    pub added_at: Option<SystemTime>,
+    #[serde(default)]
+    pub added_by: Option<PlaylistTrackAdder>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct PlaylistTrackAdder {
+    pub id: String,
 }
 
 #[derive(Deserialize, Debug, Clone)]
 pub struct SavedTrack {
-    pub added_at: String,
+    #[serde(default, deserialize_with = "deserialize_timestamp")]
+    pub added_at: Option<SystemTime>,
     pub track: TrackItem,
 }
 
+// Spotify timestamps are ISO 8601 strings in UTC, e.g. "2016-10-24T15:03:07Z"
+fn parse_timestamp(raw: &str) -> Option<SystemTime> {
+    let date = glib::DateTime::from_iso8601(raw, Some(&glib::TimeZone::utc())).ok()?;
+    let secs = u64::try_from(date.to_unix()).ok()?;
+    Some(SystemTime::UNIX_EPOCH + Duration::from_secs(secs))
+}
+
+fn deserialize_timestamp<'de, D>(deserializer: D) -> Result<Option<SystemTime>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let raw = Option::<String>::deserialize(deserializer)?;
+    Ok(raw.as_deref().and_then(parse_timestamp))
+}
+
+// Followers come as an object like {"href": null, "total": 1234}, only the total is of use
+fn deserialize_followers<'de, D>(deserializer: D) -> Result<u64, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    struct Followers {
+        total: u64,
+    }
+
+    let followers = Option::<Followers>::deserialize(deserializer)?;
+    Ok(followers.map(|f| f.total).unwrap_or(0))
+}
+
+// The shareable link of an object, from {"spotify": "https://open.spotify.com/..."}
+fn deserialize_external_url<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    struct ExternalUrls {
+        spotify: Option<String>,
+    }
+
+    let urls = Option::<ExternalUrls>::deserialize(deserializer)?;
+    Ok(urls.and_then(|urls| urls.spotify))
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct PlayContext {
+    pub uri: String,
+    #[serde(alias = "type")]
+    pub type_: String,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct PlayHistory {
+    pub track: TrackItem,
+    #[serde(default, deserialize_with = "deserialize_timestamp")]
+    pub played_at: Option<SystemTime>,
+    pub context: Option<PlayContext>,
+}
+
 #[derive(Deserialize, Debug, Clone)]
 pub struct SavedAlbum {
     pub album: Album,
@@ -205,8 +556,16 @@ pub struct Album {
     pub tracks: Option<Page<AlbumTrackItem>>,
     pub artists: Vec<Artist>,
     pub release_date: Option<String>,
+    #[serde(default)]
+    pub release_date_precision: Option<String>,
     pub name: String,
     pub images: Vec<Image>,
+    #[serde(
+        default,
+        rename = "external_urls",
+        deserialize_with = "deserialize_external_url"
+    )]
+    pub external_url: Option<String>,
 }
 
 #[derive(Deserialize, Debug, Clone)]
@@ -241,6 +600,16 @@ pub struct Artist {
     pub id: String,
     pub name: String,
     pub images: Option<Vec<Image>>,
+    #[serde(default)]
+    pub popularity: u32,
+    #[serde(default, deserialize_with = "deserialize_followers")]
+    pub followers: u64,
+    #[serde(
+        default,
+        rename = "external_urls",
+        deserialize_with = "deserialize_external_url"
+    )]
+    pub external_url: Option<String>,
 }
 
 impl WithImages for Artist {
@@ -253,10 +622,115 @@ impl WithImages for Artist {
     }
 }
 
+#[derive(Deserialize, Debug, Clone)]
+pub struct Show {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub images: Vec<Image>,
+}
+
+impl WithImages for Show {
+    fn images(&self) -> &[Image] {
+        &self.images[..]
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct SavedShow {
+    #[serde(default, deserialize_with = "deserialize_timestamp")]
+    pub added_at: Option<SystemTime>,
+    pub show: Show,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct Episode {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub images: Vec<Image>,
+    pub duration_ms: i64,
+    pub release_date: Option<String>,
+}
+
+impl WithImages for Episode {
+    fn images(&self) -> &[Image] {
+        &self.images[..]
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct Devices {
+    pub devices: Vec<Device>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct Device {
+    // restricted devices may come without an id
+    pub id: Option<String>,
+    pub name: String,
+    #[serde(rename = "type")]
+    pub type_: String,
+    pub is_active: bool,
+    pub volume_percent: Option<u32>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct PlaybackState {
+    pub device: Device,
+    pub is_playing: bool,
+    pub progress_ms: Option<u32>,
+    pub shuffle_state: bool,
+    pub repeat_state: String,
+    // null while an ad or an unavailable item is playing
+    pub item: Option<TrackItem>,
+}
+
+// Reduced version of PlaybackState, without device or shuffle and repeat settings
+#[derive(Deserialize, Debug, Clone)]
+pub struct CurrentlyPlaying {
+    pub is_playing: bool,
+    pub progress_ms: Option<u32>,
+    pub item: Option<TrackItem>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct PlayerQueue {
+    pub currently_playing: Option<TrackItem>,
+    pub queue: Vec<TrackItem>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct RelatedArtists {
+    pub artists: Vec<Artist>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct FollowedArtists {
+    pub artists: CursorPage<Artist>,
+}
+
 #[derive(Deserialize, Debug, Clone)]
 pub struct User {
     pub id: String,
     pub display_name: String,
+    pub images: Option<Vec<Image>>,
+    // only available for the current user: "premium", "free" or "open"
+    pub product: Option<String>,
+    // only available for the current user
+    pub country: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_followers")]
+    pub followers: u64,
+}
+
+impl WithImages for User {
+    fn images(&self) -> &[Image] {
+        if let Some(ref images) = self.images {
+            images
+        } else {
+            &[]
+        }
+    }
 }
 
 #[derive(Deserialize, Debug, Clone)]
@@ -264,6 +738,49 @@ pub struct TopTracks {
     pub tracks: Vec<TrackItem>,
 }
 
+#[derive(Deserialize, Debug, Clone)]
+pub struct SeveralTracks {
+    // unknown ids come back as null
+    pub tracks: Vec<Option<TrackItem>>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct SeveralAlbums {
+    pub albums: Vec<Option<Album>>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct SeveralArtists {
+    pub artists: Vec<Option<Artist>>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct AudioFeatures {
+    pub id: String,
+    pub tempo: Option<f32>,
+    pub key: Option<i32>,
+    pub mode: Option<i32>,
+    pub time_signature: Option<i32>,
+    pub loudness: Option<f32>,
+    pub energy: Option<f32>,
+    pub danceability: Option<f32>,
+    pub valence: Option<f32>,
+    pub acousticness: Option<f32>,
+    pub instrumentalness: Option<f32>,
+    pub liveness: Option<f32>,
+    pub speechiness: Option<f32>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct Recommendations {
+    pub tracks: Vec<TrackItem>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct GenreSeeds {
+    pub genres: Vec<String>,
+}
+
 #[derive(Deserialize, Debug, Clone)]
 pub struct AlbumTrackItem {
     pub id: String,
@@ -272,6 +789,16 @@ pub struct AlbumTrackItem {
     pub name: String,
     pub duration_ms: i64,
     pub artists: Vec<Artist>,
+    #[serde(default)]
+    pub explicit: bool,
+    #[serde(default)]
+    pub popularity: u32,
+    // only set when a market is given, false for tracks restricted in that market
+    #[serde(default)]
+    pub is_playable: Option<bool>,
+    // 30 second mp3 snippet, playable without Premium
+    #[serde(default)]
+    pub preview_url: Option<String>,
 }
 
 #[derive(Deserialize, Debug, Clone)]
@@ -279,22 +806,95 @@ pub struct TrackItem {
     #[serde(flatten)]
     pub track: AlbumTrackItem,
     pub album: Album,
+    #[serde(
+        default,
+        rename = "external_urls",
+        deserialize_with = "deserialize_external_url"
+    )]
+    pub external_url: Option<String>,
 }
 
 #[derive(Deserialize, Debug, Clone)]
 pub struct BadTrackItem {}
 
+// Playlists can hold podcast episodes next to tracks
 #[derive(Deserialize, Debug, Clone)]
 #[serde(untagged)]
-pub enum FailibleTrackItem {
-    Ok(Box<TrackItem>),
+pub enum PlaylistItem {
+    Track(Box<TrackItem>),
+    Episode(Box<PlaylistEpisode>),
     Failing(BadTrackItem),
 }
 
-impl FailibleTrackItem {
+#[derive(Deserialize, Debug, Clone)]
+pub struct PlaylistEpisode {
+    pub id: String,
+    pub uri: String,
+    pub name: String,
+    pub duration_ms: i64,
+    #[serde(default)]
+    pub explicit: bool,
+    #[serde(default)]
+    pub images: Vec<Image>,
+    pub show: EpisodeShow,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct EpisodeShow {
+    pub id: String,
+    pub name: String,
+}
+
+impl PlaylistItem {
+    // Episodes are listed like tracks, their show's name standing in for both artist and album
     fn get(self) -> Option<TrackItem> {
         match self {
-            Self::Ok(track) => Some(*track),
+            Self::Track(track) => Some(*track),
+            Self::Episode(episode) => {
+                let PlaylistEpisode {
+                    id,
+                    uri,
+                    name,
+                    duration_ms,
+                    explicit,
+                    images,
+                    show,
+                } = *episode;
+                let show_as_artist = Artist {
+                    id: show.id.clone(),
+                    name: show.name.clone(),
+                    images: None,
+                    popularity: 0,
+                    followers: 0,
+                    external_url: None,
+                };
+                Some(TrackItem {
+                    track: AlbumTrackItem {
+                        id,
+                        track_number: None,
+                        uri,
+                        name,
+                        duration_ms,
+                        artists: vec![show_as_artist],
+                        explicit,
+                        popularity: 0,
+                        // the player only loads tracks for now
+                        is_playable: Some(false),
+                        preview_url: None,
+                    },
+                    album: Album {
+                        id: show.id,
+                        tracks: None,
+                        artists: vec![],
+                        release_date: None,
+                        release_date_precision: None,
+                        name: show.name,
+                        images,
+                        external_url: None,
+                    },
+                    external_url: None,
+                })
+            }
             Self::Failing(_) => None,
         }
     }
@@ -304,6 +904,44 @@ impl FailibleTrackItem {
 pub struct RawSearchResults {
     pub albums: Option<Page<Album>>,
     pub artists: Option<Page<Artist>>,
+    pub playlists: Option<Page<Playlist>>,
+    pub tracks: Option<Page<TrackItem>>,
+    pub shows: Option<Page<Show>>,
+    pub episodes: Option<Page<Episode>>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct NewReleases {
+    pub albums: Page<Album>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct FeaturedPlaylists {
+    pub message: Option<String>,
+    pub playlists: Page<Playlist>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct Category {
+    pub id: String,
+    pub name: String,
+    pub icons: Vec<Image>,
+}
+
+impl WithImages for Category {
+    fn images(&self) -> &[Image] {
+        &self.icons[..]
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct Categories {
+    pub categories: Page<Category>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct CategoryPlaylists {
+    pub playlists: Page<Playlist>,
 }
 
 impl From<Artist> for ArtistSummary {
@@ -317,7 +955,11 @@ impl From<Artist> for ArtistSummary {
 impl TryFrom<PlaylistTrack> for TrackItem {
     type Error = ();
 
-    fn try_from(PlaylistTrack { is_local, track }: PlaylistTrack) -> Result<Self, Self::Error> {
+    fn try_from(
+        PlaylistTrack {
+            is_local, track, ..
+        }: PlaylistTrack,
+    ) -> Result<Self, Self::Error> {
         track.ok_or(())?.get().filter(|_| !is_local).ok_or(())
     }
 }
@@ -328,12 +970,83 @@ impl From<SavedTrack> for TrackItem {
     }
 }
 
+impl From<PlayHistory> for TrackItem {
+    fn from(history: PlayHistory) -> Self {
+        history.track
+    }
+}
+
+impl TryFrom<TrackItem> for SongDescription {
+    type Error = ();
+
+    fn try_from(track: TrackItem) -> Result<Self, Self::Error> {
+        Vec::<SongDescription>::from(Page::new(vec![track]))
+            .pop()
+            .ok_or(())
+    }
+}
+
+impl From<SeveralTracks> for Vec<SongDescription> {
+    fn from(several_tracks: SeveralTracks) -> Self {
+        let tracks = several_tracks.tracks.into_iter().flatten().collect();
+        Page::new(tracks).into()
+    }
+}
+
+impl From<SeveralAlbums> for Vec<AlbumDescription> {
+    fn from(several_albums: SeveralAlbums) -> Self {
+        several_albums
+            .albums
+            .into_iter()
+            .flatten()
+            .map(|album| album.into())
+            .collect()
+    }
+}
+
+impl From<SeveralArtists> for Vec<ArtistSummary> {
+    fn from(several_artists: SeveralArtists) -> Self {
+        several_artists
+            .artists
+            .into_iter()
+            .flatten()
+            .map(|artist| artist.into())
+            .collect()
+    }
+}
+
+impl From<PlayerQueue> for PlayerQueueDescription {
+    fn from(queue: PlayerQueue) -> Self {
+        Self {
+            currently_playing: queue
+                .currently_playing
+                .and_then(|item| item.try_into().ok()),
+            queue: Page::new(queue.queue).into(),
+        }
+    }
+}
+
+impl From<Recommendations> for Vec<SongDescription> {
+    fn from(recommendations: Recommendations) -> Self {
+        Page::new(recommendations.tracks).into()
+    }
+}
+
 impl From<TopTracks> for Vec<SongDescription> {
     fn from(top_tracks: TopTracks) -> Self {
         Page::new(top_tracks.tracks).into()
     }
 }
 
+impl<T> From<CursorPage<T>> for Vec<SongDescription>
+where
+    T: TryInto<TrackItem>,
+{
+    fn from(page: CursorPage<T>) -> Self {
+        Page::new(page.items.unwrap_or_default()).into()
+    }
+}
+
 impl<T> From<Page<T>> for Vec<SongDescription>
 where
     T: TryInto<TrackItem>,
@@ -349,6 +1062,7 @@ impl From<(Page<AlbumTrackItem>, &Album)> for SongBatch {
         Self::from(page.map(|track| TrackItem {
             track,
             album: album.clone(),
+            external_url: None,
         }))
     }
 }
@@ -366,7 +1080,11 @@ where
         let songs = page
             .into_iter()
             .filter_map(|t| {
-                let TrackItem { track, album } = t.try_into().ok()?;
+                let TrackItem {
+                    track,
+                    album,
+                    external_url,
+                } = t.try_into().ok()?;
                 let AlbumTrackItem {
                     artists,
                     id,
@@ -374,6 +1092,10 @@ where
                     name,
                     duration_ms,
                     track_number,
+                    explicit,
+                    preview_url,
+                    is_playable,
+                    ..
                 } = track;
                 let artists = artists
                     .into_iter()
@@ -404,6 +1126,10 @@ where
                     album: album_ref,
                     duration: duration_ms as u32,
                     art,
+                    explicit,
+                    preview_url,
+                    is_playable: is_playable.unwrap_or(true),
+                    external_url,
                 })
             })
             .collect();
@@ -481,6 +1207,190 @@ impl From<AlbumInfo> for AlbumReleaseDetails {
     }
 }
 
+impl From<User> for CurrentUserDescription {
+    fn from(user: User) -> Self {
+        let avatar = user.best_image_for_width(200).map(|i| i.url.clone());
+        let User {
+            id,
+            display_name,
+            product,
+            ..
+        } = user;
+        Self {
+            id,
+            display_name,
+            avatar,
+            product,
+        }
+    }
+}
+
+impl From<FeaturedPlaylists> for FeaturedPlaylistsDescription {
+    fn from(featured: FeaturedPlaylists) -> Self {
+        let playlists = featured
+            .playlists
+            .into_iter()
+            .map(|playlist| playlist.into())
+            .collect();
+        Self {
+            message: featured.message,
+            playlists,
+        }
+    }
+}
+
+impl From<AudioFeatures> for AudioFeaturesDescription {
+    fn from(features: AudioFeatures) -> Self {
+        let AudioFeatures {
+            id,
+            tempo,
+            key,
+            mode,
+            time_signature,
+            loudness,
+            energy,
+            danceability,
+            valence,
+            acousticness,
+            instrumentalness,
+            liveness,
+            speechiness,
+        } = features;
+        Self {
+            id,
+            tempo,
+            key,
+            mode,
+            time_signature,
+            loudness,
+            energy,
+            danceability,
+            valence,
+            acousticness,
+            instrumentalness,
+            liveness,
+            speechiness,
+        }
+    }
+}
+
+impl From<Show> for ShowDescription {
+    fn from(show: Show) -> Self {
+        let art = show.best_image_for_width(200).map(|i| i.url.clone());
+        let Show {
+            id,
+            name,
+            description,
+            ..
+        } = show;
+        Self {
+            id,
+            title: name,
+            description,
+            art,
+        }
+    }
+}
+
+impl From<Episode> for EpisodeDescription {
+    fn from(episode: Episode) -> Self {
+        let art = episode.best_image_for_width(200).map(|i| i.url.clone());
+        let Episode {
+            id,
+            name,
+            description,
+            duration_ms,
+            release_date,
+            ..
+        } = episode;
+        Self {
+            id,
+            title: name,
+            description,
+            art,
+            duration: duration_ms as u32,
+            release_date,
+        }
+    }
+}
+
+impl TryFrom<Device> for ConnectDevice {
+    type Error = ();
+
+    fn try_from(device: Device) -> Result<Self, Self::Error> {
+        let Device {
+            id,
+            name,
+            type_,
+            is_active,
+            volume_percent,
+        } = device;
+        Ok(Self {
+            id: id.ok_or(())?,
+            label: name,
+            kind: type_,
+            is_active,
+            volume: volume_percent,
+        })
+    }
+}
+
+impl From<Devices> for Vec<ConnectDevice> {
+    fn from(devices: Devices) -> Self {
+        devices
+            .devices
+            .into_iter()
+            .filter_map(|device| device.try_into().ok())
+            .collect()
+    }
+}
+
+impl From<CurrentlyPlaying> for CurrentlyPlayingDescription {
+    fn from(currently_playing: CurrentlyPlaying) -> Self {
+        let CurrentlyPlaying {
+            is_playing,
+            progress_ms,
+            item,
+        } = currently_playing;
+        Self {
+            is_playing,
+            progress_ms,
+            song: item.and_then(|item| item.try_into().ok()),
+            stale_at: None,
+        }
+    }
+}
+
+impl From<PlaybackState> for ConnectPlaybackState {
+    fn from(state: PlaybackState) -> Self {
+        let PlaybackState {
+            device,
+            is_playing,
+            progress_ms,
+            shuffle_state,
+            repeat_state,
+            item,
+        } = state;
+        let repeat = repeat_state.parse().unwrap_or(RepeatMode::Off);
+        Self {
+            device: device.try_into().ok(),
+            is_playing,
+            progress_ms,
+            shuffle: shuffle_state.into(),
+            repeat,
+            song: item.and_then(|item| item.try_into().ok()),
+        }
+    }
+}
+
+impl From<Category> for CategoryDescription {
+    fn from(category: Category) -> Self {
+        let icon = category.best_image_for_width(200).map(|i| i.url.clone());
+        let Category { id, name, .. } = category;
+        Self { id, name, icon }
+    }
+}
+
 impl From<Playlist> for PlaylistDescription {
     fn from(playlist: Playlist) -> Self {
         let art = playlist.best_image_for_width(200).map(|i| i.url.clone());
@@ -530,6 +1440,27 @@ mod tests {
         assert!(track_item.is_none());
     }
 
+    #[test]
+    fn test_playlist_track_unplayable() {
+        let track = r#"{"is_local":false,"track":{"album":{"artists":[],"id":"","images":[],"name":""},"artists":[],"duration_ms":1,"id":"","name":"","uri":"","is_playable":false}}"#;
+        let deserialized: PlaylistTrack = serde_json::from_str(track).unwrap();
+        let track_item: TrackItem = deserialized.try_into().unwrap();
+        let song: SongDescription = track_item.try_into().unwrap();
+        assert!(!song.is_playable);
+    }
+
+    #[test]
+    fn test_playlist_episode() {
+        let track = r#"{"is_local":false,"track":{"id":"e","uri":"spotify:episode:e","name":"Episode","duration_ms":1,"images":[],"show":{"id":"s","name":"Show"}}}"#;
+        let deserialized: PlaylistTrack = serde_json::from_str(track).unwrap();
+        let track_item: TrackItem = deserialized.try_into().unwrap();
+        let song: SongDescription = track_item.try_into().unwrap();
+        assert_eq!(&song.uri, "spotify:episode:e");
+        assert_eq!(song.artists_name(), "Show");
+        assert_eq!(&song.album.name, "Show");
+        assert!(!song.is_playable);
+    }
+
     #[test]
     fn test_playlist_track_ok() {
         let track = r#"{"is_local":false,"track":{"album":{"artists":[{"external_urls":{"spotify":""},"href":"","id":"","name":"","type":"artist","uri":""}],"id":"","images":[{"height":64,"url":"","width":64}],"name":""},"artists":[{"id":"","name":""}],"duration_ms":1,"id":"","name":"","uri":""}}"#;
@@ -537,4 +1468,129 @@ mod tests {
         let track_item: Option<TrackItem> = deserialized.try_into().ok();
         assert!(track_item.is_some());
     }
+
+    #[test]
+    fn test_track_explicit_default() {
+        let track = r#"{"album":{"artists":[],"id":"","images":[],"name":""},"artists":[],"duration_ms":1,"id":"","name":"","uri":""}"#;
+        let deserialized: TrackItem = serde_json::from_str(track).unwrap();
+        assert!(!deserialized.track.explicit);
+        assert_eq!(deserialized.track.popularity, 0);
+
+        let track = r#"{"album":{"artists":[],"id":"","images":[],"name":""},"artists":[],"duration_ms":1,"id":"","name":"","uri":"","explicit":true,"popularity":42}"#;
+        let song: SongDescription = serde_json::from_str::<TrackItem>(track)
+            .unwrap()
+            .try_into()
+            .unwrap();
+        assert!(song.explicit);
+    }
+
+    #[test]
+    fn test_several_tracks_null() {
+        let tracks = r#"{"tracks":[null,{"album":{"artists":[],"id":"","images":[],"name":""},"artists":[],"duration_ms":1,"id":"a","name":"","uri":""}]}"#;
+        let deserialized: SeveralTracks = serde_json::from_str(tracks).unwrap();
+        let songs: Vec<SongDescription> = deserialized.into();
+        assert_eq!(songs.len(), 1);
+        assert_eq!(&songs[0].id, "a");
+    }
+
+    #[test]
+    fn test_several_artists_null() {
+        let artists = r#"{"artists":[{"id":"a","name":"","images":[]},null]}"#;
+        let deserialized: SeveralArtists = serde_json::from_str(artists).unwrap();
+        let artists: Vec<ArtistSummary> = deserialized.into();
+        assert_eq!(artists.len(), 1);
+        assert_eq!(&artists[0].id, "a");
+    }
+
+    #[test]
+    fn test_artist_followers() {
+        let artist = r#"{"id":"a","name":"","followers":{"href":null,"total":1234}}"#;
+        let deserialized: Artist = serde_json::from_str(artist).unwrap();
+        assert_eq!(deserialized.followers, 1234);
+
+        let artist = r#"{"id":"a","name":""}"#;
+        let deserialized: Artist = serde_json::from_str(artist).unwrap();
+        assert_eq!(deserialized.followers, 0);
+    }
+
+    #[test]
+    fn test_track_external_url() {
+        let track = r#"{"album":{"artists":[],"id":"","images":[],"name":""},"artists":[],"duration_ms":1,"id":"a","name":"","uri":"","external_urls":{"spotify":"https://open.spotify.com/track/a"}}"#;
+        let deserialized: TrackItem = serde_json::from_str(track).unwrap();
+        assert_eq!(
+            deserialized.external_url.as_deref(),
+            Some("https://open.spotify.com/track/a")
+        );
+        assert_eq!(deserialized.album.external_url, None);
+
+        let songs = Vec::<SongDescription>::from(Page::new(vec![deserialized]));
+        assert_eq!(
+            songs[0].external_url.as_deref(),
+            Some("https://open.spotify.com/track/a")
+        );
+    }
+
+    #[test]
+    fn test_playlist_track_added() {
+        let track = r#"{"added_at":"2016-10-24T15:03:07Z","added_by":{"id":"user","type":"user"},"is_local":false,"track":null}"#;
+        let deserialized: PlaylistTrack = serde_json::from_str(track).unwrap();
+        assert_eq!(
+            deserialized.added_at,
+            Some(SystemTime::UNIX_EPOCH + Duration::from_secs(1477321387))
+        );
+        assert_eq!(deserialized.added_by.unwrap().id, "user");
+    }
+
+    #[test]
+    fn test_saved_track_added_at() {
+        let track = r#"{"added_at":"2016-10-24T15:03:07Z","track":{"album":{"artists":[],"id":"","images":[],"name":""},"artists":[],"duration_ms":1,"id":"","name":"","uri":""}}"#;
+        let deserialized: SavedTrack = serde_json::from_str(track).unwrap();
+        assert_eq!(
+            deserialized.added_at,
+            Some(SystemTime::UNIX_EPOCH + Duration::from_secs(1477321387))
+        );
+    }
+
+    #[test]
+    fn test_followed_artists_cursor() {
+        let artists = r#"{"artists":{"items":[{"id":"a","name":"A","images":[]}],"next":"https://api.spotify.com/v1/me/following?type=artist&after=a&limit=1","cursors":{"after":"a"}}}"#;
+        let deserialized: FollowedArtists = serde_json::from_str(artists).unwrap();
+        let page = deserialized.artists;
+        assert_eq!(page.after(), Some("a"));
+        assert_eq!(page.into_iter().count(), 1);
+    }
+
+    #[test]
+    fn test_playback_state_repeat() {
+        let state = r#"{"device":{"id":"d","name":"Phone","type":"Smartphone","is_active":true,"volume_percent":60},"is_playing":true,"progress_ms":42,"shuffle_state":false,"repeat_state":"context","item":null}"#;
+        let deserialized: PlaybackState = serde_json::from_str(state).unwrap();
+        let state: ConnectPlaybackState = deserialized.into();
+        assert!(matches!(state.repeat, RepeatMode::Context));
+        assert_eq!(state.shuffle, ShuffleState::Off);
+        assert_eq!(state.device.map(|d| d.id), Some("d".to_string()));
+        assert!(state.song.is_none());
+    }
+
+    #[test]
+    fn test_currently_playing() {
+        let currently_playing = r#"{"is_playing":false,"progress_ms":1000,"currently_playing_type":"track","item":{"album":{"artists":[],"id":"al","images":[],"name":"Album"},"artists":[],"duration_ms":1,"id":"t","name":"Track","uri":"spotify:track:t"}}"#;
+        let deserialized: CurrentlyPlaying = serde_json::from_str(currently_playing).unwrap();
+        let description: CurrentlyPlayingDescription = deserialized.into();
+        assert!(!description.is_playing);
+        assert_eq!(description.progress_ms, Some(1000));
+        assert_eq!(description.song.map(|s| s.id), Some("t".to_string()));
+    }
+
+    #[test]
+    fn test_player_queue() {
+        let queue = r#"{"currently_playing":{"album":{"artists":[],"id":"al","images":[],"name":"Album"},"artists":[],"duration_ms":1,"id":"t","name":"Track","uri":"spotify:track:t"},"queue":[{"album":{"artists":[],"id":"al","images":[],"name":"Album"},"artists":[],"duration_ms":1,"id":"u","name":"Next","uri":"spotify:track:u"}]}"#;
+        let deserialized: PlayerQueue = serde_json::from_str(queue).unwrap();
+        let description: PlayerQueueDescription = deserialized.into();
+        assert_eq!(
+            description.currently_playing.map(|s| s.id),
+            Some("t".to_string())
+        );
+        assert_eq!(description.queue.len(), 1);
+        assert_eq!(description.queue[0].id, "u");
+    }
 }
@@ -1,32 +1,68 @@
 use async_std::fs;
 use async_std::io;
-use async_std::path::Path;
-use async_std::path::PathBuf;
+use async_std::path::{Path, PathBuf};
 use async_std::prelude::*;
+use async_std::sync::Mutex as AsyncMutex;
 use core::mem::size_of;
 use futures::join;
+use gio::prelude::*;
 use regex::Regex;
+use std::borrow::Cow;
+use std::collections::HashMap;
 use std::convert::From;
+use std::env;
 use std::future::Future;
+use std::io::Read;
+use std::sync::{Arc, Mutex};
 use std::time::{Duration, SystemTime};
 use thiserror::Error;
 
 const EXPIRY_FILE_EXT: &str = ".expiry";
+// Next to a hashed file, holds the name of the resource it was stored for
+const KEY_FILE_EXT: &str = ".key";
+// Resource names longer than this are hashed, filesystems commonly cap names at 255 bytes
+const MAX_FILE_NAME_LEN: usize = 128;
 
 #[derive(Error, Debug)]
 pub enum CacheError {
     #[error("No content available")]
     NoContent,
-    #[error("File could not be saved to cache: {0}")]
-    WriteError(std::io::Error),
-    #[error("File could not be read from cache: {0}")]
-    ReadError(std::io::Error),
-    #[error("File could not be removed from cache: {0}")]
-    RemoveError(std::io::Error),
+    #[error("{} could not be saved to cache: {source}", .path.display())]
+    Write { path: PathBuf, source: io::Error },
+    #[error("{} could not be read from cache: {source}", .path.display())]
+    Read { path: PathBuf, source: io::Error },
+    #[error("{} could not be removed from cache: {source}", .path.display())]
+    Remove { path: PathBuf, source: io::Error },
+    // e.g. left half written by an unclean shutdown
+    #[error("{} is corrupt", .path.display())]
+    Corrupt { path: PathBuf },
     #[error(transparent)]
     ConversionError(#[from] std::string::FromUtf8Error),
 }
 
+impl CacheError {
+    fn write(path: &Path) -> impl FnOnce(io::Error) -> Self + '_ {
+        move |source| Self::Write {
+            path: path.to_path_buf(),
+            source,
+        }
+    }
+
+    fn read(path: &Path) -> impl FnOnce(io::Error) -> Self + '_ {
+        move |source| Self::Read {
+            path: path.to_path_buf(),
+            source,
+        }
+    }
+
+    fn remove(path: &Path) -> impl FnOnce(io::Error) -> Self + '_ {
+        move |source| Self::Remove {
+            path: path.to_path_buf(),
+            source,
+        }
+    }
+}
+
 pub type ETag = String;
 
 pub enum CacheFile {
@@ -69,6 +105,14 @@ impl CacheExpiry {
         }
     }
 
+    // A 304 response may omit the ETag, in which case the one we already had remains valid
+    fn or_etag(self, fallback: Option<ETag>) -> Self {
+        match self {
+            Self::AtUnixTimestamp(duration, None) => Self::AtUnixTimestamp(duration, fallback),
+            expiry => expiry,
+        }
+    }
+
     fn etag(&self) -> Option<&String> {
         match self {
             Self::Never => None,
@@ -77,30 +121,270 @@ impl CacheExpiry {
     }
 }
 
+// Counters since startup, shared by all the managers of a directory
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CacheStats {
+    // served from cache without asking the network
+    pub hits: u64,
+    pub misses: u64,
+    // stale entries that had to be checked with the remote
+    pub revalidations: u64,
+    pub bytes_served: u64,
+}
+
+struct CacheEntry {
+    size: u64,
+    last_access: SystemTime,
+}
+
+// Disk usage of a cache directory, shared by all the managers of that directory
+#[derive(Default)]
+struct CacheUsage {
+    max_size: Option<u64>,
+    compression: bool,
+    stats: CacheStats,
+    total_size: u64,
+    // None until the directory has been scanned once
+    entries: Option<HashMap<String, CacheEntry>>,
+    // held while a resource and its expiry file are read, written or removed
+    locks: HashMap<String, Arc<AsyncMutex<()>>>,
+}
+
+impl CacheUsage {
+    // Locks nobody holds anymore are only referenced by the map, so they are dropped from it
+    fn lock_for_file(&mut self, file_name: &str) -> Arc<AsyncMutex<()>> {
+        self.locks.retain(|_, lock| Arc::strong_count(lock) > 1);
+        self.locks.entry(file_name.to_string()).or_default().clone()
+    }
+
+    fn remove(&mut self, resource: &str) {
+        if let Some(entry) = self.entries.as_mut().and_then(|e| e.remove(resource)) {
+            self.total_size -= entry.size;
+        }
+    }
+
+    // Records a write and returns the least recently used resources to evict to stay under budget
+    fn insert(&mut self, resource: &str, size: u64) -> Vec<String> {
+        self.remove(resource);
+        let entries = match self.entries.as_mut() {
+            Some(entries) => entries,
+            None => return vec![],
+        };
+        entries.insert(
+            resource.to_string(),
+            CacheEntry {
+                size,
+                last_access: SystemTime::now(),
+            },
+        );
+        self.total_size += size;
+
+        let max_size = match self.max_size {
+            Some(max_size) if self.total_size > max_size => max_size,
+            _ => return vec![],
+        };
+
+        let mut candidates = entries
+            .iter()
+            .filter(|(key, _)| *key != resource)
+            .map(|(key, entry)| (entry.last_access, key.clone()))
+            .collect::<Vec<(SystemTime, String)>>();
+        candidates.sort();
+
+        let mut evicted = vec![];
+        for (_, key) in candidates {
+            if self.total_size <= max_size {
+                break;
+            }
+            self.remove(&key);
+            evicted.push(key);
+        }
+        evicted
+    }
+
+    fn touch(&mut self, resource: &str) {
+        if let Some(entry) = self.entries.as_mut().and_then(|e| e.get_mut(resource)) {
+            entry.last_access = SystemTime::now();
+        }
+    }
+}
+
+// Every gzip stream starts with these, unlike the JSON and images we otherwise store
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+fn compress(content: &[u8]) -> Result<Vec<u8>, glib::Error> {
+    let output = gio::MemoryOutputStream::new_resizable();
+    let compressor = gio::ZlibCompressor::new(gio::ZlibCompressorFormat::Gzip, -1);
+    let stream = gio::ConverterOutputStream::new(&output, &compressor);
+    stream.write_all(content, gio::Cancellable::NONE)?;
+    stream.close(gio::Cancellable::NONE)?;
+    Ok(output.steal_as_bytes().to_vec())
+}
+
+// Entries written before compression was enabled are returned as they are
+fn decompress(content: Vec<u8>) -> io::Result<Vec<u8>> {
+    if !content.starts_with(&GZIP_MAGIC) {
+        return Ok(content);
+    }
+    let input = gio::MemoryInputStream::from_bytes(&glib::Bytes::from_owned(content));
+    let decompressor = gio::ZlibDecompressor::new(gio::ZlibCompressorFormat::Gzip);
+    let stream = gio::ConverterInputStream::new(&input, &decompressor);
+    let mut decompressed = vec![];
+    stream.into_read().read_to_end(&mut decompressed)?;
+    Ok(decompressed)
+}
+
+// Length of a hex encoded SHA-256
+const HASHED_FILE_NAME_LEN: usize = 64;
+
+fn is_sidecar(file_name: &str) -> bool {
+    file_name.ends_with(EXPIRY_FILE_EXT) || file_name.ends_with(KEY_FILE_EXT)
+}
+
+fn is_hashed(file_name: &str) -> bool {
+    file_name.len() == HASHED_FILE_NAME_LEN && file_name.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+// Short names made of safe characters are stored as is, so that they can still be matched by
+// patterns; anything else (user ids, long or odd characters) is stored under its SHA-256
+fn file_name(resource: &str) -> String {
+    let plain = resource.len() <= MAX_FILE_NAME_LEN
+        && !resource.starts_with('.')
+        && resource
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'));
+    if plain && !is_hashed(resource) {
+        return resource.to_string();
+    }
+    glib::compute_checksum_for_string(glib::ChecksumType::Sha256, resource)
+        .map(|hash| hash.to_string())
+        .unwrap_or_else(|| {
+            resource
+                .chars()
+                .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+                .take(MAX_FILE_NAME_LEN)
+                .collect()
+        })
+}
+
+lazy_static! {
+    static ref CACHE_USAGE: Mutex<HashMap<PathBuf, Arc<Mutex<CacheUsage>>>> =
+        Mutex::new(HashMap::new());
+}
+
 #[derive(Clone)]
 pub struct CacheManager {
     root: PathBuf,
+    usage: Arc<Mutex<CacheUsage>>,
+}
+
+// $SPOT_CACHE_DIR takes the place of the user cache directory, which follows $XDG_CACHE_HOME
+fn cache_root() -> PathBuf {
+    match env::var_os("SPOT_CACHE_DIR").filter(|dir| !dir.is_empty()) {
+        Some(dir) => PathBuf::from(dir),
+        None => glib::user_cache_dir().into(),
+    }
 }
 
 impl CacheManager {
     pub fn for_dir(dir: &str) -> Option<Self> {
-        let root: PathBuf = glib::user_cache_dir().into();
-        let root = root.join(dir);
+        Self::new_with_dir(cache_root().join(dir))
+    }
+
+    pub fn new_with_dir(root: impl Into<PathBuf>) -> Option<Self> {
+        let root = root.into();
         let mask = 0o744;
 
         glib::mkdir_with_parents(&root, mask);
 
-        Some(Self { root })
+        let usage = CACHE_USAGE
+            .lock()
+            .ok()?
+            .entry(root.clone())
+            .or_default()
+            .clone();
+
+        Some(Self { root, usage })
+    }
+
+    fn lock_for(&self, resource: &str) -> Arc<AsyncMutex<()>> {
+        self.lock_for_file(&file_name(resource))
+    }
+
+    fn lock_for_file(&self, file_name: &str) -> Arc<AsyncMutex<()>> {
+        self.usage
+            .lock()
+            .map(|mut usage| usage.lock_for_file(file_name))
+            .unwrap_or_default()
+    }
+
+    // Caps the size of the cached files, evicting the least recently used ones past that size
+    pub fn set_max_size(&self, bytes: u64) {
+        if let Ok(mut usage) = self.usage.lock() {
+            usage.max_size = Some(bytes);
+        }
+    }
+
+    // Gzips files as they get written, files are read back whether they are compressed or not
+    pub fn set_compression(&self, enabled: bool) {
+        if let Ok(mut usage) = self.usage.lock() {
+            usage.compression = enabled;
+        }
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        self.usage
+            .lock()
+            .map(|usage| usage.stats)
+            .unwrap_or_default()
+    }
+
+    fn record(&self, f: impl FnOnce(&mut CacheStats)) {
+        if let Ok(mut usage) = self.usage.lock() {
+            f(&mut usage.stats);
+        }
+    }
+
+    fn compression(&self) -> bool {
+        self.usage
+            .lock()
+            .map(|usage| usage.compression)
+            .unwrap_or(false)
     }
 
     fn cache_path(&self, resource: &str) -> PathBuf {
-        self.root.join(resource)
+        self.root.join(file_name(resource))
+    }
+
+    // Where a resource is stored, if it is, for others to read directly; as files are stored
+    // gzipped when compression is on, there's no usable path then
+    pub fn file_path(&self, resource: &str) -> Option<PathBuf> {
+        Some(self.cache_path(resource)).filter(|path| !self.compression() && path.is_file())
     }
 
     fn cache_meta_path(&self, resource: &str) -> PathBuf {
-        let full = resource.to_string() + EXPIRY_FILE_EXT;
+        let full = file_name(resource) + EXPIRY_FILE_EXT;
         self.root.join(&full)
     }
+
+    // Paths of the content, expiry and key files of a file as named on disk
+    fn file_paths(&self, file_name: &str) -> [PathBuf; 3] {
+        [
+            self.root.join(file_name),
+            self.root.join(file_name.to_string() + EXPIRY_FILE_EXT),
+            self.root.join(file_name.to_string() + KEY_FILE_EXT),
+        ]
+    }
+
+    // The resource a file on disk was stored for, looking up the key file of hashed ones
+    async fn resource_for_file(&self, file_name: &str) -> Option<String> {
+        if !is_hashed(file_name) {
+            return Some(file_name.to_string());
+        }
+        let key_file = self.root.join(file_name.to_string() + KEY_FILE_EXT);
+        let key = fs::read(&key_file).await.ok()?;
+        String::from_utf8(key).ok()
+    }
 }
 
 impl CacheManager {
@@ -109,10 +393,13 @@ impl CacheManager {
         match fs::read(&expiry_file).await {
             Err(e) => match e.kind() {
                 io::ErrorKind::NotFound => Ok(CacheExpiry::Never),
-                _ => Err(CacheError::ReadError(e)),
+                _ => Err(CacheError::read(&expiry_file)(e)),
             },
             Ok(buffer) => {
                 const OFFSET: usize = size_of::<u64>();
+                if buffer.len() < OFFSET {
+                    return Err(CacheError::Corrupt { path: expiry_file });
+                }
 
                 let mut duration: [u8; OFFSET] = Default::default();
                 duration.copy_from_slice(&buffer[..OFFSET]);
@@ -131,20 +418,67 @@ impl CacheManager {
         policy: CachePolicy,
     ) -> Result<CacheFile, CacheError> {
         if matches!(policy, CachePolicy::IgnoreCached) {
+            self.record(|stats| stats.misses += 1);
             return Ok(CacheFile::None);
         }
 
         let path = self.cache_path(resource);
-        let (file, expiry) = join!(fs::read(&path), self.read_expiry_file(resource));
+        let lock = self.lock_for(resource);
+        let (file, expiry) = {
+            let _guard = lock.lock().await;
+            join!(fs::read(&path), self.read_expiry_file(resource))
+        };
+
+        if file.is_ok() {
+            if let Ok(mut usage) = self.usage.lock() {
+                usage.touch(&file_name(resource));
+            }
+        }
 
-        match (file, policy) {
-            (Ok(buf), CachePolicy::IgnoreExpiry) => Ok(CacheFile::Fresh(buf, None)),
-            (Ok(buf), CachePolicy::Revalidate) => {
+        // an entry we can't make sense of is as good as no entry
+        let result = match Self::to_cache_file(&path, file, expiry, policy) {
+            Err(CacheError::Corrupt { path }) => {
+                warn!("Discarding corrupt cache file {}", path.display());
+                self.remove(resource).await?;
+                Ok(CacheFile::None)
+            }
+            result => result,
+        };
+
+        match result {
+            Ok(CacheFile::Fresh(ref buf, _)) => self.record(|stats| {
+                stats.hits += 1;
+                stats.bytes_served += buf.len() as u64;
+            }),
+            Ok(CacheFile::Expired(_, _)) => self.record(|stats| stats.revalidations += 1),
+            Ok(CacheFile::None) => self.record(|stats| stats.misses += 1),
+            Err(_) => {}
+        }
+        result
+    }
+
+    fn to_cache_file(
+        path: &Path,
+        file: io::Result<Vec<u8>>,
+        expiry: Result<CacheExpiry, CacheError>,
+        policy: CachePolicy,
+    ) -> Result<CacheFile, CacheError> {
+        let buf = match file {
+            Ok(buf) => decompress(buf).map_err(|_| CacheError::Corrupt {
+                path: path.to_path_buf(),
+            })?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(CacheFile::None),
+            Err(e) => return Err(CacheError::read(path)(e)),
+        };
+
+        match policy {
+            CachePolicy::IgnoreExpiry => Ok(CacheFile::Fresh(buf, None)),
+            CachePolicy::Revalidate => {
                 let expiry = expiry.unwrap_or(CacheExpiry::Never);
                 let etag = expiry.etag().cloned();
                 Ok(CacheFile::Expired(buf, etag))
             }
-            (Ok(buf), CachePolicy::Default) => {
+            CachePolicy::Default => {
                 let expiry = expiry?;
                 let etag = expiry.etag().cloned();
                 Ok(if expiry.is_expired() {
@@ -153,12 +487,26 @@ impl CacheManager {
                     CacheFile::Fresh(buf, etag)
                 })
             }
-            (_, CachePolicy::IgnoreCached) => Ok(CacheFile::None),
-            (Err(e), _) => match e.kind() {
-                io::ErrorKind::NotFound => Ok(CacheFile::None),
-                _ => Err(CacheError::ReadError(e)),
-            },
+            CachePolicy::IgnoreCached => Ok(CacheFile::None),
+        }
+    }
+
+    // Drops everything stored for a resource
+    pub async fn remove(&self, resource: &str) -> Result<(), CacheError> {
+        let name = file_name(resource);
+        let lock = self.lock_for_file(&name);
+        let _guard = lock.lock().await;
+        for path in self.file_paths(&name) {
+            match fs::remove_file(&path).await {
+                Ok(_) => {}
+                Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+                Err(e) => return Err(CacheError::remove(&path)(e)),
+            }
+        }
+        if let Ok(mut usage) = self.usage.lock() {
+            usage.remove(&name);
         }
+        Ok(())
     }
 }
 
@@ -175,7 +523,63 @@ impl CacheManager {
             }
             fs::write(path, content)
                 .await
-                .map_err(CacheError::WriteError)?;
+                .map_err(CacheError::write(path))?;
+        }
+        Ok(())
+    }
+
+    async fn scan_usage(&self) -> Result<(), CacheError> {
+        let mut entries = fs::read_dir(&self.root)
+            .await
+            .map_err(CacheError::read(&self.root))?;
+
+        let mut scanned = HashMap::new();
+        let mut total_size = 0;
+        while let Some(Ok(entry)) = entries.next().await {
+            let resource = match entry.file_name().to_str() {
+                Some(name) if !is_sidecar(name) => name.to_string(),
+                _ => continue,
+            };
+            if let Ok(metadata) = entry.metadata().await {
+                let size = metadata.len();
+                let last_access = metadata
+                    .accessed()
+                    .or_else(|_| metadata.modified())
+                    .unwrap_or(SystemTime::UNIX_EPOCH);
+                total_size += size;
+                scanned.insert(resource, CacheEntry { size, last_access });
+            }
+        }
+
+        if let Ok(mut usage) = self.usage.lock() {
+            if usage.entries.is_none() {
+                usage.entries = Some(scanned);
+                usage.total_size = total_size;
+            }
+        }
+        Ok(())
+    }
+
+    // Usage is tracked by file name, as found when scanning the directory
+    async fn track_write(&self, resource: &str, size: u64) -> Result<(), CacheError> {
+        let resource = &file_name(resource);
+        let needs_scan = match self.usage.lock() {
+            Ok(usage) => usage.max_size.is_some() && usage.entries.is_none(),
+            Err(_) => return Ok(()),
+        };
+        if needs_scan {
+            self.scan_usage().await?;
+        }
+
+        let evicted = match self.usage.lock() {
+            Ok(mut usage) => usage.insert(resource, size),
+            Err(_) => return Ok(()),
+        };
+        for file_name in evicted {
+            debug!("Evicting {} from cache", file_name);
+            for path in self.file_paths(&file_name) {
+                let _ = fs::remove_file(path).await;
+            }
         }
         Ok(())
     }
@@ -183,46 +587,101 @@ impl CacheManager {
     pub async fn clear_cache_pattern(&self, regex: &Regex) -> Result<(), CacheError> {
         let mut entries = fs::read_dir(&self.root)
             .await
-            .map_err(CacheError::ReadError)?;
+            .map_err(CacheError::read(&self.root))?;
 
         while let Some(Ok(entry)) = entries.next().await {
-            let matches = entry
-                .file_name()
-                .to_str()
-                .map(|s| regex.is_match(s))
+            let name = match entry.file_name().to_str() {
+                Some(name) if !is_sidecar(name) => name.to_string(),
+                _ => continue,
+            };
+            let matches = self
+                .resource_for_file(&name)
+                .await
+                .map(|resource| regex.is_match(&resource))
                 .unwrap_or(false);
             if matches {
-                info!("Removing {}...", entry.file_name().to_str().unwrap_or(""));
-                fs::remove_file(entry.path())
+                info!("Removing {}...", name);
+                let [file, expiry_file, key_file] = self.file_paths(&name);
+                fs::remove_file(&file)
                     .await
-                    .map_err(CacheError::RemoveError)?;
-                if let Some(expiry_file_path) = entry
-                    .path()
-                    .to_str()
-                    .map(|path| path.to_string() + EXPIRY_FILE_EXT)
-                {
-                    let _ = fs::remove_file(Path::new(&expiry_file_path)).await;
+                    .map_err(CacheError::remove(&file))?;
+                if let Ok(mut usage) = self.usage.lock() {
+                    usage.remove(&name);
                 }
+                let _ = fs::remove_file(expiry_file).await;
+                let _ = fs::remove_file(key_file).await;
             }
         }
 
         Ok(())
     }
 
+    // Removes every cached file, returning the number of bytes freed
+    pub async fn clear(&self) -> Result<u64, CacheError> {
+        let mut entries = fs::read_dir(&self.root)
+            .await
+            .map_err(CacheError::read(&self.root))?;
+
+        let mut freed = 0;
+        while let Some(Ok(entry)) = entries.next().await {
+            let name = match entry.file_name().to_str() {
+                Some(name) => name.to_string(),
+                None => continue,
+            };
+            let file_name = name
+                .strip_suffix(EXPIRY_FILE_EXT)
+                .or_else(|| name.strip_suffix(KEY_FILE_EXT))
+                .unwrap_or(&name);
+
+            let lock = self.lock_for_file(file_name);
+            let _guard = lock.lock().await;
+            for path in self.file_paths(file_name) {
+                let size = match fs::metadata(&path).await {
+                    Ok(metadata) => metadata.len(),
+                    Err(_) => continue,
+                };
+                match fs::remove_file(&path).await {
+                    Ok(_) => freed += size,
+                    Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+                    Err(e) => return Err(CacheError::remove(&path)(e)),
+                }
+            }
+        }
+
+        if let Ok(mut usage) = self.usage.lock() {
+            if usage.entries.is_some() {
+                usage.entries = Some(HashMap::new());
+            }
+            usage.total_size = 0;
+        }
+
+        info!("Cleared {} bytes from cache", freed);
+        Ok(freed)
+    }
+
     pub async fn set_expired_pattern(&self, regex: &Regex) -> Result<(), CacheError> {
         let mut entries = fs::read_dir(&self.root)
             .await
-            .map_err(CacheError::ReadError)?;
+            .map_err(CacheError::read(&self.root))?;
 
         while let Some(Ok(entry)) = entries.next().await {
-            let matches = entry
+            let file_name = entry
                 .file_name()
                 .to_str()
                 .and_then(|s| s.strip_suffix(EXPIRY_FILE_EXT))
-                .map(|s| regex.is_match(s))
-                .unwrap_or(false);
-            if matches {
-                self.set_expiry_for_path(&entry.path(), CacheExpiry::expire_in_seconds(0, None))
+                .map(|s| s.to_string());
+            let resource = match file_name {
+                Some(file_name) => self.resource_for_file(&file_name).await,
+                None => continue,
+            };
+            if let Some(resource) = resource.filter(|s| regex.is_match(s)) {
+                // keep the ETag so that the next request can still be answered with a 304
+                let etag = self
+                    .read_expiry_file(&resource)
+                    .await
+                    .ok()
+                    .and_then(|expiry| expiry.etag().cloned());
+                self.set_expiry_for_path(&entry.path(), CacheExpiry::expire_in_seconds(0, etag))
                     .await?;
             }
         }
@@ -237,14 +696,33 @@ impl CacheManager {
         expiry: CacheExpiry,
     ) -> Result<(), CacheError> {
         let file = self.cache_path(resource);
+        let content = if self.compression() {
+            let compressed = compress(content)
+                .map_err(|e| CacheError::write(&file)(io::Error::new(io::ErrorKind::Other, e)))?;
+            Cow::Owned(compressed)
+        } else {
+            Cow::Borrowed(content)
+        };
+
         let meta = self.cache_meta_path(resource);
-        let (r1, r2) = join!(
-            fs::write(&file, content),
-            self.set_expiry_for_path(&meta, expiry)
-        );
-        r1.map_err(CacheError::WriteError)?;
+        let lock = self.lock_for(resource);
+        let (r1, r2) = {
+            let _guard = lock.lock().await;
+            join!(
+                fs::write(&file, &content[..]),
+                self.set_expiry_for_path(&meta, expiry)
+            )
+        };
+        r1.map_err(CacheError::write(&file))?;
         r2?;
-        Ok(())
+        let name = file_name(resource);
+        if name != resource {
+            let [_, _, key_file] = self.file_paths(&name);
+            fs::write(&key_file, resource)
+                .await
+                .map_err(CacheError::write(&key_file))?;
+        }
+        self.track_write(resource, content.len() as u64).await
     }
 
     pub async fn get_or_write<O, F, E>(
@@ -261,16 +739,19 @@ impl CacheManager {
         let file = self.read_cache_file(resource, policy).await?;
         match file {
             CacheFile::Fresh(buf, _) => Ok(buf),
-            CacheFile::Expired(buf, etag) => match fetch(etag).await? {
+            CacheFile::Expired(buf, etag) => match fetch(etag.clone()).await? {
                 FetchResult::NotModified(expiry) => {
                     let meta = self.cache_meta_path(resource);
-                    self.set_expiry_for_path(&meta, expiry).await?;
+                    self.set_expiry_for_path(&meta, expiry.or_etag(etag))
+                        .await?;
+                    self.record(|stats| stats.bytes_served += buf.len() as u64);
                     Ok(buf)
                 }
                 FetchResult::Modified(fresh, expiry) => {
                     self.write_cache_file(resource, &fresh, expiry).await?;
                     Ok(fresh)
                 }
+                FetchResult::Uncacheable(fresh) => Ok(fresh),
             },
             CacheFile::None => match fetch(None).await? {
                 FetchResult::NotModified(_) => Err(E::from(CacheError::NoContent)),
@@ -278,6 +759,7 @@ impl CacheManager {
                     self.write_cache_file(resource, &fresh, expiry).await?;
                     Ok(fresh)
                 }
+                FetchResult::Uncacheable(fresh) => Ok(fresh),
             },
         }
     }
@@ -286,4 +768,48 @@ impl CacheManager {
 pub enum FetchResult {
     NotModified(CacheExpiry),
     Modified(Vec<u8>, CacheExpiry),
+    // must not be written to disk (no-store)
+    Uncacheable(Vec<u8>),
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_compression() {
+        let content = br#"{"items":[{"name":"a"},{"name":"a"},{"name":"a"}]}"#.to_vec();
+        let compressed = compress(&content).unwrap();
+        assert!(compressed.starts_with(&GZIP_MAGIC));
+        assert_eq!(decompress(compressed).unwrap(), content);
+        assert_eq!(decompress(content.clone()).unwrap(), content);
+    }
+
+    #[test]
+    fn test_file_name() {
+        assert_eq!(file_name("playlist_a_0_50.json"), "playlist_a_0_50.json");
+
+        let hashed = file_name("user_anna.lafuente❤.json");
+        assert!(is_hashed(&hashed));
+        assert_ne!(hashed, file_name("user_anna.lafuente.json"));
+
+        let long = "a".repeat(MAX_FILE_NAME_LEN + 1);
+        assert!(is_hashed(&file_name(&long)));
+    }
+
+    #[test]
+    fn test_unused_locks_are_dropped() {
+        let mut usage = CacheUsage::default();
+        let a = usage.lock_for_file("a.json");
+        assert!(Arc::ptr_eq(&a, &usage.lock_for_file("a.json")));
+
+        let _b = usage.lock_for_file("b.json");
+        assert_eq!(usage.locks.len(), 2);
+
+        drop(a);
+        usage.lock_for_file("b.json");
+        assert_eq!(usage.locks.len(), 1);
+        assert!(usage.locks.contains_key("b.json"));
+    }
 }
@@ -1,13 +1,25 @@
 use futures::future::BoxFuture;
+use futures::stream::{BoxStream, StreamExt, TryStreamExt};
 use futures::{join, FutureExt};
 use regex::Regex;
 use serde::de::DeserializeOwned;
 use serde_json::from_slice;
-use std::convert::Into;
+use std::collections::{HashMap, HashSet};
+use std::convert::{Into, TryInto};
 use std::future::Future;
+use std::sync::{Mutex, RwLock};
+use std::time::{Duration, Instant};
 
+use super::api_models::{
+    AlbumType, PlaybackTarget, PlaylistTrack, RecommendationParams, SearchType, TimeRange,
+    TrackItem,
+};
 use super::cache::{CacheExpiry, CacheManager, CachePolicy, FetchResult};
-use super::client::{SpotifyApiError, SpotifyClient, SpotifyResponse, SpotifyResponseKind};
+use super::client::{
+    paginate, OnTokenExpired, ResourceInfo, SpotifyApiError, SpotifyClient, SpotifyResponse,
+    SpotifyResponseKind, MAX_ALBUM_IDS_PER_REQUEST, MAX_IDS_PER_REQUEST, MAX_PLAYLISTS_PER_REQUEST,
+    MAX_PLAYLIST_FOLLOWERS_PER_REQUEST, MAX_PLAYLIST_TRACKS_PER_REQUEST,
+};
 use crate::app::models::*;
 
 pub type SpotifyResult<T> = Result<T, SpotifyApiError>;
@@ -15,6 +27,8 @@ pub type SpotifyResult<T> = Result<T, SpotifyApiError>;
 pub trait SpotifyApiClient {
     fn get_artist(&self, id: &str) -> BoxFuture<SpotifyResult<ArtistDescription>>;
 
+    fn get_related_artists(&self, id: &str) -> BoxFuture<SpotifyResult<Vec<ArtistSummary>>>;
+
     fn get_album(&self, id: &str) -> BoxFuture<SpotifyResult<AlbumFullDescription>>;
 
     fn get_album_tracks(
@@ -24,6 +38,37 @@ pub trait SpotifyApiClient {
         limit: usize,
     ) -> BoxFuture<SpotifyResult<SongBatch>>;
 
+    fn get_track(&self, id: &str) -> BoxFuture<SpotifyResult<SongDescription>>;
+
+    fn get_audio_features(&self, id: &str) -> BoxFuture<SpotifyResult<AudioFeaturesDescription>>;
+
+    fn get_several_tracks(
+        &self,
+        ids: Vec<String>,
+    ) -> BoxFuture<SpotifyResult<Vec<SongDescription>>>;
+
+    fn get_several_albums(
+        &self,
+        ids: Vec<String>,
+    ) -> BoxFuture<SpotifyResult<Vec<AlbumDescription>>>;
+
+    fn get_several_artists(&self, ids: Vec<String>)
+        -> BoxFuture<SpotifyResult<Vec<ArtistSummary>>>;
+
+    fn get_show(&self, id: &str) -> BoxFuture<SpotifyResult<ShowDescription>>;
+
+    fn get_episode(&self, id: &str) -> BoxFuture<SpotifyResult<EpisodeDescription>>;
+
+    fn get_saved_shows(
+        &self,
+        offset: usize,
+        limit: usize,
+    ) -> BoxFuture<SpotifyResult<Vec<ShowDescription>>>;
+
+    fn save_shows(&self, ids: Vec<String>) -> BoxFuture<SpotifyResult<()>>;
+
+    fn remove_saved_shows(&self, ids: Vec<String>) -> BoxFuture<SpotifyResult<()>>;
+
     fn get_playlist(&self, id: &str) -> BoxFuture<SpotifyResult<PlaylistDescription>>;
 
     fn get_playlist_tracks(
@@ -33,6 +78,9 @@ pub trait SpotifyApiClient {
         limit: usize,
     ) -> BoxFuture<SpotifyResult<SongBatch>>;
 
+    // Every track of a playlist, fetched a page at a time as the stream is polled
+    fn get_all_playlist_tracks(&self, id: &str) -> BoxStream<SpotifyResult<SongDescription>>;
+
     fn get_saved_albums(
         &self,
         offset: usize,
@@ -41,10 +89,34 @@ pub trait SpotifyApiClient {
 
     fn get_saved_tracks(&self, offset: usize, limit: usize) -> BoxFuture<SpotifyResult<SongBatch>>;
 
+    fn get_recently_played(
+        &self,
+        before: Option<String>,
+        limit: usize,
+    ) -> BoxFuture<SpotifyResult<Vec<SongDescription>>>;
+
+    fn get_top_tracks(
+        &self,
+        time_range: TimeRange,
+        offset: usize,
+        limit: usize,
+    ) -> BoxFuture<SpotifyResult<Vec<SongDescription>>>;
+
+    fn get_top_artists(
+        &self,
+        time_range: TimeRange,
+        offset: usize,
+        limit: usize,
+    ) -> BoxFuture<SpotifyResult<Vec<ArtistSummary>>>;
+
     fn save_album(&self, id: &str) -> BoxFuture<SpotifyResult<AlbumDescription>>;
 
     fn save_tracks(&self, ids: Vec<String>) -> BoxFuture<SpotifyResult<()>>;
 
+    fn are_tracks_saved(&self, ids: Vec<String>) -> BoxFuture<SpotifyResult<Vec<bool>>>;
+
+    fn are_albums_saved(&self, ids: Vec<String>) -> BoxFuture<SpotifyResult<Vec<bool>>>;
+
     fn remove_saved_album(&self, id: &str) -> BoxFuture<SpotifyResult<()>>;
 
     fn remove_saved_tracks(&self, ids: Vec<String>) -> BoxFuture<SpotifyResult<()>>;
@@ -55,13 +127,60 @@ pub trait SpotifyApiClient {
         limit: usize,
     ) -> BoxFuture<SpotifyResult<Vec<PlaylistDescription>>>;
 
-    fn add_to_playlist(&self, id: &str, uris: Vec<String>) -> BoxFuture<SpotifyResult<()>>;
+    fn get_all_saved_playlists(&self) -> BoxFuture<SpotifyResult<Vec<PlaylistDescription>>>;
+
+    fn create_playlist(
+        &self,
+        user_id: &str,
+        name: &str,
+        public: bool,
+    ) -> BoxFuture<SpotifyResult<PlaylistDescription>>;
+
+    fn follow_playlist(&self, id: &str, public: bool) -> BoxFuture<SpotifyResult<()>>;
+
+    fn unfollow_playlist(&self, id: &str) -> BoxFuture<SpotifyResult<()>>;
+
+    fn do_users_follow_playlist(
+        &self,
+        id: &str,
+        user_ids: Vec<String>,
+    ) -> BoxFuture<SpotifyResult<Vec<bool>>>;
+
+    // Both return the playlist's new snapshot id, or the last known one when uris is empty
+    fn add_to_playlist(
+        &self,
+        id: &str,
+        uris: Vec<String>,
+    ) -> BoxFuture<SpotifyResult<Option<String>>>;
+
+    fn remove_from_playlist(
+        &self,
+        id: &str,
+        uris: Vec<String>,
+    ) -> BoxFuture<SpotifyResult<Option<String>>>;
 
-    fn remove_from_playlist(&self, id: &str, uris: Vec<String>) -> BoxFuture<SpotifyResult<()>>;
+    fn reorder_playlist_tracks(
+        &self,
+        id: &str,
+        range_start: usize,
+        insert_before: usize,
+        range_length: usize,
+        snapshot_id: Option<String>,
+    ) -> BoxFuture<SpotifyResult<String>>;
+
+    fn update_playlist_details(
+        &self,
+        id: &str,
+        name: Option<String>,
+        description: Option<String>,
+        public: Option<bool>,
+    ) -> BoxFuture<SpotifyResult<()>>;
 
+    // Only the results of the given types are filled in
     fn search(
         &self,
         query: &str,
+        types: &[SearchType],
         offset: usize,
         limit: usize,
     ) -> BoxFuture<SpotifyResult<SearchResults>>;
@@ -69,10 +188,116 @@ pub trait SpotifyApiClient {
     fn get_artist_albums(
         &self,
         id: &str,
+        groups: &[AlbumType],
+        offset: usize,
+        limit: usize,
+    ) -> BoxFuture<SpotifyResult<Vec<AlbumDescription>>>;
+
+    fn follow_artists(&self, ids: Vec<String>) -> BoxFuture<SpotifyResult<()>>;
+
+    fn unfollow_artists(&self, ids: Vec<String>) -> BoxFuture<SpotifyResult<()>>;
+
+    fn are_artists_followed(&self, ids: Vec<String>) -> BoxFuture<SpotifyResult<Vec<bool>>>;
+
+    fn get_followed_artists(
+        &self,
+        after: Option<String>,
+        limit: usize,
+    ) -> BoxFuture<SpotifyResult<Vec<ArtistSummary>>>;
+
+    fn get_recommendations(
+        &self,
+        seed_artists: Vec<String>,
+        seed_tracks: Vec<String>,
+        seed_genres: Vec<String>,
+        limit: usize,
+        params: Option<RecommendationParams>,
+    ) -> BoxFuture<SpotifyResult<Vec<SongDescription>>>;
+
+    fn get_available_genre_seeds(&self) -> BoxFuture<SpotifyResult<Vec<String>>>;
+
+    fn get_new_releases(
+        &self,
         offset: usize,
         limit: usize,
     ) -> BoxFuture<SpotifyResult<Vec<AlbumDescription>>>;
 
+    fn get_featured_playlists(
+        &self,
+        offset: usize,
+        limit: usize,
+    ) -> BoxFuture<SpotifyResult<FeaturedPlaylistsDescription>>;
+
+    fn get_categories(
+        &self,
+        offset: usize,
+        limit: usize,
+    ) -> BoxFuture<SpotifyResult<Vec<CategoryDescription>>>;
+
+    fn get_category_playlists(
+        &self,
+        id: &str,
+        offset: usize,
+        limit: usize,
+    ) -> BoxFuture<SpotifyResult<Vec<PlaylistDescription>>>;
+
+    fn get_devices(&self) -> BoxFuture<SpotifyResult<Vec<ConnectDevice>>>;
+
+    fn get_playback_state(&self) -> BoxFuture<SpotifyResult<Option<ConnectPlaybackState>>>;
+
+    fn get_currently_playing(
+        &self,
+    ) -> BoxFuture<SpotifyResult<Option<CurrentlyPlayingDescription>>>;
+
+    fn get_queue(&self) -> BoxFuture<SpotifyResult<PlayerQueueDescription>>;
+
+    // Size and ETag of a resource (e.g. album art), to decide whether it's worth fetching again
+    fn get_resource_info(&self, uri: &str) -> BoxFuture<SpotifyResult<ResourceInfo>>;
+
+    fn add_to_queue(&self, uri: &str, device_id: Option<String>) -> BoxFuture<SpotifyResult<()>>;
+
+    fn transfer_playback(&self, device_id: &str, play: bool) -> BoxFuture<SpotifyResult<()>>;
+
+    fn remote_play(&self, device_id: Option<String>) -> BoxFuture<SpotifyResult<()>>;
+
+    fn play_context(
+        &self,
+        device_id: Option<String>,
+        target: PlaybackTarget,
+    ) -> BoxFuture<SpotifyResult<()>>;
+
+    fn remote_pause(&self, device_id: Option<String>) -> BoxFuture<SpotifyResult<()>>;
+
+    fn remote_next(&self, device_id: Option<String>) -> BoxFuture<SpotifyResult<()>>;
+
+    fn remote_previous(&self, device_id: Option<String>) -> BoxFuture<SpotifyResult<()>>;
+
+    fn set_volume(&self, percent: u8, device_id: Option<String>) -> BoxFuture<SpotifyResult<()>>;
+
+    fn set_repeat(
+        &self,
+        mode: RepeatMode,
+        device_id: Option<String>,
+    ) -> BoxFuture<SpotifyResult<()>>;
+
+    fn set_shuffle(
+        &self,
+        state: ShuffleState,
+        device_id: Option<String>,
+    ) -> BoxFuture<SpotifyResult<()>>;
+
+    fn seek(
+        &self,
+        position_ms: u32,
+        duration_ms: Option<u32>,
+        device_id: Option<String>,
+    ) -> BoxFuture<SpotifyResult<()>>;
+
+    fn get_current_user(&self) -> BoxFuture<SpotifyResult<CurrentUserDescription>>;
+
+    // Known once get_current_user has been called
+    fn is_premium(&self) -> Option<bool>;
+
     fn get_user(&self, id: &str) -> BoxFuture<SpotifyResult<UserDescription>>;
 
     fn get_user_playlists(
@@ -83,22 +308,52 @@ pub trait SpotifyApiClient {
     ) -> BoxFuture<SpotifyResult<Vec<PlaylistDescription>>>;
 
     fn update_token(&self, token: String);
+
+    fn update_token_with_expiry(&self, token: String, expires_in_secs: u64);
+
+    fn token_expires_in(&self) -> Option<Duration>;
+
+    fn set_on_token_expired(&self, on_token_expired: Option<OnTokenExpired>);
+
+    // Resolves with the next token given through update_token, None if there won't be one
+    fn next_token(&self) -> BoxFuture<'static, Option<String>>;
+
+    fn set_market(&self, market: Option<String>);
+
+    // What's kept in memory rather than in the cache directory
+    fn clear_memory_cache(&self);
 }
 
 enum SpotCacheKey<'a> {
     SavedAlbums(usize, usize),
     SavedTracks(usize, usize),
     SavedPlaylists(usize, usize),
-    Album(&'a str),
+    SavedShows(usize, usize),
+    TopTracks(TimeRange, usize, usize),
+    TopArtists(TimeRange, usize, usize),
+    // the language the description was fetched in, it is part of the cache key
+    Album(&'a str, &'a str),
+    Track(&'a str),
+    Show(&'a str, &'a str),
+    Episode(&'a str, &'a str),
+    AudioFeatures(&'a str),
     AlbumLiked(&'a str),
     AlbumTracks(&'a str, usize, usize),
     Playlist(&'a str),
     PlaylistTracks(&'a str, usize, usize),
-    ArtistAlbums(&'a str, usize, usize),
+    ArtistAlbums(&'a str, &'a str, usize, usize),
     Artist(&'a str),
+    ArtistFollowed(&'a str),
     ArtistTopTracks(&'a str),
+    ArtistRelated(&'a str),
+    CurrentUser,
     User(&'a str),
     UserPlaylists(&'a str, usize, usize),
+    NewReleases(usize, usize, &'a str),
+    FeaturedPlaylists(usize, usize, &'a str),
+    Categories(usize, usize, &'a str),
+    CategoryPlaylists(&'a str, usize, usize, &'a str),
+    GenreSeeds,
 }
 
 impl<'a> SpotCacheKey<'a> {
@@ -109,7 +364,24 @@ impl<'a> SpotCacheKey<'a> {
             Self::SavedPlaylists(offset, limit) => {
                 format!("me_playlists_{}_{}.json", offset, limit)
             }
-            Self::Album(id) => format!("album_{}.json", id),
+            Self::SavedShows(offset, limit) => format!("me_shows_{}_{}.json", offset, limit),
+            Self::TopTracks(time_range, offset, limit) => format!(
+                "me_top_tracks_{}_{}_{}.json",
+                time_range.into_string(),
+                offset,
+                limit
+            ),
+            Self::TopArtists(time_range, offset, limit) => format!(
+                "me_top_artists_{}_{}_{}.json",
+                time_range.into_string(),
+                offset,
+                limit
+            ),
+            Self::Album(id, lang) => format!("album_{}_{}.json", id, language_tag(lang)),
+            Self::Track(id) => format!("track_{}.json", id),
+            Self::Show(id, lang) => format!("show_{}_{}.json", id, language_tag(lang)),
+            Self::Episode(id, lang) => format!("episode_{}_{}.json", id, language_tag(lang)),
+            Self::AudioFeatures(id) => format!("audio_features_{}.json", id),
             Self::AlbumTracks(id, offset, limit) => {
                 format!("album_item_{}_{}_{}.json", id, offset, limit)
             }
@@ -118,15 +390,92 @@ impl<'a> SpotCacheKey<'a> {
             Self::PlaylistTracks(id, offset, limit) => {
                 format!("playlist_item_{}_{}_{}.json", id, offset, limit)
             }
-            Self::ArtistAlbums(id, offset, limit) => {
-                format!("artist_albums_{}_{}_{}.json", id, offset, limit)
+            Self::ArtistAlbums(id, groups, offset, limit) => {
+                format!("artist_albums_{}_{}_{}_{}.json", id, groups, offset, limit)
             }
             Self::Artist(id) => format!("artist_{}.json", id),
+            Self::ArtistFollowed(id) => format!("artist_followed_{}.json", id),
             Self::ArtistTopTracks(id) => format!("artist_top_tracks_{}.json", id),
+            Self::ArtistRelated(id) => format!("artist_related_{}.json", id),
+            Self::CurrentUser => "me.json".to_string(),
             Self::User(id) => format!("user_{}.json", id),
             Self::UserPlaylists(id, offset, limit) => {
                 format!("user_playlists_{}_{}_{}.json", id, offset, limit)
             }
+            Self::NewReleases(offset, limit, lang) => {
+                format!(
+                    "new_releases_{}_{}_{}.json",
+                    offset,
+                    limit,
+                    language_tag(lang)
+                )
+            }
+            Self::FeaturedPlaylists(offset, limit, lang) => format!(
+                "featured_playlists_{}_{}_{}.json",
+                offset,
+                limit,
+                language_tag(lang)
+            ),
+            Self::Categories(offset, limit, lang) => {
+                format!(
+                    "categories_{}_{}_{}.json",
+                    offset,
+                    limit,
+                    language_tag(lang)
+                )
+            }
+            Self::CategoryPlaylists(id, offset, limit, lang) => format!(
+                "category_playlists_{}_{}_{}_{}.json",
+                id,
+                offset,
+                limit,
+                language_tag(lang)
+            ),
+            Self::GenreSeeds => "genre_seeds.json".to_string(),
+        }
+    }
+}
+
+const MAX_RETRIES: u32 = 3;
+
+// Genre seeds hardly ever change, keep them at least this long (in seconds) unless told otherwise
+const GENRE_SEEDS_MIN_MAX_AGE: u64 = 7 * 24 * 60 * 60;
+
+// Artists fetched in bulk are reused for this long, the same ones come up over and over
+// when going through a compilation
+const ARTIST_SUMMARIES_TTL: Duration = Duration::from_secs(5 * 60);
+
+// Short-lived cache kept in memory, for items fetched in bulk that aren't worth a cache file each
+struct MemoryCache<V> {
+    ttl: Duration,
+    entries: Mutex<HashMap<String, (Instant, V)>>,
+}
+
+impl<V: Clone> MemoryCache<V> {
+    fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn get(&self, key: &str) -> Option<V> {
+        let entries = self.entries.lock().ok()?;
+        let (inserted_at, value) = entries.get(key)?;
+        Some(value.clone()).filter(|_| inserted_at.elapsed() < self.ttl)
+    }
+
+    fn insert(&self, key: String, value: V) {
+        if let Ok(mut entries) = self.entries.lock() {
+            let ttl = self.ttl;
+            entries.retain(|_, (inserted_at, _)| inserted_at.elapsed() < ttl);
+            entries.insert(key, (Instant::now(), value));
+        }
+    }
+
+    fn clear(&self) {
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.clear();
         }
     }
 }
@@ -134,8 +483,44 @@ impl<'a> SpotCacheKey<'a> {
 lazy_static! {
     pub static ref ME_TRACKS_CACHE: Regex = Regex::new(r"^me_tracks_\w+_\w+\.json$").unwrap();
     pub static ref ME_ALBUMS_CACHE: Regex = Regex::new(r"^me_albums_\w+_\w+\.json$").unwrap();
+    pub static ref ARTIST_FOLLOWED_CACHE: Regex =
+        Regex::new(r"^artist_followed_\w+\.json$").unwrap();
+    pub static ref ME_PLAYLISTS_CACHE: Regex =
+        Regex::new(r"^me_playlists_\w+_\w+\.json$").unwrap();
+    pub static ref ME_SHOWS_CACHE: Regex = Regex::new(r"^me_shows_\w+_\w+\.json$").unwrap();
     pub static ref USER_CACHE: Regex =
-        Regex::new(r"^me_(albums|playlists|tracks)_\w+_\w+\.json$").unwrap();
+        Regex::new(r"^me(_(albums|playlists|shows|tracks|top_(tracks|artists))_\w+_\w+)?\.json$")
+            .unwrap();
+
+    // Album, show and episode descriptions and browse categories are localized by Spotify,
+    // e.g. "pt-BR, pt"
+    static ref ACCEPT_LANGUAGE: String = {
+        let languages = glib::language_names()
+            .iter()
+            .filter(|name| !name.contains('.') && !name.contains('@'))
+            .filter(|name| name.as_str() != "C" && name.as_str() != "POSIX")
+            .map(|name| name.replace('_', "-"))
+            .collect::<Vec<String>>();
+        if languages.is_empty() {
+            "en".to_string()
+        } else {
+            languages.join(", ")
+        }
+    };
+}
+
+// Accept-Language values such as "pt-BR, pt" as they appear in cache file names, "pt-BR_pt"
+fn language_tag(language: &str) -> String {
+    language
+        .split(',')
+        .map(|lang| {
+            lang.chars()
+                .filter(|c| c.is_ascii_alphanumeric() || *c == '-')
+                .collect::<String>()
+        })
+        .filter(|lang| !lang.is_empty())
+        .collect::<Vec<String>>()
+        .join("_")
 }
 
 fn playlist_cache_key(id: &str) -> Regex {
@@ -145,16 +530,38 @@ fn playlist_cache_key(id: &str) -> Regex {
 pub struct CachedSpotifyClient {
     client: SpotifyClient,
     cache: CacheManager,
+    // set from the settings, takes precedence over the system languages
+    content_language: RwLock<Option<String>>,
+    artist_summaries: MemoryCache<ArtistSummary>,
 }
 
 impl CachedSpotifyClient {
     pub fn new() -> CachedSpotifyClient {
+        let cache = CacheManager::for_dir("spot/net").unwrap();
+        // responses are JSON, which shrinks a lot
+        cache.set_compression(true);
         CachedSpotifyClient {
-            client: SpotifyClient::new(),
-            cache: CacheManager::for_dir("spot/net").unwrap(),
+            client: SpotifyClient::new().with_max_retries(MAX_RETRIES),
+            cache,
+            content_language: RwLock::new(None),
+            artist_summaries: MemoryCache::new(ARTIST_SUMMARIES_TTL),
+        }
+    }
+
+    pub fn set_content_language(&self, language: Option<String>) {
+        if let Ok(mut content_language) = self.content_language.write() {
+            *content_language = language.filter(|l| !l.is_empty());
         }
     }
 
+    fn accept_language(&self) -> String {
+        self.content_language
+            .read()
+            .ok()
+            .and_then(|language| language.clone())
+            .unwrap_or_else(|| ACCEPT_LANGUAGE.clone())
+    }
+
     fn default_cache_policy(&self) -> CachePolicy {
         if self.client.has_token() {
             CachePolicy::Default
@@ -163,6 +570,28 @@ impl CachedSpotifyClient {
         }
     }
 
+    // The API refuses edits made against an outdated snapshot, meaning the playlist was changed
+    // elsewhere (e.g. from another device) since we last loaded it
+    fn snapshot_error(
+        &self,
+        id: &str,
+        sent_snapshot: bool,
+        err: SpotifyApiError,
+    ) -> SpotifyApiError {
+        let stale = sent_snapshot
+            && match &err {
+                SpotifyApiError::BadStatus(409 | 412, _, _) => true,
+                SpotifyApiError::BadStatus(400, body, _) => body.contains("snapshot"),
+                _ => false,
+            };
+        if stale {
+            self.client.set_playlist_snapshot(id, None);
+            SpotifyApiError::StaleSnapshot
+        } else {
+            err
+        }
+    }
+
     async fn wrap_write<T, O, F>(write: &F, etag: Option<String>) -> SpotifyResult<FetchResult>
     where
         O: Future<Output = SpotifyResult<SpotifyResponse<T>>>,
@@ -172,11 +601,15 @@ impl CachedSpotifyClient {
             .map(|r| {
                 let SpotifyResponse {
                     kind,
-                    max_age,
+                    cache_control,
                     etag,
+                    ..
                 } = r?;
-                let expiry = CacheExpiry::expire_in_seconds(u64::max(max_age, 10), etag);
+                let expiry = CacheExpiry::expire_in_seconds(cache_control.expires_in(), etag);
                 SpotifyResult::Ok(match kind {
+                    SpotifyResponseKind::Ok(content, _) if cache_control.no_store => {
+                        FetchResult::Uncacheable(content.into_bytes())
+                    }
                     SpotifyResponseKind::Ok(content, _) => {
                         FetchResult::Modified(content.into_bytes(), expiry)
                     }
@@ -211,9 +644,10 @@ impl CachedSpotifyClient {
         let result = from_slice::<T>(&raw);
         match result {
             Ok(t) => Ok(t),
-            // parsing failed: cache is likely invalid, request again, ignoring cache
+            // parsing failed: cache is likely invalid, drop it and request again
             Err(e) => {
-                dbg!(&cache_key, e);
+                warn!("Discarding unreadable cache entry {}: {}", &cache_key, e);
+                self.cache.remove(&cache_key).await.unwrap_or(());
                 let new_raw = self
                     .cache
                     .get_or_write(&cache_key, CachePolicy::IgnoreCached, |etag| {
@@ -231,6 +665,32 @@ impl SpotifyApiClient for CachedSpotifyClient {
         self.client.update_token(new_token)
     }
 
+    fn update_token_with_expiry(&self, new_token: String, expires_in_secs: u64) {
+        self.client
+            .update_token_with_expiry(new_token, expires_in_secs)
+    }
+
+    fn token_expires_in(&self) -> Option<Duration> {
+        self.client.token_expires_in()
+    }
+
+    fn set_on_token_expired(&self, on_token_expired: Option<OnTokenExpired>) {
+        self.client.set_on_token_expired(on_token_expired)
+    }
+
+    fn next_token(&self) -> BoxFuture<'static, Option<String>> {
+        let next_token = self.client.next_token();
+        Box::pin(async move { next_token.await.ok() })
+    }
+
+    fn set_market(&self, market: Option<String>) {
+        self.client.set_market(market)
+    }
+
+    fn clear_memory_cache(&self) {
+        self.artist_summaries.clear();
+    }
+
     fn get_saved_albums(
         &self,
         offset: usize,
@@ -270,6 +730,76 @@ impl SpotifyApiClient for CachedSpotifyClient {
         })
     }
 
+    fn get_recently_played(
+        &self,
+        before: Option<String>,
+        limit: usize,
+    ) -> BoxFuture<SpotifyResult<Vec<SongDescription>>> {
+        Box::pin(async move {
+            let page = self
+                .client
+                .get_recently_played(before.as_deref(), limit)
+                .send()
+                .await?
+                .deserialize_result()?;
+
+            Ok(page.into())
+        })
+    }
+
+    fn get_top_tracks(
+        &self,
+        time_range: TimeRange,
+        offset: usize,
+        limit: usize,
+    ) -> BoxFuture<SpotifyResult<Vec<SongDescription>>> {
+        Box::pin(async move {
+            let page = self
+                .cache_get_or_write(
+                    SpotCacheKey::TopTracks(time_range, offset, limit),
+                    None,
+                    |etag| {
+                        self.client
+                            .get_top_tracks(time_range, offset, limit)
+                            .etag(etag)
+                            .send()
+                    },
+                )
+                .await?;
+
+            Ok(page.into())
+        })
+    }
+
+    fn get_top_artists(
+        &self,
+        time_range: TimeRange,
+        offset: usize,
+        limit: usize,
+    ) -> BoxFuture<SpotifyResult<Vec<ArtistSummary>>> {
+        Box::pin(async move {
+            let page = self
+                .cache_get_or_write(
+                    SpotCacheKey::TopArtists(time_range, offset, limit),
+                    None,
+                    |etag| {
+                        self.client
+                            .get_top_artists(time_range, offset, limit)
+                            .etag(etag)
+                            .send()
+                    },
+                )
+                .await?;
+
+            let artists = page
+                .into_iter()
+                .map(|artist| artist.into())
+                .collect::<Vec<ArtistSummary>>();
+
+            Ok(artists)
+        })
+    }
+
     fn get_saved_playlists(
         &self,
         offset: usize,
@@ -294,56 +824,235 @@ impl SpotifyApiClient for CachedSpotifyClient {
         })
     }
 
-    fn add_to_playlist(&self, id: &str, uris: Vec<String>) -> BoxFuture<SpotifyResult<()>> {
-        let id = id.to_owned();
-
+    fn get_all_saved_playlists(&self) -> BoxFuture<SpotifyResult<Vec<PlaylistDescription>>> {
         Box::pin(async move {
-            self.cache
-                .set_expired_pattern(&playlist_cache_key(&id))
-                .await
-                .unwrap_or(());
+            paginate(0, MAX_PLAYLISTS_PER_REQUEST, |offset, limit| {
+                self.cache_get_or_write(
+                    SpotCacheKey::SavedPlaylists(offset, limit),
+                    None,
+                    move |etag| {
+                        self.client
+                            .get_saved_playlists(offset, limit)
+                            .etag(etag)
+                            .send()
+                    },
+                )
+            })
+            .map_ok(|playlist| playlist.into())
+            .try_collect()
+            .await
+        })
+    }
 
-            self.client
-                .add_to_playlist(&id, uris)
-                .send_no_response()
-                .await?;
-            Ok(())
+    fn create_playlist(
+        &self,
+        user_id: &str,
+        name: &str,
+        public: bool,
+    ) -> BoxFuture<SpotifyResult<PlaylistDescription>> {
+        let user_id = user_id.to_owned();
+        let name = name.to_owned();
+
+        Box::pin(async move {
+            let _ = self.cache.set_expired_pattern(&*ME_PLAYLISTS_CACHE).await;
+            let playlist = self
+                .client
+                .create_playlist(&user_id, &name, public)
+                .send()
+                .await?
+                .deserialize_result()?;
+            Ok(playlist.into())
         })
     }
 
-    fn remove_from_playlist(&self, id: &str, uris: Vec<String>) -> BoxFuture<SpotifyResult<()>> {
+    fn follow_playlist(&self, id: &str, public: bool) -> BoxFuture<SpotifyResult<()>> {
         let id = id.to_owned();
 
         Box::pin(async move {
-            self.cache
-                .set_expired_pattern(&playlist_cache_key(&id))
-                .await
-                .unwrap_or(());
-
+            let _ = self.cache.set_expired_pattern(&*ME_PLAYLISTS_CACHE).await;
             self.client
-                .remove_from_playlist(&id, uris)
+                .follow_playlist(&id, public)
                 .send_no_response()
-                .await?;
-            Ok(())
+                .await
         })
     }
 
-    fn get_album(&self, id: &str) -> BoxFuture<SpotifyResult<AlbumFullDescription>> {
+    fn unfollow_playlist(&self, id: &str) -> BoxFuture<SpotifyResult<()>> {
         let id = id.to_owned();
 
         Box::pin(async move {
-            let album = self.cache_get_or_write(SpotCacheKey::Album(&id), None, |etag| {
-                self.client.get_album(&id).etag(etag).send()
-            });
+            let _ = self.cache.set_expired_pattern(&*ME_PLAYLISTS_CACHE).await;
+            self.client.unfollow_playlist(&id).send_no_response().await
+        })
+    }
 
-            let liked = self.cache_get_or_write(
-                SpotCacheKey::AlbumLiked(&id),
-                Some(if self.client.has_token() {
-                    CachePolicy::Revalidate
-                } else {
-                    CachePolicy::IgnoreExpiry
+    fn do_users_follow_playlist(
+        &self,
+        id: &str,
+        user_ids: Vec<String>,
+    ) -> BoxFuture<SpotifyResult<Vec<bool>>> {
+        let id = id.to_owned();
+
+        Box::pin(async move {
+            let mut following = Vec::with_capacity(user_ids.len());
+            for chunk in user_ids.chunks(MAX_PLAYLIST_FOLLOWERS_PER_REQUEST) {
+                let chunk: Vec<&str> = chunk.iter().map(|id| &id[..]).collect();
+                let mut chunk_following = self
+                    .client
+                    .do_users_follow_playlist(&id, &chunk)
+                    .send()
+                    .await?
+                    .deserialize_result()?;
+                following.append(&mut chunk_following);
+            }
+            Ok(following)
+        })
+    }
+
+    fn add_to_playlist(
+        &self,
+        id: &str,
+        uris: Vec<String>,
+    ) -> BoxFuture<SpotifyResult<Option<String>>> {
+        let id = id.to_owned();
+
+        Box::pin(async move {
+            if uris.is_empty() {
+                return Ok(self.client.playlist_snapshot(&id));
+            }
+
+            self.cache
+                .set_expired_pattern(&playlist_cache_key(&id))
+                .await
+                .unwrap_or(());
+
+            let mut snapshot_id = None;
+            for chunk in uris.chunks(MAX_PLAYLIST_TRACKS_PER_REQUEST) {
+                let snapshot = self
+                    .client
+                    .add_to_playlist(&id, chunk.to_vec())
+                    .send()
+                    .await?
+                    .deserialize_result()?;
+                self.client
+                    .set_playlist_snapshot(&id, Some(snapshot.snapshot_id.clone()));
+                snapshot_id = Some(snapshot.snapshot_id);
+            }
+            Ok(snapshot_id)
+        })
+    }
+
+    fn remove_from_playlist(
+        &self,
+        id: &str,
+        uris: Vec<String>,
+    ) -> BoxFuture<SpotifyResult<Option<String>>> {
+        let id = id.to_owned();
+
+        Box::pin(async move {
+            if uris.is_empty() {
+                return Ok(self.client.playlist_snapshot(&id));
+            }
+
+            self.cache
+                .set_expired_pattern(&playlist_cache_key(&id))
+                .await
+                .unwrap_or(());
+
+            let mut snapshot_id = self.client.playlist_snapshot(&id);
+            for chunk in uris.chunks(MAX_PLAYLIST_TRACKS_PER_REQUEST) {
+                let sent_snapshot = snapshot_id.is_some();
+                let snapshot = self
+                    .client
+                    .remove_from_playlist(&id, chunk.to_vec(), snapshot_id)
+                    .send()
+                    .await
+                    .map_err(|e| self.snapshot_error(&id, sent_snapshot, e))?
+                    .deserialize_result()?;
+                self.client
+                    .set_playlist_snapshot(&id, Some(snapshot.snapshot_id.clone()));
+                snapshot_id = Some(snapshot.snapshot_id);
+            }
+            Ok(snapshot_id)
+        })
+    }
+
+    fn reorder_playlist_tracks(
+        &self,
+        id: &str,
+        range_start: usize,
+        insert_before: usize,
+        range_length: usize,
+        snapshot_id: Option<String>,
+    ) -> BoxFuture<SpotifyResult<String>> {
+        let id = id.to_owned();
+
+        Box::pin(async move {
+            self.cache
+                .set_expired_pattern(&playlist_cache_key(&id))
+                .await
+                .unwrap_or(());
+
+            let snapshot_id = snapshot_id.or_else(|| self.client.playlist_snapshot(&id));
+            let sent_snapshot = snapshot_id.is_some();
+            let snapshot = self
+                .client
+                .reorder_playlist_tracks(&id, range_start, insert_before, range_length, snapshot_id)
+                .send()
+                .await
+                .map_err(|e| self.snapshot_error(&id, sent_snapshot, e))?
+                .deserialize_result()?;
+            self.client
+                .set_playlist_snapshot(&id, Some(snapshot.snapshot_id.clone()));
+            Ok(snapshot.snapshot_id)
+        })
+    }
+
+    fn update_playlist_details(
+        &self,
+        id: &str,
+        name: Option<String>,
+        description: Option<String>,
+        public: Option<bool>,
+    ) -> BoxFuture<SpotifyResult<()>> {
+        let id = id.to_owned();
+
+        Box::pin(async move {
+            self.cache
+                .set_expired_pattern(&playlist_cache_key(&id))
+                .await
+                .unwrap_or(());
+            let _ = self.cache.set_expired_pattern(&*ME_PLAYLISTS_CACHE).await;
+
+            self.client
+                .update_playlist_details(&id, name.as_deref(), description.as_deref(), public)
+                .send_no_response()
+                .await
+        })
+    }
+
+    fn get_album(&self, id: &str) -> BoxFuture<SpotifyResult<AlbumFullDescription>> {
+        let id = id.to_owned();
+
+        Box::pin(async move {
+            let language = self.accept_language();
+            let album =
+                self.cache_get_or_write(SpotCacheKey::Album(&id, &language), None, |etag| {
+                    self.client
+                        .get_album(&id)
+                        .header("Accept-Language", &language)
+                        .etag(etag)
+                        .send()
+                });
+
+            let liked = self.cache_get_or_write(
+                SpotCacheKey::AlbumLiked(&id),
+                Some(if self.client.has_token() {
+                    CachePolicy::Revalidate
+                } else {
+                    CachePolicy::IgnoreExpiry
                 }),
-                |etag| self.client.is_album_saved(&id).etag(etag).send(),
+                |etag| self.client.are_albums_saved(&[&id]).etag(etag).send(),
             );
 
             let (album, liked) = join!(album, liked);
@@ -368,11 +1077,49 @@ impl SpotifyApiClient for CachedSpotifyClient {
     fn save_tracks(&self, ids: Vec<String>) -> BoxFuture<SpotifyResult<()>> {
         Box::pin(async move {
             let _ = self.cache.set_expired_pattern(&*ME_TRACKS_CACHE).await;
-            self.client.save_tracks(ids).send_no_response().await?;
+            for chunk in ids.chunks(MAX_IDS_PER_REQUEST) {
+                let chunk: Vec<&str> = chunk.iter().map(|id| &id[..]).collect();
+                self.client.save_tracks(&chunk).send_no_response().await?;
+            }
             Ok(())
         })
     }
 
+    fn are_tracks_saved(&self, ids: Vec<String>) -> BoxFuture<SpotifyResult<Vec<bool>>> {
+        Box::pin(async move {
+            let mut saved = Vec::with_capacity(ids.len());
+            // an empty slice never reaches the network, since the loop has nothing to iterate on
+            for chunk in ids.chunks(MAX_IDS_PER_REQUEST) {
+                let chunk: Vec<&str> = chunk.iter().map(|id| &id[..]).collect();
+                let mut chunk_saved = self
+                    .client
+                    .are_tracks_saved(&chunk)
+                    .send()
+                    .await?
+                    .deserialize_result()?;
+                saved.append(&mut chunk_saved);
+            }
+            Ok(saved)
+        })
+    }
+
+    fn are_albums_saved(&self, ids: Vec<String>) -> BoxFuture<SpotifyResult<Vec<bool>>> {
+        Box::pin(async move {
+            let mut saved = Vec::with_capacity(ids.len());
+            for chunk in ids.chunks(MAX_ALBUM_IDS_PER_REQUEST) {
+                let chunk: Vec<&str> = chunk.iter().map(|id| &id[..]).collect();
+                let mut chunk_saved = self
+                    .client
+                    .are_albums_saved(&chunk)
+                    .send()
+                    .await?
+                    .deserialize_result()?;
+                saved.append(&mut chunk_saved);
+            }
+            Ok(saved)
+        })
+    }
+
     fn remove_saved_album(&self, id: &str) -> BoxFuture<SpotifyResult<()>> {
         let id = id.to_owned();
 
@@ -385,10 +1132,14 @@ impl SpotifyApiClient for CachedSpotifyClient {
     fn remove_saved_tracks(&self, ids: Vec<String>) -> BoxFuture<SpotifyResult<()>> {
         Box::pin(async move {
             let _ = self.cache.set_expired_pattern(&*ME_TRACKS_CACHE).await;
-            self.client
-                .remove_saved_tracks(ids)
-                .send_no_response()
-                .await
+            for chunk in ids.chunks(MAX_IDS_PER_REQUEST) {
+                let chunk: Vec<&str> = chunk.iter().map(|id| &id[..]).collect();
+                self.client
+                    .remove_saved_tracks(&chunk)
+                    .send_no_response()
+                    .await?;
+            }
+            Ok(())
         })
     }
 
@@ -401,10 +1152,17 @@ impl SpotifyApiClient for CachedSpotifyClient {
         let id = id.to_owned();
 
         Box::pin(async move {
+            let language = self.accept_language();
             let album = self.cache_get_or_write(
-                SpotCacheKey::Album(&id),
+                SpotCacheKey::Album(&id, &language),
                 Some(CachePolicy::IgnoreExpiry),
-                |etag| self.client.get_album(&id).etag(etag).send(),
+                |etag| {
+                    self.client
+                        .get_album(&id)
+                        .header("Accept-Language", &language)
+                        .etag(etag)
+                        .send()
+                },
             );
 
             let songs = self.cache_get_or_write(
@@ -423,6 +1181,197 @@ impl SpotifyApiClient for CachedSpotifyClient {
         })
     }
 
+    fn get_track(&self, id: &str) -> BoxFuture<SpotifyResult<SongDescription>> {
+        let id = id.to_owned();
+
+        Box::pin(async move {
+            let track = self
+                .cache_get_or_write(SpotCacheKey::Track(&id), None, |etag| {
+                    self.client.get_track(&id).etag(etag).send()
+                })
+                .await?;
+
+            track.try_into().map_err(|_| SpotifyApiError::NoContent)
+        })
+    }
+
+    fn get_audio_features(&self, id: &str) -> BoxFuture<SpotifyResult<AudioFeaturesDescription>> {
+        let id = id.to_owned();
+
+        Box::pin(async move {
+            let features = self
+                .cache_get_or_write(SpotCacheKey::AudioFeatures(&id), None, |etag| {
+                    self.client.get_audio_features(&id).etag(etag).send()
+                })
+                .await?;
+
+            Ok(features.into())
+        })
+    }
+
+    fn get_several_tracks(
+        &self,
+        ids: Vec<String>,
+    ) -> BoxFuture<SpotifyResult<Vec<SongDescription>>> {
+        Box::pin(async move {
+            let mut songs = Vec::with_capacity(ids.len());
+            for chunk in ids.chunks(MAX_IDS_PER_REQUEST) {
+                let chunk: Vec<&str> = chunk.iter().map(|id| &id[..]).collect();
+                let tracks = self
+                    .client
+                    .get_several_tracks(&chunk)
+                    .send()
+                    .await?
+                    .deserialize_result()?;
+                songs.append(&mut tracks.into());
+            }
+            Ok(songs)
+        })
+    }
+
+    fn get_several_albums(
+        &self,
+        ids: Vec<String>,
+    ) -> BoxFuture<SpotifyResult<Vec<AlbumDescription>>> {
+        Box::pin(async move {
+            let mut albums = Vec::with_capacity(ids.len());
+            for chunk in ids.chunks(MAX_ALBUM_IDS_PER_REQUEST) {
+                let chunk: Vec<&str> = chunk.iter().map(|id| &id[..]).collect();
+                let several_albums = self
+                    .client
+                    .get_several_albums(&chunk)
+                    .send()
+                    .await?
+                    .deserialize_result()?;
+                albums.append(&mut several_albums.into());
+            }
+            Ok(albums)
+        })
+    }
+
+    fn get_several_artists(
+        &self,
+        ids: Vec<String>,
+    ) -> BoxFuture<SpotifyResult<Vec<ArtistSummary>>> {
+        Box::pin(async move {
+            let mut artists: HashMap<String, ArtistSummary> = ids
+                .iter()
+                .filter_map(|id| Some((id.clone(), self.artist_summaries.get(id)?)))
+                .collect();
+
+            // only what isn't cached is requested, each id once
+            let mut requested = HashSet::new();
+            let missing: Vec<&str> = ids
+                .iter()
+                .filter(|id| !artists.contains_key(*id) && requested.insert(*id))
+                .map(|id| &id[..])
+                .collect();
+
+            for chunk in missing.chunks(MAX_IDS_PER_REQUEST) {
+                let several_artists = self
+                    .client
+                    .get_several_artists(chunk)
+                    .send()
+                    .await?
+                    .deserialize_result()?;
+                for artist in Vec::<ArtistSummary>::from(several_artists) {
+                    self.artist_summaries
+                        .insert(artist.id.clone(), artist.clone());
+                    artists.insert(artist.id.clone(), artist);
+                }
+            }
+
+            Ok(ids
+                .iter()
+                .filter_map(|id| artists.get(id).cloned())
+                .collect())
+        })
+    }
+
+    fn get_show(&self, id: &str) -> BoxFuture<SpotifyResult<ShowDescription>> {
+        let id = id.to_owned();
+
+        Box::pin(async move {
+            let language = self.accept_language();
+            let show = self
+                .cache_get_or_write(SpotCacheKey::Show(&id, &language), None, |etag| {
+                    self.client
+                        .get_show(&id)
+                        .header("Accept-Language", &language)
+                        .etag(etag)
+                        .send()
+                })
+                .await?;
+
+            Ok(show.into())
+        })
+    }
+
+    fn get_episode(&self, id: &str) -> BoxFuture<SpotifyResult<EpisodeDescription>> {
+        let id = id.to_owned();
+
+        Box::pin(async move {
+            let language = self.accept_language();
+            let episode = self
+                .cache_get_or_write(SpotCacheKey::Episode(&id, &language), None, |etag| {
+                    self.client
+                        .get_episode(&id)
+                        .header("Accept-Language", &language)
+                        .etag(etag)
+                        .send()
+                })
+                .await?;
+
+            Ok(episode.into())
+        })
+    }
+
+    fn get_saved_shows(
+        &self,
+        offset: usize,
+        limit: usize,
+    ) -> BoxFuture<SpotifyResult<Vec<ShowDescription>>> {
+        Box::pin(async move {
+            let page = self
+                .cache_get_or_write(SpotCacheKey::SavedShows(offset, limit), None, |etag| {
+                    self.client.get_saved_shows(offset, limit).etag(etag).send()
+                })
+                .await?;
+
+            let shows = page
+                .into_iter()
+                .map(|saved| saved.show.into())
+                .collect::<Vec<ShowDescription>>();
+
+            Ok(shows)
+        })
+    }
+
+    fn save_shows(&self, ids: Vec<String>) -> BoxFuture<SpotifyResult<()>> {
+        Box::pin(async move {
+            let _ = self.cache.set_expired_pattern(&*ME_SHOWS_CACHE).await;
+            for chunk in ids.chunks(MAX_IDS_PER_REQUEST) {
+                let chunk: Vec<&str> = chunk.iter().map(|id| &id[..]).collect();
+                self.client.save_shows(&chunk).send_no_response().await?;
+            }
+            Ok(())
+        })
+    }
+
+    fn remove_saved_shows(&self, ids: Vec<String>) -> BoxFuture<SpotifyResult<()>> {
+        Box::pin(async move {
+            let _ = self.cache.set_expired_pattern(&*ME_SHOWS_CACHE).await;
+            for chunk in ids.chunks(MAX_IDS_PER_REQUEST) {
+                let chunk: Vec<&str> = chunk.iter().map(|id| &id[..]).collect();
+                self.client
+                    .remove_saved_shows(&chunk)
+                    .send_no_response()
+                    .await?;
+            }
+            Ok(())
+        })
+    }
+
     fn get_playlist(&self, id: &str) -> BoxFuture<SpotifyResult<PlaylistDescription>> {
         let id = id.to_owned();
 
@@ -432,6 +1381,8 @@ impl SpotifyApiClient for CachedSpotifyClient {
                     self.client.get_playlist(&id).etag(etag).send()
                 })
                 .await?;
+            self.client
+                .set_playlist_snapshot(&id, playlist.snapshot_id.clone());
 
             Ok(playlist.into())
         })
@@ -463,81 +1414,234 @@ impl SpotifyApiClient for CachedSpotifyClient {
         })
     }
 
+    fn get_all_playlist_tracks(&self, id: &str) -> BoxStream<SpotifyResult<SongDescription>> {
+        let id = id.to_owned();
+
+        paginate(0, MAX_PLAYLIST_TRACKS_PER_REQUEST, move |offset, limit| {
+            let id = id.clone();
+            async move {
+                self.cache_get_or_write(
+                    SpotCacheKey::PlaylistTracks(&id, offset, limit),
+                    None,
+                    |etag| {
+                        self.client
+                            .get_playlist_tracks(&id, offset, limit)
+                            .etag(etag)
+                            .send()
+                    },
+                )
+                .await
+            }
+        })
+        // local files and unavailable tracks have no song to export
+        .try_filter_map(|track: PlaylistTrack| async move {
+            let song: Option<SongDescription> = TryInto::<TrackItem>::try_into(track)
+                .ok()
+                .and_then(|item| item.try_into().ok());
+            Ok(song)
+        })
+        .boxed()
+    }
+
     fn get_artist_albums(
         &self,
         id: &str,
+        groups: &[AlbumType],
         offset: usize,
         limit: usize,
     ) -> BoxFuture<SpotifyResult<Vec<AlbumDescription>>> {
         let id = id.to_owned();
+        let groups = groups.to_vec();
 
         Box::pin(async move {
+            let cache_groups = groups
+                .iter()
+                .map(|group| group.into_string())
+                .collect::<Vec<&str>>()
+                .join("-");
             let albums = self
                 .cache_get_or_write(
-                    SpotCacheKey::ArtistAlbums(&id, offset, limit),
+                    SpotCacheKey::ArtistAlbums(&id, &cache_groups, offset, limit),
                     None,
                     |etag| {
                         self.client
-                            .get_artist_albums(&id, offset, limit)
+                            .get_artist_albums(&id, &groups, offset, limit)
                             .etag(etag)
                             .send()
                     },
                 )
                 .await?;
 
+            // the artist's own releases are paged through by how many Spotify returned, so they're left as is
+            let dedupe = groups.iter().all(|group| group.is_listed_per_market());
+            let mut seen = HashSet::new();
             let albums = albums
                 .into_iter()
                 .map(|a| a.into())
+                .filter(|a: &AlbumDescription| {
+                    !dedupe || seen.insert((a.title.to_lowercase(), a.year()))
+                })
                 .collect::<Vec<AlbumDescription>>();
 
             Ok(albums)
         })
     }
 
-    fn get_artist(&self, id: &str) -> BoxFuture<SpotifyResult<ArtistDescription>> {
-        let id = id.to_owned();
-
+    fn follow_artists(&self, ids: Vec<String>) -> BoxFuture<SpotifyResult<()>> {
         Box::pin(async move {
-            let artist = self.cache_get_or_write(SpotCacheKey::Artist(&id), None, |etag| {
-                self.client.get_artist(&id).etag(etag).send()
-            });
-
-            let albums = self.get_artist_albums(&id, 0, 20);
-
-            let top_tracks =
-                self.cache_get_or_write(SpotCacheKey::ArtistTopTracks(&id), None, |etag| {
-                    self.client.get_artist_top_tracks(&id).etag(etag).send()
-                });
+            let _ = self
+                .cache
+                .set_expired_pattern(&*ARTIST_FOLLOWED_CACHE)
+                .await;
+            for chunk in ids.chunks(MAX_IDS_PER_REQUEST) {
+                let chunk: Vec<&str> = chunk.iter().map(|id| &id[..]).collect();
+                self.client
+                    .follow_artists(&chunk)
+                    .send_no_response()
+                    .await?;
+            }
+            Ok(())
+        })
+    }
 
-            let (artist, albums, top_tracks) = join!(artist, albums, top_tracks);
+    fn unfollow_artists(&self, ids: Vec<String>) -> BoxFuture<SpotifyResult<()>> {
+        Box::pin(async move {
+            let _ = self
+                .cache
+                .set_expired_pattern(&*ARTIST_FOLLOWED_CACHE)
+                .await;
+            for chunk in ids.chunks(MAX_IDS_PER_REQUEST) {
+                let chunk: Vec<&str> = chunk.iter().map(|id| &id[..]).collect();
+                self.client
+                    .unfollow_artists(&chunk)
+                    .send_no_response()
+                    .await?;
+            }
+            Ok(())
+        })
+    }
 
-            let artist = artist?;
-            let result = ArtistDescription {
-                id: artist.id,
-                name: artist.name,
-                albums: albums?,
-                top_tracks: top_tracks?.into(),
-            };
-            Ok(result)
+    fn are_artists_followed(&self, ids: Vec<String>) -> BoxFuture<SpotifyResult<Vec<bool>>> {
+        Box::pin(async move {
+            let mut followed = Vec::with_capacity(ids.len());
+            for chunk in ids.chunks(MAX_IDS_PER_REQUEST) {
+                let chunk: Vec<&str> = chunk.iter().map(|id| &id[..]).collect();
+                let mut chunk_followed = self
+                    .client
+                    .are_artists_followed(&chunk)
+                    .send()
+                    .await?
+                    .deserialize_result()?;
+                followed.append(&mut chunk_followed);
+            }
+            Ok(followed)
         })
     }
 
-    fn search(
-        &self,
-        query: &str,
-        offset: usize,
+    fn get_related_artists(&self, id: &str) -> BoxFuture<SpotifyResult<Vec<ArtistSummary>>> {
+        let id = id.to_owned();
+
+        Box::pin(async move {
+            let related = self
+                .cache_get_or_write(SpotCacheKey::ArtistRelated(&id), None, |etag| {
+                    self.client.get_related_artists(&id).etag(etag).send()
+                })
+                .await?;
+
+            let artists = related
+                .artists
+                .into_iter()
+                .map(|artist| artist.into())
+                .collect::<Vec<ArtistSummary>>();
+
+            Ok(artists)
+        })
+    }
+
+    fn get_followed_artists(
+        &self,
+        after: Option<String>,
+        limit: usize,
+    ) -> BoxFuture<SpotifyResult<Vec<ArtistSummary>>> {
+        Box::pin(async move {
+            let followed = self
+                .client
+                .get_followed_artists(after.as_deref(), limit)
+                .send()
+                .await?
+                .deserialize_result()?;
+
+            let artists = followed
+                .artists
+                .into_iter()
+                .map(|artist| artist.into())
+                .collect::<Vec<ArtistSummary>>();
+
+            Ok(artists)
+        })
+    }
+
+    fn get_artist(&self, id: &str) -> BoxFuture<SpotifyResult<ArtistDescription>> {
+        let id = id.to_owned();
+
+        Box::pin(async move {
+            let artist = self.cache_get_or_write(SpotCacheKey::Artist(&id), None, |etag| {
+                self.client.get_artist(&id).etag(etag).send()
+            });
+
+            let albums = self.get_artist_albums(&id, AlbumType::DEFAULT_GROUPS, 0, 20);
+
+            let top_tracks =
+                self.cache_get_or_write(SpotCacheKey::ArtistTopTracks(&id), None, |etag| {
+                    self.client.get_artist_top_tracks(&id).etag(etag).send()
+                });
+
+            let followed = self.cache_get_or_write(
+                SpotCacheKey::ArtistFollowed(&id),
+                Some(if self.client.has_token() {
+                    CachePolicy::Revalidate
+                } else {
+                    CachePolicy::IgnoreExpiry
+                }),
+                |etag| self.client.are_artists_followed(&[&id]).etag(etag).send(),
+            );
+
+            let (artist, albums, top_tracks, followed) =
+                join!(artist, albums, top_tracks, followed);
+
+            let artist = artist?;
+            let result = ArtistDescription {
+                id: artist.id,
+                name: artist.name,
+                albums: albums?,
+                top_tracks: top_tracks?.into(),
+                // best effort, the artist page shouldn't fail because of it
+                is_followed: followed
+                    .ok()
+                    .and_then(|followed: Vec<bool>| followed.first().cloned())
+                    .unwrap_or(false),
+            };
+            Ok(result)
+        })
+    }
+
+    fn search(
+        &self,
+        query: &str,
+        types: &[SearchType],
+        offset: usize,
         limit: usize,
     ) -> BoxFuture<SpotifyResult<SearchResults>> {
         let query = query.to_owned();
+        let types = types.to_vec();
 
         Box::pin(async move {
             let results = self
                 .client
-                .search(query, offset, limit)
+                .search(query, &types, offset, limit)
                 .send()
                 .await?
-                .deserialize()
-                .ok_or(SpotifyApiError::NoContent)?;
+                .deserialize_result()?;
 
             let albums = results
                 .albums
@@ -553,7 +1657,40 @@ impl SpotifyApiClient for CachedSpotifyClient {
                 .map(|saved| saved.into())
                 .collect::<Vec<ArtistSummary>>();
 
-            Ok(SearchResults { albums, artists })
+            let playlists = results
+                .playlists
+                .unwrap_or_default()
+                .into_iter()
+                .map(|p| p.into())
+                .collect::<Vec<PlaylistDescription>>();
+
+            let songs = results
+                .tracks
+                .map(Vec::<SongDescription>::from)
+                .unwrap_or_default();
+
+            let shows = results
+                .shows
+                .unwrap_or_default()
+                .into_iter()
+                .map(|s| s.into())
+                .collect::<Vec<ShowDescription>>();
+
+            let episodes = results
+                .episodes
+                .unwrap_or_default()
+                .into_iter()
+                .map(|e| e.into())
+                .collect::<Vec<EpisodeDescription>>();
+
+            Ok(SearchResults {
+                albums,
+                artists,
+                playlists,
+                songs,
+                shows,
+                episodes,
+            })
         })
     }
 
@@ -588,6 +1725,384 @@ impl SpotifyApiClient for CachedSpotifyClient {
         })
     }
 
+    fn get_recommendations(
+        &self,
+        seed_artists: Vec<String>,
+        seed_tracks: Vec<String>,
+        seed_genres: Vec<String>,
+        limit: usize,
+        params: Option<RecommendationParams>,
+    ) -> BoxFuture<SpotifyResult<Vec<SongDescription>>> {
+        Box::pin(async move {
+            let seed_artists: Vec<&str> = seed_artists.iter().map(|s| &s[..]).collect();
+            let seed_tracks: Vec<&str> = seed_tracks.iter().map(|s| &s[..]).collect();
+            let seed_genres: Vec<&str> = seed_genres.iter().map(|s| &s[..]).collect();
+
+            let recommendations = self
+                .client
+                .get_recommendations(
+                    &seed_artists,
+                    &seed_tracks,
+                    &seed_genres,
+                    limit,
+                    params.as_ref(),
+                )
+                .send()
+                .await?
+                .deserialize_result()?;
+
+            Ok(recommendations.into())
+        })
+    }
+
+    fn get_available_genre_seeds(&self) -> BoxFuture<SpotifyResult<Vec<String>>> {
+        Box::pin(async move {
+            let seeds = self
+                .cache_get_or_write(SpotCacheKey::GenreSeeds, None, |etag| {
+                    self.client
+                        .get_available_genre_seeds()
+                        .etag(etag)
+                        .send()
+                        .map(|response| {
+                            response.map(|mut response| {
+                                let max_age = response.cache_control.max_age.unwrap_or(0);
+                                response.cache_control.max_age =
+                                    Some(u64::max(max_age, GENRE_SEEDS_MIN_MAX_AGE));
+                                response
+                            })
+                        })
+                })
+                .await?;
+
+            Ok(seeds.genres)
+        })
+    }
+
+    fn get_new_releases(
+        &self,
+        offset: usize,
+        limit: usize,
+    ) -> BoxFuture<SpotifyResult<Vec<AlbumDescription>>> {
+        Box::pin(async move {
+            let language = self.accept_language();
+            let releases = self
+                .cache_get_or_write(
+                    SpotCacheKey::NewReleases(offset, limit, &language),
+                    None,
+                    |etag| {
+                        self.client
+                            .get_new_releases(offset, limit)
+                            .header("Accept-Language", &language)
+                            .etag(etag)
+                            .send()
+                    },
+                )
+                .await?;
+
+            let albums = releases
+                .albums
+                .into_iter()
+                .map(|album| album.into())
+                .collect::<Vec<AlbumDescription>>();
+
+            Ok(albums)
+        })
+    }
+
+    fn get_featured_playlists(
+        &self,
+        offset: usize,
+        limit: usize,
+    ) -> BoxFuture<SpotifyResult<FeaturedPlaylistsDescription>> {
+        Box::pin(async move {
+            let language = self.accept_language();
+            let featured = self
+                .cache_get_or_write(
+                    SpotCacheKey::FeaturedPlaylists(offset, limit, &language),
+                    None,
+                    |etag| {
+                        self.client
+                            .get_featured_playlists(offset, limit)
+                            .header("Accept-Language", &language)
+                            .etag(etag)
+                            .send()
+                    },
+                )
+                .await?;
+
+            Ok(featured.into())
+        })
+    }
+
+    fn get_categories(
+        &self,
+        offset: usize,
+        limit: usize,
+    ) -> BoxFuture<SpotifyResult<Vec<CategoryDescription>>> {
+        Box::pin(async move {
+            let language = self.accept_language();
+            let categories = self
+                .cache_get_or_write(
+                    SpotCacheKey::Categories(offset, limit, &language),
+                    None,
+                    |etag| {
+                        self.client
+                            .get_categories(offset, limit)
+                            .header("Accept-Language", &language)
+                            .etag(etag)
+                            .send()
+                    },
+                )
+                .await?;
+
+            let categories = categories
+                .categories
+                .into_iter()
+                .map(|category| category.into())
+                .collect::<Vec<CategoryDescription>>();
+
+            Ok(categories)
+        })
+    }
+
+    fn get_category_playlists(
+        &self,
+        id: &str,
+        offset: usize,
+        limit: usize,
+    ) -> BoxFuture<SpotifyResult<Vec<PlaylistDescription>>> {
+        let id = id.to_owned();
+
+        Box::pin(async move {
+            let language = self.accept_language();
+            let playlists = self
+                .cache_get_or_write(
+                    SpotCacheKey::CategoryPlaylists(&id, offset, limit, &language),
+                    None,
+                    |etag| {
+                        self.client
+                            .get_category_playlists(&id, offset, limit)
+                            .header("Accept-Language", &language)
+                            .etag(etag)
+                            .send()
+                    },
+                )
+                .await?;
+
+            let playlists = playlists
+                .playlists
+                .into_iter()
+                .map(|playlist| playlist.into())
+                .collect::<Vec<PlaylistDescription>>();
+
+            Ok(playlists)
+        })
+    }
+
+    fn get_devices(&self) -> BoxFuture<SpotifyResult<Vec<ConnectDevice>>> {
+        Box::pin(async move {
+            let devices = self
+                .client
+                .get_devices()
+                .send()
+                .await?
+                .deserialize_result()?;
+
+            Ok(devices.into())
+        })
+    }
+
+    fn get_playback_state(&self) -> BoxFuture<SpotifyResult<Option<ConnectPlaybackState>>> {
+        Box::pin(async move {
+            let state = self
+                .client
+                .get_playback_state()
+                .send()
+                .await?
+                .deserialize_result()?;
+
+            Ok(state.map(|state| state.into()))
+        })
+    }
+
+    fn get_currently_playing(
+        &self,
+    ) -> BoxFuture<SpotifyResult<Option<CurrentlyPlayingDescription>>> {
+        Box::pin(async move {
+            let response = self.client.get_currently_playing().send().await?;
+            let stale_at = response.stale_at();
+            let currently_playing = response.deserialize_result()?;
+
+            Ok(currently_playing.map(|c| CurrentlyPlayingDescription {
+                stale_at,
+                ..c.into()
+            }))
+        })
+    }
+
+    fn get_queue(&self) -> BoxFuture<SpotifyResult<PlayerQueueDescription>> {
+        Box::pin(async move {
+            let queue = self.client.get_queue().send().await?.deserialize_result()?;
+
+            Ok(queue.into())
+        })
+    }
+
+    fn get_resource_info(&self, uri: &str) -> BoxFuture<SpotifyResult<ResourceInfo>> {
+        let uri = uri.to_owned();
+
+        Box::pin(async move { self.client.head(&uri)?.send_head().await })
+    }
+
+    fn add_to_queue(&self, uri: &str, device_id: Option<String>) -> BoxFuture<SpotifyResult<()>> {
+        let uri = uri.to_owned();
+
+        Box::pin(async move {
+            self.client
+                .add_to_queue(&uri, device_id.as_deref())
+                .send_no_response()
+                .await
+        })
+    }
+
+    fn transfer_playback(&self, device_id: &str, play: bool) -> BoxFuture<SpotifyResult<()>> {
+        let device_id = device_id.to_owned();
+
+        Box::pin(async move {
+            self.client
+                .transfer_playback(&device_id, play)
+                .send_no_response()
+                .await
+                .map_err(|e| match e {
+                    SpotifyApiError::BadStatus(404, _, _) => {
+                        SpotifyApiError::DeviceNotFound(device_id)
+                    }
+                    e => e,
+                })
+        })
+    }
+
+    fn remote_play(&self, device_id: Option<String>) -> BoxFuture<SpotifyResult<()>> {
+        Box::pin(async move {
+            self.client
+                .remote_play(device_id.as_deref())
+                .send_no_response()
+                .await
+        })
+    }
+
+    fn play_context(
+        &self,
+        device_id: Option<String>,
+        target: PlaybackTarget,
+    ) -> BoxFuture<SpotifyResult<()>> {
+        Box::pin(async move {
+            self.client
+                .play_context(device_id.as_deref(), target)
+                .send_no_response()
+                .await
+        })
+    }
+
+    fn remote_pause(&self, device_id: Option<String>) -> BoxFuture<SpotifyResult<()>> {
+        Box::pin(async move {
+            self.client
+                .remote_pause(device_id.as_deref())
+                .send_no_response()
+                .await
+        })
+    }
+
+    fn remote_next(&self, device_id: Option<String>) -> BoxFuture<SpotifyResult<()>> {
+        Box::pin(async move {
+            self.client
+                .remote_next(device_id.as_deref())
+                .send_no_response()
+                .await
+        })
+    }
+
+    fn remote_previous(&self, device_id: Option<String>) -> BoxFuture<SpotifyResult<()>> {
+        Box::pin(async move {
+            self.client
+                .remote_previous(device_id.as_deref())
+                .send_no_response()
+                .await
+        })
+    }
+
+    fn set_volume(&self, percent: u8, device_id: Option<String>) -> BoxFuture<SpotifyResult<()>> {
+        Box::pin(async move {
+            self.client
+                .set_volume(percent, device_id.as_deref())
+                .send_no_response()
+                .await
+        })
+    }
+
+    fn set_repeat(
+        &self,
+        mode: RepeatMode,
+        device_id: Option<String>,
+    ) -> BoxFuture<SpotifyResult<()>> {
+        Box::pin(async move {
+            self.client
+                .set_repeat(mode, device_id.as_deref())
+                .send_no_response()
+                .await
+        })
+    }
+
+    fn set_shuffle(
+        &self,
+        state: ShuffleState,
+        device_id: Option<String>,
+    ) -> BoxFuture<SpotifyResult<()>> {
+        Box::pin(async move {
+            self.client
+                .set_shuffle(state, device_id.as_deref())
+                .send_no_response()
+                .await
+        })
+    }
+
+    fn seek(
+        &self,
+        position_ms: u32,
+        duration_ms: Option<u32>,
+        device_id: Option<String>,
+    ) -> BoxFuture<SpotifyResult<()>> {
+        // Seeking past the end of the track is rejected by the API
+        let position_ms = duration_ms
+            .map(|duration_ms| position_ms.min(duration_ms.saturating_sub(1)))
+            .unwrap_or(position_ms);
+
+        Box::pin(async move {
+            self.client
+                .seek(position_ms, device_id.as_deref())
+                .send_no_response()
+                .await
+        })
+    }
+
+    fn get_current_user(&self) -> BoxFuture<SpotifyResult<CurrentUserDescription>> {
+        Box::pin(async move {
+            let user = self
+                .cache_get_or_write(SpotCacheKey::CurrentUser, None, |etag| {
+                    self.client.get_current_user().etag(etag).send()
+                })
+                .await?;
+            self.client.set_product(user.product.clone());
+            self.client.set_detected_country(user.country.clone());
+
+            Ok(user.into())
+        })
+    }
+
+    fn is_premium(&self) -> Option<bool> {
+        self.client.is_premium()
+    }
+
     fn get_user(&self, id: &str) -> BoxFuture<SpotifyResult<UserDescription>> {
         let id = id.to_owned();
 
@@ -614,7 +2129,33 @@ impl SpotifyApiClient for CachedSpotifyClient {
 #[cfg(test)]
 pub mod tests {
 
+    use super::{MemoryCache, SpotCacheKey};
     use crate::api::api_models::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_memory_cache() {
+        let cache = MemoryCache::new(Duration::from_secs(60));
+        cache.insert("a".to_string(), 1);
+        assert_eq!(cache.get("a"), Some(1));
+        assert_eq!(cache.get("b"), None);
+
+        let cache = MemoryCache::new(Duration::from_secs(0));
+        cache.insert("a".to_string(), 1);
+        assert_eq!(cache.get("a"), None);
+    }
+
+    #[test]
+    fn test_localized_cache_keys() {
+        assert_eq!(
+            SpotCacheKey::Album("a", "pt-BR, pt").into_raw(),
+            "album_a_pt-BR_pt.json"
+        );
+        assert_ne!(
+            SpotCacheKey::Show("a", "de").into_raw(),
+            SpotCacheKey::Show("a", "en").into_raw()
+        );
+    }
 
     #[test]
     fn test_search_query() {
@@ -1,21 +1,72 @@
 use form_urlencoded::Serializer;
+use futures::channel::oneshot;
+use futures::future::BoxFuture;
+use futures::stream::{self, Stream, StreamExt};
 use isahc::config::Configurable;
-use isahc::http::{method::Method, request::Builder, StatusCode, Uri};
-use isahc::{AsyncReadResponseExt, HttpClient, Request};
+use isahc::error::ErrorKind;
+use isahc::http::{method::Method, request::Builder, HeaderValue, StatusCode, Uri};
+use isahc::{AsyncBody, AsyncReadResponseExt, HttpClient, Request, Response};
 use percent_encoding::{utf8_percent_encode, AsciiSet, CONTROLS};
+use rand::Rng;
 use serde::{de::Deserialize, Serialize};
 use serde_json::from_str;
+use std::collections::HashMap;
 use std::convert::Into;
+use std::env;
+use std::future::Future;
 use std::marker::PhantomData;
 use std::str::FromStr;
-use std::sync::Mutex;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
 use thiserror::Error;
 
 pub use super::api_models::*;
 use super::cache::CacheError;
+use crate::app::models::{RepeatMode, ShuffleState};
 
 const SPOTIFY_HOST: &str = "api.spotify.com";
 
+const USER_AGENT: &str = concat!("spot/", env!("CARGO_PKG_VERSION"));
+
+// Rate limited (429) or unavailable (503) requests are retried if the API asks us to wait at most this long (in seconds)
+const MAX_RETRY_AFTER: u64 = 5;
+
+// Checked in order, the first one set is used as the proxy for all API requests
+const PROXY_ENV_VARS: [&str; 4] = ["HTTPS_PROXY", "https_proxy", "ALL_PROXY", "all_proxy"];
+
+const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+// Headers that may carry the id Spotify assigned to a request, checked in order
+const REQUEST_ID_HEADERS: [&str; 2] = ["spotify-request-id", "x-request-id"];
+
+// Delay before the first retry, doubled on every following attempt (in milliseconds)
+const BACKOFF_BASE: u64 = 250;
+
+// Most endpoints taking a list of ids accept at most this many per call
+pub(crate) const MAX_IDS_PER_REQUEST: usize = 50;
+
+// Checking saved albums and fetching several albums is capped lower than other id lists
+pub(crate) const MAX_ALBUM_IDS_PER_REQUEST: usize = 20;
+
+// Following a playlist can be checked for at most this many users at once
+pub(crate) const MAX_PLAYLIST_FOLLOWERS_PER_REQUEST: usize = 5;
+
+// Playlist edits are capped at this many tracks per call
+pub(crate) const MAX_PLAYLIST_TRACKS_PER_REQUEST: usize = 100;
+
+// The user's playlists are returned at most this many at a time
+pub(crate) const MAX_PLAYLISTS_PER_REQUEST: usize = 50;
+
+// Top tracks and artists are returned at most this many at a time
+pub(crate) const MAX_TOP_ITEMS_PER_REQUEST: usize = 50;
+
+// How many characters of the body are shown before the point where parsing a response failed
+const ERROR_CONTEXT_LEN: usize = 40;
+
+// Recommendations accept up to this many seeds, across artists, tracks and genres
+pub(crate) const MAX_RECOMMENDATION_SEEDS: usize = 5;
+
 // https://url.spec.whatwg.org/#path-percent-encode-set
 const PATH_ENCODE_SET: &AsciiSet = &CONTROLS
     .add(b' ')
@@ -28,20 +79,66 @@ const PATH_ENCODE_SET: &AsciiSet = &CONTROLS
     .add(b'{')
     .add(b'}');
 
+lazy_static! {
+    static ref ACCEPT_INVALID_CERTS: bool = {
+        let enabled = env::var("SPOT_ACCEPT_INVALID_CERTS").as_deref() == Ok("1");
+        if enabled {
+            warn!("SPOT_ACCEPT_INVALID_CERTS is set, TLS certificates will NOT be verified!");
+        }
+        enabled
+    };
+}
+
+// Only meant for debugging through an intercepting proxy, never enabled by default
+pub fn accept_invalid_certs() -> bool {
+    *ACCEPT_INVALID_CERTS
+}
+
 fn make_query_params<'a>() -> Serializer<'a, String> {
     Serializer::new(String::new())
 }
 
+// Fetches pages of page_size items on demand until the API reports there is no next page,
+// an error ends the stream
+pub(crate) fn paginate<'a, T, F, O>(
+    initial_offset: usize,
+    page_size: usize,
+    fetch: F,
+) -> impl Stream<Item = Result<T, SpotifyApiError>> + 'a
+where
+    T: 'a,
+    F: FnMut(usize, usize) -> O + 'a,
+    O: Future<Output = Result<Page<T>, SpotifyApiError>> + 'a,
+{
+    stream::unfold(
+        (fetch, Some(initial_offset)),
+        move |(mut fetch, offset)| async move {
+            let offset = offset?;
+            let (items, next_offset) = match fetch(offset, page_size).await {
+                Ok(page) => {
+                    let next_offset = page.next_offset();
+                    (page.into_iter().map(Ok).collect::<Vec<_>>(), next_offset)
+                }
+                Err(e) => (vec![Err(e)], None),
+            };
+            Some((stream::iter(items), (fetch, next_offset)))
+        },
+    )
+    .flatten()
+}
+
 pub(crate) struct SpotifyRequest<'a, Body, Response> {
     client: &'a SpotifyClient,
     request: Builder,
     body: Body,
+    // Set when the request can't be valid, it is returned instead of sending the request
+    invalid: Option<SpotifyApiError>,
     _type: PhantomData<Response>,
 }
 
 impl<'a, B, R> SpotifyRequest<'a, B, R>
 where
-    B: Into<isahc::AsyncBody>,
+    B: Into<isahc::AsyncBody> + Clone,
 {
     fn method(mut self, method: Method) -> Self {
         self.request = self.request.method(method);
@@ -63,15 +160,38 @@ where
         self
     }
 
+    fn invalid_if<F>(mut self, invalid: bool, error: F) -> Self
+    where
+        F: FnOnce() -> SpotifyApiError,
+    {
+        if invalid && self.invalid.is_none() {
+            self.invalid = Some(error());
+        }
+        self
+    }
+
+    // Spotify caps how many ids or uris a single request may carry
+    fn at_most(self, given: usize, max: usize) -> Self {
+        self.invalid_if(given > max, || SpotifyApiError::TooManyItems { given, max })
+    }
+
     fn authenticated(mut self) -> Result<Self, SpotifyApiError> {
-        let token = self.client.token.lock().unwrap();
+        if let Some(error) = self.invalid.take() {
+            return Err(error);
+        }
+        let token = self.client.token.read().unwrap();
         let token = token.as_ref().ok_or(SpotifyApiError::NoToken)?;
         self.request = self
             .request
-            .header("Authorization", format!("Bearer {}", token));
+            .header("Authorization", format!("Bearer {}", token.value));
         Ok(self)
     }
 
+    pub(crate) fn header(mut self, name: &str, value: &str) -> Self {
+        self.request = self.request.header(name, value);
+        self
+    }
+
     pub(crate) fn etag(mut self, etag: Option<String>) -> Self {
         if let Some(etag) = etag {
             self.request = self.request.header("If-None-Match", etag);
@@ -86,6 +206,7 @@ where
         let Self {
             client,
             request,
+            invalid,
             _type,
             ..
         } = self;
@@ -93,6 +214,7 @@ where
             client,
             request: request.header("Content-Type", "application/json"),
             body: serde_json::to_vec(&body).unwrap(),
+            invalid,
             _type,
         }
     }
@@ -104,7 +226,41 @@ where
             body,
             ..
         } = self.authenticated()?;
-        client.send_req(request.body(body).unwrap()).await
+        let request = request.body(body).unwrap();
+        let retry = SpotifyClient::copy_request(&request);
+        match client.send_req(request).await {
+            Err(SpotifyApiError::InvalidToken) if client.refresh_token().await => {
+                client.send_req(client.reauthenticated(retry)?).await
+            }
+            Err(err) if err.is_market_error() => match client.with_detected_market(&retry) {
+                Some(retry) => client.send_req(retry).await,
+                None => Err(err),
+            },
+            result => result,
+        }
+    }
+
+    // Only the headers come back, the Spotify token is not sent to other hosts (e.g. image CDNs)
+    pub(crate) async fn send_head(self) -> Result<ResourceInfo, SpotifyApiError> {
+        let is_spotify = self.request.uri_ref().and_then(|uri| uri.host()) == Some(SPOTIFY_HOST);
+        let Self {
+            client,
+            request,
+            body,
+            ..
+        } = if is_spotify {
+            self.authenticated()?
+        } else {
+            self
+        };
+        let request = request.body(body).unwrap();
+        let retry = SpotifyClient::copy_request(&request);
+        match client.send_req_head(request).await {
+            Err(SpotifyApiError::InvalidToken) if is_spotify && client.refresh_token().await => {
+                client.send_req_head(client.reauthenticated(retry)?).await
+            }
+            result => result,
+        }
     }
 
     pub(crate) async fn send_no_response(self) -> Result<(), SpotifyApiError> {
@@ -114,9 +270,16 @@ where
             body,
             ..
         } = self.authenticated()?;
-        client
-            .send_req_no_response(request.body(body).unwrap())
-            .await
+        let request = request.body(body).unwrap();
+        let retry = SpotifyClient::copy_request(&request);
+        match client.send_req_no_response(request).await {
+            Err(SpotifyApiError::InvalidToken) if client.refresh_token().await => {
+                client
+                    .send_req_no_response(client.reauthenticated(retry)?)
+                    .await
+            }
+            result => result,
+        }
     }
 }
 
@@ -125,23 +288,86 @@ pub(crate) enum SpotifyResponseKind<T> {
     NotModified,
 }
 
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct CacheControl {
+    pub max_age: Option<u64>,
+    pub no_cache: bool,
+    pub no_store: bool,
+    pub must_revalidate: bool,
+}
+
+impl CacheControl {
+    // How long (in seconds) a response can be served from cache before revalidating it
+    pub(crate) fn expires_in(&self) -> u64 {
+        match (self.no_cache, self.max_age) {
+            (true, _) => 0,
+            (false, Some(max_age)) if self.must_revalidate => max_age,
+            (false, max_age) => u64::max(max_age.unwrap_or(10), 10),
+        }
+    }
+}
+
+// What a HEAD request says about a resource, without downloading it
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ResourceInfo {
+    pub content_length: Option<u64>,
+    pub etag: Option<String>,
+}
+
 pub(crate) struct SpotifyResponse<T> {
     pub kind: SpotifyResponseKind<T>,
-    pub max_age: u64,
+    pub cache_control: CacheControl,
     pub etag: Option<String>,
+    pub received_at: Instant,
+}
+
+impl<T> SpotifyResponse<T> {
+    // When the server says this data should be fetched again, None if it didn't say
+    pub(crate) fn stale_at(&self) -> Option<Instant> {
+        if self.cache_control.no_cache {
+            return Some(self.received_at);
+        }
+        let max_age = self.cache_control.max_age?;
+        Some(self.received_at + Duration::from_secs(max_age))
+    }
 }
 
 impl<'a, T> SpotifyResponse<T>
 where
     T: Deserialize<'a>,
 {
+    // For callers that don't need to know why parsing failed
+    #[allow(dead_code)]
     pub(crate) fn deserialize(&'a self) -> Option<T> {
-        if let SpotifyResponseKind::Ok(ref content, _) = self.kind {
-            from_str(content).ok()
-        } else {
-            None
+        self.deserialize_result().ok()
+    }
+
+    // Says why parsing failed and where in the body it happened
+    pub(crate) fn deserialize_result(&'a self) -> Result<T, SpotifyApiError> {
+        match self.kind {
+            // 204 No Content comes back with an empty body, which optional responses read as none
+            SpotifyResponseKind::Ok(ref content, _) if content.is_empty() => {
+                from_str("null").map_err(|_| SpotifyApiError::NoContent)
+            }
+            SpotifyResponseKind::Ok(ref content, _) => from_str(content).map_err(|error| {
+                let near = Self::error_context(content, &error);
+                SpotifyApiError::UnexpectedResponse { error, near }
+            }),
+            SpotifyResponseKind::NotModified => Err(SpotifyApiError::NoContent),
         }
     }
+
+    // The bit of the body leading up to where parsing failed, usually enough to tell the field
+    fn error_context(content: &str, error: &serde_json::Error) -> String {
+        let line = content
+            .lines()
+            .nth(error.line().saturating_sub(1))
+            .unwrap_or("");
+        line.chars()
+            .skip(error.column().saturating_sub(ERROR_CONTEXT_LEN))
+            .take(ERROR_CONTEXT_LEN)
+            .collect()
+    }
 }
 
 #[derive(Error, Debug)]
@@ -152,79 +378,575 @@ pub enum SpotifyApiError {
     NoToken,
     #[error("No content from request")]
     NoContent,
-    #[error("Request failed ({0}): {1}")]
-    BadStatus(u16, String),
+    // the last field is the id Spotify gave the request, if any, worth quoting when reporting issues
+    #[error("Request failed ({0}): {1}{}", request_id_suffix(.2))]
+    BadStatus(u16, String, Option<String>),
+    // 403, usually because the token lacks a scope the endpoint needs
+    #[error(
+        "Forbidden: {}{}",
+        .reason.as_deref().unwrap_or("no reason given"),
+        request_id_suffix(.request_id)
+    )]
+    Forbidden {
+        reason: Option<String>,
+        request_id: Option<String>,
+    },
+    #[error("Device not found: {0}")]
+    DeviceNotFound(String),
+    #[error("Invalid proxy URI: {0}")]
+    InvalidProxy(String),
+    #[error("Invalid URI: {0}")]
+    InvalidUri(String),
+    #[error("Too many items for one request: {given}, at most {max}")]
+    TooManyItems { given: usize, max: usize },
+    #[error("Recommendations need between 1 and {max} seeds, got {given}")]
+    InvalidSeedCount { given: usize, max: usize },
+    #[error("Playlist was changed elsewhere, reload it and try again")]
+    StaleSnapshot,
+    #[error("Rate limited, retry after {retry_after}s")]
+    RateLimited { retry_after: u64 },
+    #[error("No network connection")]
+    Offline,
     #[error(transparent)]
-    ClientError(#[from] isahc::Error),
+    ClientError(isahc::Error),
     #[error(transparent)]
     IoError(#[from] std::io::Error),
     #[error(transparent)]
     CacheError(#[from] CacheError),
     #[error(transparent)]
     ParseError(#[from] serde_json::Error),
+    #[error("Unexpected response ({error}) near: {near}")]
+    UnexpectedResponse {
+        error: serde_json::Error,
+        near: String,
+    },
     #[error(transparent)]
     ConversionError(#[from] std::string::FromUtf8Error),
 }
 
+impl SpotifyApiError {
+    // Keeps the message of JSON error bodies, or the raw body if it isn't one
+    fn from_status(status: StatusCode, body: String, request_id: Option<String>) -> Self {
+        let message = SpotifyApiErrorBody::message_from(&body);
+        match status {
+            StatusCode::FORBIDDEN => Self::Forbidden {
+                reason: message,
+                request_id,
+            },
+            s => Self::BadStatus(s.as_u16(), message.unwrap_or(body), request_id),
+        }
+    }
+
+    // Some tokens don't let the API resolve market=from_token, it then complains about the market
+    fn is_market_error(&self) -> bool {
+        match self {
+            Self::BadStatus(400 | 404, message, _) => {
+                let message = message.to_lowercase();
+                message.contains("market") || message.contains("country")
+            }
+            _ => false,
+        }
+    }
+
+    pub fn is_missing_scope(&self) -> bool {
+        matches!(self, Self::Forbidden { reason: Some(reason), .. } if reason.to_lowercase().contains("scope"))
+    }
+}
+
+fn request_id_suffix(request_id: &Option<String>) -> String {
+    request_id
+        .as_ref()
+        .map(|id| format!(" (request id: {})", id))
+        .unwrap_or_default()
+}
+
+impl From<isahc::Error> for SpotifyApiError {
+    fn from(err: isahc::Error) -> Self {
+        match err.kind() {
+            ErrorKind::ConnectionFailed | ErrorKind::NameResolution => Self::Offline,
+            _ => Self::ClientError(err),
+        }
+    }
+}
+
+#[derive(Clone)]
+struct RawResponse {
+    status: StatusCode,
+    etag: Option<String>,
+    cache_control: CacheControl,
+    request_id: Option<String>,
+    body: String,
+}
+
+type PendingRequests = Mutex<HashMap<String, Vec<oneshot::Sender<RawResponse>>>>;
+
+// Removes an in-flight request once it completes, or when it is dropped before that,
+// in which case the requests waiting on it are woken up to send their own
+struct PendingRequest<'a> {
+    pending: &'a PendingRequests,
+    key: String,
+}
+
+impl<'a> PendingRequest<'a> {
+    fn complete(self, raw: &RawResponse) {
+        let waiters = self
+            .pending
+            .lock()
+            .ok()
+            .and_then(|mut pending| pending.remove(&self.key));
+        for waiter in waiters.unwrap_or_default() {
+            let _ = waiter.send(raw.clone());
+        }
+    }
+}
+
+impl<'a> Drop for PendingRequest<'a> {
+    fn drop(&mut self) {
+        if let Ok(mut pending) = self.pending.lock() {
+            pending.remove(&self.key);
+        }
+    }
+}
+
+// Called when the API rejects our token, should resolve to a fresh one if it can get one
+pub type OnTokenExpired = Arc<dyn Fn() -> BoxFuture<'static, Option<String>> + Send + Sync>;
+
+struct ApiToken {
+    value: String,
+    expires_at: Option<Instant>,
+}
+
 pub(crate) struct SpotifyClient {
-    token: Mutex<Option<String>>,
+    token: RwLock<Option<ApiToken>>,
+    on_token_expired: RwLock<Option<OnTokenExpired>>,
+    // waiting for whatever token is set next
+    token_waiters: Mutex<Vec<oneshot::Sender<String>>>,
+    market: Mutex<Option<String>>,
+    // subscription of the current user, as last read from their profile
+    product: Mutex<Option<String>>,
+    // country of the current user, used when the API can't work out the market from the token
+    detected_country: Mutex<Option<String>>,
     client: HttpClient,
+    max_retries: u32,
+    pending: PendingRequests,
+    // Latest known snapshot_id of each playlist we have loaded or edited
+    snapshots: Mutex<HashMap<String, String>>,
 }
 
 impl SpotifyClient {
     pub(crate) fn new() -> Self {
-        let mut builder = HttpClient::builder();
-        if cfg!(debug_assertions) {
+        let proxy = PROXY_ENV_VARS
+            .iter()
+            .find_map(|name| env::var(name).ok().filter(|value| !value.is_empty()));
+
+        match proxy.map(|proxy| Self::with_proxy(&proxy)) {
+            Some(Ok(client)) => client,
+            Some(Err(err)) => {
+                warn!("Ignoring proxy configuration: {}", err);
+                Self::with_timeouts(DEFAULT_CONNECT_TIMEOUT, DEFAULT_TIMEOUT)
+            }
+            None => Self::with_timeouts(DEFAULT_CONNECT_TIMEOUT, DEFAULT_TIMEOUT),
+        }
+    }
+
+    pub(crate) fn with_timeouts(connect: Duration, total: Duration) -> Self {
+        Self::build(connect, total, None)
+    }
+
+    pub(crate) fn with_proxy(proxy: &str) -> Result<Self, SpotifyApiError> {
+        let uri = Uri::from_str(proxy)
+            .ok()
+            .filter(|uri| uri.scheme().is_some() && uri.authority().is_some())
+            .ok_or_else(|| SpotifyApiError::InvalidProxy(proxy.to_string()))?;
+        Ok(Self::build(
+            DEFAULT_CONNECT_TIMEOUT,
+            DEFAULT_TIMEOUT,
+            Some(uri),
+        ))
+    }
+
+    fn build(connect: Duration, total: Duration, proxy: Option<Uri>) -> Self {
+        let mut builder = HttpClient::builder()
+            .default_header("User-Agent", USER_AGENT)
+            // bodies are decompressed before reaching us, text() decodes them as usual
+            .automatic_decompression(true)
+            .connect_timeout(connect)
+            .timeout(total)
+            .proxy(proxy);
+        if accept_invalid_certs() {
             builder = builder.ssl_options(isahc::config::SslOption::DANGER_ACCEPT_INVALID_CERTS);
         }
         let client = builder.build().unwrap();
         Self {
-            token: Mutex::new(None),
+            token: RwLock::new(None),
+            on_token_expired: RwLock::new(None),
+            token_waiters: Mutex::new(vec![]),
+            market: Mutex::new(None),
+            product: Mutex::new(None),
+            detected_country: Mutex::new(None),
             client,
+            max_retries: 0,
+            pending: Mutex::new(HashMap::new()),
+            snapshots: Mutex::new(HashMap::new()),
         }
     }
 
+    // Transient failures (5xx, network errors) of GET requests are retried up to this many times
+    pub(crate) fn with_max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
     pub(crate) fn request<T>(&self) -> SpotifyRequest<'_, (), T> {
         SpotifyRequest {
             client: self,
             request: Builder::new(),
             body: (),
+            invalid: None,
             _type: PhantomData,
         }
     }
 
     pub(crate) fn has_token(&self) -> bool {
-        self.token.lock().unwrap().is_some()
+        self.token.read().unwrap().is_some()
+    }
+
+    fn has_token_value(&self, value: &str) -> bool {
+        let token = self.token.read().unwrap();
+        token
+            .as_ref()
+            .map(|token| token.value == value)
+            .unwrap_or(false)
     }
 
     pub(crate) fn update_token(&self, new_token: String) {
-        if let Ok(mut token) = self.token.lock() {
-            *token = Some(new_token)
+        self.set_token(ApiToken {
+            value: new_token,
+            expires_at: None,
+        });
+    }
+
+    pub(crate) fn update_token_with_expiry(&self, new_token: String, expires_in_secs: u64) {
+        self.set_token(ApiToken {
+            value: new_token,
+            expires_at: Some(Instant::now() + Duration::from_secs(expires_in_secs)),
+        });
+    }
+
+    fn set_token(&self, new_token: ApiToken) {
+        let value = new_token.value.clone();
+        if let Ok(mut token) = self.token.write() {
+            *token = Some(new_token);
+        }
+        if let Ok(mut waiters) = self.token_waiters.lock() {
+            for waiter in waiters.drain(..) {
+                let _ = waiter.send(value.clone());
+            }
+        }
+    }
+
+    // Resolves with the next token set, or fails if the client goes away first
+    pub(crate) fn next_token(&self) -> oneshot::Receiver<String> {
+        let (sender, receiver) = oneshot::channel();
+        if let Ok(mut waiters) = self.token_waiters.lock() {
+            waiters.push(sender);
+        }
+        receiver
+    }
+
+    pub(crate) fn set_on_token_expired(&self, on_token_expired: Option<OnTokenExpired>) {
+        if let Ok(mut handler) = self.on_token_expired.write() {
+            *handler = on_token_expired
+        }
+    }
+
+    // True if a fresh token was obtained, in which case the failed request can be retried
+    async fn refresh_token(&self) -> bool {
+        let on_token_expired = self
+            .on_token_expired
+            .read()
+            .ok()
+            .and_then(|handler| handler.clone());
+        let new_token = match on_token_expired {
+            Some(on_token_expired) => on_token_expired().await,
+            None => None,
+        };
+        match new_token {
+            // the callback may have set it already, along with when it expires
+            Some(new_token) if self.has_token_value(&new_token) => true,
+            Some(new_token) => {
+                self.update_token(new_token);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn reauthenticated<B>(&self, mut request: Request<B>) -> Result<Request<B>, SpotifyApiError> {
+        let token = self.token.read().unwrap();
+        let token = token.as_ref().ok_or(SpotifyApiError::NoToken)?;
+        let value = HeaderValue::from_str(&format!("Bearer {}", token.value))
+            .map_err(|_| SpotifyApiError::InvalidToken)?;
+        request.headers_mut().insert("Authorization", value);
+        Ok(request)
+    }
+
+    // None if there is no token or we were not told when it expires
+    pub(crate) fn token_expires_in(&self) -> Option<Duration> {
+        let token = self.token.read().ok()?;
+        let expires_at = token.as_ref()?.expires_at?;
+        Some(expires_at.saturating_duration_since(Instant::now()))
+    }
+
+    // Country code used for catalog requests, `None` lets the API pick the one of the user
+    pub(crate) fn set_market(&self, code: Option<String>) {
+        if let Ok(mut market) = self.market.lock() {
+            *market = code
+        }
+    }
+
+    pub(crate) fn set_product(&self, new_product: Option<String>) {
+        if let Ok(mut product) = self.product.lock() {
+            *product = new_product
+        }
+    }
+
+    pub(crate) fn set_detected_country(&self, country: Option<String>) {
+        if let Ok(mut detected_country) = self.detected_country.lock() {
+            *detected_country = country
+        }
+    }
+
+    // Same request with the from_token market swapped for the country of the user's profile
+    fn with_detected_market<B>(&self, request: &Request<B>) -> Option<Request<B>>
+    where
+        B: Clone,
+    {
+        let country = self.detected_country.lock().ok()?.clone()?;
+        let uri = request.uri().to_string();
+        if !uri.contains("=from_token") {
+            return None;
+        }
+        let uri: Uri = uri
+            .replace("=from_token", &format!("={}", country))
+            .parse()
+            .ok()?;
+        let mut request = Self::copy_request(request);
+        *request.uri_mut() = uri;
+        Some(request)
+    }
+
+    // None until the current user has been fetched
+    pub(crate) fn is_premium(&self) -> Option<bool> {
+        self.product
+            .lock()
+            .ok()
+            .and_then(|product| product.as_ref().map(|product| product == "premium"))
+    }
+
+    fn market(&self) -> String {
+        self.market
+            .lock()
+            .ok()
+            .and_then(|market| market.clone())
+            .unwrap_or_else(|| "from_token".to_string())
+    }
+
+    pub(crate) fn playlist_snapshot(&self, id: &str) -> Option<String> {
+        self.snapshots
+            .lock()
+            .ok()
+            .and_then(|snapshots| snapshots.get(id).cloned())
+    }
+
+    // None forgets the snapshot, so that the next edit isn't tied to a version of the playlist
+    pub(crate) fn set_playlist_snapshot(&self, id: &str, snapshot_id: Option<String>) {
+        if let Ok(mut snapshots) = self.snapshots.lock() {
+            match snapshot_id {
+                Some(snapshot_id) => snapshots.insert(id.to_string(), snapshot_id),
+                None => snapshots.remove(id),
+            };
         }
     }
 
     fn clear_token(&self) {
-        if let Ok(mut token) = self.token.lock() {
+        if let Ok(mut token) = self.token.write() {
             *token = None
         }
     }
 
-    fn parse_cache_control(cache_control: &str) -> Option<u64> {
+    fn parse_cache_control(cache_control: &str) -> CacheControl {
         cache_control
             .split(',')
-            .find(|s| s.trim().starts_with("max-age="))
-            .and_then(|s| s.split('=').nth(1))
-            .and_then(|s| u64::from_str(s).ok())
+            .map(|s| s.trim().to_lowercase())
+            .fold(CacheControl::default(), |mut parsed, directive| {
+                match &directive[..] {
+                    "no-cache" => parsed.no_cache = true,
+                    "no-store" => parsed.no_store = true,
+                    "must-revalidate" => parsed.must_revalidate = true,
+                    d => {
+                        if let Some(max_age) = d.strip_prefix("max-age=") {
+                            parsed.max_age = u64::from_str(max_age).ok();
+                        }
+                    }
+                };
+                parsed
+            })
     }
 
-    async fn send_req<B, T>(
+    fn retry_after<T>(response: &Response<T>) -> Option<u64> {
+        response
+            .headers()
+            .get("retry-after")
+            .and_then(|header| header.to_str().ok())
+            .and_then(|s| u64::from_str(s.trim()).ok())
+    }
+
+    fn parse_retry_after(response: &Response<AsyncBody>) -> u64 {
+        Self::retry_after(response).unwrap_or(1)
+    }
+
+    fn copy_request<B>(request: &Request<B>) -> Request<B>
+    where
+        B: Clone,
+    {
+        let mut copy = Request::builder()
+            .method(request.method().clone())
+            .uri(request.uri().clone());
+        for (name, value) in request.headers() {
+            copy = copy.header(name, value);
+        }
+        copy.body(request.body().clone()).unwrap()
+    }
+
+    async fn send_async<B>(
         &self,
         request: Request<B>,
-    ) -> Result<SpotifyResponse<T>, SpotifyApiError>
+    ) -> Result<Response<AsyncBody>, SpotifyApiError>
+    where
+        B: Into<isahc::AsyncBody> + Clone,
+    {
+        let retry = Self::copy_request(&request);
+        let result = self.client.send_async(request).await?;
+        if result.status() != StatusCode::TOO_MANY_REQUESTS {
+            return Ok(result);
+        }
+
+        let retry_after = Self::parse_retry_after(&result);
+        if retry_after > MAX_RETRY_AFTER {
+            return Err(SpotifyApiError::RateLimited { retry_after });
+        }
+
+        debug!("Rate limited, retrying in {}s", retry_after);
+        async_std::task::sleep(Duration::from_secs(retry_after)).await;
+
+        let result = self.client.send_async(retry).await?;
+        match result.status() {
+            StatusCode::TOO_MANY_REQUESTS => Err(SpotifyApiError::RateLimited {
+                retry_after: Self::parse_retry_after(&result),
+            }),
+            _ => Ok(result),
+        }
+    }
+
+    fn backoff_delay(attempt: u32) -> Duration {
+        let delay = BACKOFF_BASE * 2u64.pow(attempt);
+        let jitter = rand::thread_rng().gen_range(0..=delay / 2);
+        Duration::from_millis(delay + jitter)
+    }
+
+    // A 503 may say when to come back, otherwise we back off exponentially.
+    // None if the server asks us to wait longer than we're willing to.
+    fn retry_delay<T>(response: Option<&Response<T>>, attempt: u32) -> Option<Duration> {
+        let retry_after = response
+            .filter(|response| response.status() == StatusCode::SERVICE_UNAVAILABLE)
+            .and_then(Self::retry_after);
+        match retry_after {
+            Some(retry_after) if retry_after > MAX_RETRY_AFTER => None,
+            Some(retry_after) => Some(Duration::from_secs(retry_after)),
+            None => Some(Self::backoff_delay(attempt)),
+        }
+    }
+
+    async fn send_with_retries<B>(
+        &self,
+        request: Request<B>,
+    ) -> Result<Response<AsyncBody>, SpotifyApiError>
+    where
+        B: Into<isahc::AsyncBody> + Clone,
+    {
+        // Only idempotent requests are retried, so that saves and playlist edits are never applied twice
+        let max_retries = if matches!(*request.method(), Method::GET | Method::HEAD) {
+            self.max_retries
+        } else {
+            0
+        };
+
+        let mut attempt = 0;
+        loop {
+            let result = self.send_async(Self::copy_request(&request)).await;
+            let transient = match &result {
+                Ok(response) => response.status().is_server_error(),
+                Err(SpotifyApiError::Offline) => true,
+                Err(SpotifyApiError::ClientError(e)) => e.is_network() || e.is_timeout(),
+                Err(_) => false,
+            };
+            if !transient || attempt >= max_retries {
+                return result;
+            }
+
+            let delay = match Self::retry_delay(result.as_ref().ok(), attempt) {
+                Some(delay) => delay,
+                None => return result,
+            };
+            debug!("Request failed, retrying in {}ms", delay.as_millis());
+            async_std::task::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+
+    fn request_id<T>(response: &Response<T>) -> Option<String> {
+        REQUEST_ID_HEADERS.iter().find_map(|name| {
+            response
+                .headers()
+                .get(*name)
+                .and_then(|header| header.to_str().ok())
+                .map(|s| s.to_owned())
+        })
+    }
+
+    fn resource_info<T>(response: &Response<T>) -> ResourceInfo {
+        let header = |name: &str| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|header| header.to_str().ok())
+                .map(|s| s.to_owned())
+        };
+        ResourceInfo {
+            content_length: header("content-length").and_then(|s| u64::from_str(s.trim()).ok()),
+            etag: header("etag"),
+        }
+    }
+
+    // Identical concurrent GETs (same uri and ETag) are sent only once
+    fn coalescing_key<B>(request: &Request<B>) -> Option<String> {
+        if request.method() != Method::GET {
+            return None;
+        }
+        let etag = request
+            .headers()
+            .get("If-None-Match")
+            .and_then(|header| header.to_str().ok())
+            .unwrap_or("");
+        Some(format!("{} {} {}", request.method(), request.uri(), etag))
+    }
+
+    async fn fetch_raw<B>(&self, request: Request<B>) -> Result<RawResponse, SpotifyApiError>
     where
-        B: Into<isahc::AsyncBody>,
+        B: Into<isahc::AsyncBody> + Clone,
     {
-        let mut result = self.client.send_async(request).await?;
+        let mut result = self.send_with_retries(request).await?;
 
         let etag = result
             .headers()
@@ -236,13 +958,89 @@ impl SpotifyClient {
             .headers()
             .get("cache-control")
             .and_then(|header| header.to_str().ok())
-            .and_then(Self::parse_cache_control);
+            .map(Self::parse_cache_control)
+            .unwrap_or_default();
 
-        match result.status() {
+        let request_id = Self::request_id(&result);
+
+        let status = result.status();
+        let body = match result.text().await {
+            Ok(body) => body,
+            Err(e) if status.is_success() => return Err(e.into()),
+            Err(_) => "(no details available)".to_string(),
+        };
+
+        Ok(RawResponse {
+            status,
+            etag,
+            cache_control,
+            request_id,
+            body,
+        })
+    }
+
+    async fn fetch_coalesced<B>(
+        &self,
+        key: String,
+        request: Request<B>,
+    ) -> Result<RawResponse, SpotifyApiError>
+    where
+        B: Into<isahc::AsyncBody> + Clone,
+    {
+        let waiter = self.pending.lock().ok().and_then(|mut pending| {
+            if let Some(waiters) = pending.get_mut(&key) {
+                let (sender, receiver) = oneshot::channel();
+                waiters.push(sender);
+                Some(receiver)
+            } else {
+                pending.insert(key.clone(), vec![]);
+                None
+            }
+        });
+
+        if let Some(waiter) = waiter {
+            return match waiter.await {
+                Ok(raw) => Ok(raw),
+                // the request we were waiting on failed or got cancelled, try on our own
+                Err(_) => self.fetch_raw(request).await,
+            };
+        }
+
+        let pending = PendingRequest {
+            pending: &self.pending,
+            key,
+        };
+        let result = self.fetch_raw(request).await;
+        if let Ok(ref raw) = result {
+            pending.complete(raw);
+        }
+        result
+    }
+
+    async fn send_req<B, T>(
+        &self,
+        request: Request<B>,
+    ) -> Result<SpotifyResponse<T>, SpotifyApiError>
+    where
+        B: Into<isahc::AsyncBody> + Clone,
+    {
+        let RawResponse {
+            status,
+            etag,
+            cache_control,
+            request_id,
+            body,
+        } = match Self::coalescing_key(&request) {
+            Some(key) => self.fetch_coalesced(key, request).await?,
+            None => self.fetch_raw(request).await?,
+        };
+
+        match status {
             s if s.is_success() => Ok(SpotifyResponse {
-                kind: SpotifyResponseKind::Ok(result.text().await?, PhantomData),
-                max_age: cache_control.unwrap_or(10),
+                kind: SpotifyResponseKind::Ok(body, PhantomData),
+                cache_control,
                 etag,
+                received_at: Instant::now(),
             }),
             StatusCode::UNAUTHORIZED => {
                 self.clear_token();
@@ -250,24 +1048,42 @@ impl SpotifyClient {
             }
             StatusCode::NOT_MODIFIED => Ok(SpotifyResponse {
                 kind: SpotifyResponseKind::NotModified,
-                max_age: cache_control.unwrap_or(10),
+                cache_control,
                 etag,
+                received_at: Instant::now(),
             }),
-            s => Err(SpotifyApiError::BadStatus(
-                s.as_u16(),
-                result
-                    .text()
-                    .await
-                    .unwrap_or_else(|_| "(no details available)".to_string()),
+            s => Err(SpotifyApiError::from_status(s, body, request_id)),
+        }
+    }
+
+    // Like send_req_no_response, but keeps the headers and never reads a body, HEAD responses have none
+    async fn send_req_head<B>(&self, request: Request<B>) -> Result<ResourceInfo, SpotifyApiError>
+    where
+        B: Into<isahc::AsyncBody> + Clone,
+    {
+        let is_spotify = request.uri().host() == Some(SPOTIFY_HOST);
+        let result = self.send_with_retries(request).await?;
+        match result.status() {
+            StatusCode::UNAUTHORIZED if is_spotify => {
+                self.clear_token();
+                Err(SpotifyApiError::InvalidToken)
+            }
+            s if s.is_success() || s == StatusCode::NOT_MODIFIED => {
+                Ok(Self::resource_info(&result))
+            }
+            s => Err(SpotifyApiError::from_status(
+                s,
+                "(no details available)".to_string(),
+                Self::request_id(&result),
             )),
         }
     }
 
     async fn send_req_no_response<B>(&self, request: Request<B>) -> Result<(), SpotifyApiError>
     where
-        B: Into<isahc::AsyncBody>,
+        B: Into<isahc::AsyncBody> + Clone,
     {
-        let mut result = self.client.send_async(request).await?;
+        let mut result = self.send_with_retries(request).await?;
         match result.status() {
             StatusCode::UNAUTHORIZED => {
                 self.clear_token();
@@ -275,19 +1091,34 @@ impl SpotifyClient {
             }
             StatusCode::NOT_MODIFIED => Ok(()),
             s if s.is_success() => Ok(()),
-            s => Err(SpotifyApiError::BadStatus(
-                s.as_u16(),
-                result
-                    .text()
-                    .await
-                    .unwrap_or_else(|_| "(no details available)".to_string()),
-            )),
+            s => {
+                let request_id = Self::request_id(&result);
+                Err(SpotifyApiError::from_status(
+                    s,
+                    result
+                        .text()
+                        .await
+                        .unwrap_or_else(|_| "(no details available)".to_string()),
+                    request_id,
+                ))
+            }
         }
     }
 }
 
 impl SpotifyClient {
-    pub(crate) fn get_artist(&self, id: &str) -> SpotifyRequest<'_, (), Artist> {
+    // Takes a full url, unlike the other requests which are relative to the API host
+    pub(crate) fn head(
+        &self,
+        uri: &str,
+    ) -> Result<SpotifyRequest<'_, (), ResourceInfo>, SpotifyApiError> {
+        let uri = Uri::from_str(uri).map_err(|_| SpotifyApiError::InvalidUri(uri.to_owned()))?;
+        let mut request = self.request().method(Method::HEAD);
+        request.request = request.request.uri(uri);
+        Ok(request)
+    }
+
+    pub(crate) fn get_artist(&self, id: &str) -> SpotifyRequest<'_, (), Artist> {
         self.request()
             .method(Method::GET)
             .uri(format!("/v1/artists/{}", id), None)
@@ -296,12 +1127,18 @@ impl SpotifyClient {
     pub(crate) fn get_artist_albums(
         &self,
         id: &str,
+        groups: &[AlbumType],
         offset: usize,
         limit: usize,
     ) -> SpotifyRequest<'_, (), Page<Album>> {
+        let groups = groups
+            .iter()
+            .map(|group| group.into_string())
+            .collect::<Vec<&str>>()
+            .join(",");
         let query = make_query_params()
-            .append_pair("include_groups", "album,single")
-            .append_pair("country", "from_token")
+            .append_pair("include_groups", &groups)
+            .append_pair("country", &self.market())
             .append_pair("offset", &offset.to_string()[..])
             .append_pair("limit", &limit.to_string()[..])
             .finish();
@@ -311,9 +1148,63 @@ impl SpotifyClient {
             .uri(format!("/v1/artists/{}/albums", id), Some(&query))
     }
 
+    pub(crate) fn follow_artists(&self, ids: &[&str]) -> SpotifyRequest<'_, (), ()> {
+        let query = make_query_params()
+            .append_pair("type", "artist")
+            .append_pair("ids", &ids.join(","))
+            .finish();
+
+        self.request()
+            .at_most(ids.len(), MAX_IDS_PER_REQUEST)
+            .method(Method::PUT)
+            .uri("/v1/me/following".to_string(), Some(&query))
+    }
+
+    pub(crate) fn unfollow_artists(&self, ids: &[&str]) -> SpotifyRequest<'_, (), ()> {
+        let query = make_query_params()
+            .append_pair("type", "artist")
+            .append_pair("ids", &ids.join(","))
+            .finish();
+
+        self.request()
+            .at_most(ids.len(), MAX_IDS_PER_REQUEST)
+            .method(Method::DELETE)
+            .uri("/v1/me/following".to_string(), Some(&query))
+    }
+
+    pub(crate) fn get_followed_artists(
+        &self,
+        after: Option<&str>,
+        limit: usize,
+    ) -> SpotifyRequest<'_, (), FollowedArtists> {
+        let mut query = make_query_params();
+        query
+            .append_pair("type", "artist")
+            .append_pair("limit", &limit.to_string()[..]);
+        if let Some(after) = after {
+            query.append_pair("after", after);
+        }
+
+        self.request()
+            .method(Method::GET)
+            .uri("/v1/me/following".to_string(), Some(&query.finish()))
+    }
+
+    pub(crate) fn are_artists_followed(&self, ids: &[&str]) -> SpotifyRequest<'_, (), Vec<bool>> {
+        let query = make_query_params()
+            .append_pair("type", "artist")
+            .append_pair("ids", &ids.join(","))
+            .finish();
+
+        self.request()
+            .at_most(ids.len(), MAX_IDS_PER_REQUEST)
+            .method(Method::GET)
+            .uri("/v1/me/following/contains".to_string(), Some(&query))
+    }
+
     pub(crate) fn get_artist_top_tracks(&self, id: &str) -> SpotifyRequest<'_, (), TopTracks> {
         let query = make_query_params()
-            .append_pair("market", "from_token")
+            .append_pair("market", &self.market())
             .finish();
 
         self.request()
@@ -321,13 +1212,32 @@ impl SpotifyClient {
             .uri(format!("/v1/artists/{}/top-tracks", id), Some(&query))
     }
 
-    pub(crate) fn is_album_saved(&self, id: &str) -> SpotifyRequest<'_, (), Vec<bool>> {
-        let query = make_query_params().append_pair("ids", id).finish();
+    pub(crate) fn get_related_artists(&self, id: &str) -> SpotifyRequest<'_, (), RelatedArtists> {
+        self.request()
+            .method(Method::GET)
+            .uri(format!("/v1/artists/{}/related-artists", id), None)
+    }
+
+    pub(crate) fn are_albums_saved(&self, ids: &[&str]) -> SpotifyRequest<'_, (), Vec<bool>> {
+        let query = make_query_params()
+            .append_pair("ids", &ids.join(","))
+            .finish();
         self.request()
+            .at_most(ids.len(), MAX_ALBUM_IDS_PER_REQUEST)
             .method(Method::GET)
             .uri("/v1/me/albums/contains".to_string(), Some(&query))
     }
 
+    pub(crate) fn are_tracks_saved(&self, ids: &[&str]) -> SpotifyRequest<'_, (), Vec<bool>> {
+        let query = make_query_params()
+            .append_pair("ids", &ids.join(","))
+            .finish();
+        self.request()
+            .at_most(ids.len(), MAX_IDS_PER_REQUEST)
+            .method(Method::GET)
+            .uri("/v1/me/tracks/contains".to_string(), Some(&query))
+    }
+
     pub(crate) fn save_album(&self, id: &str) -> SpotifyRequest<'_, (), ()> {
         let query = make_query_params().append_pair("ids", id).finish();
         self.request()
@@ -335,11 +1245,14 @@ impl SpotifyClient {
             .uri("/v1/me/albums".to_string(), Some(&query))
     }
 
-    pub(crate) fn save_tracks(&self, ids: Vec<String>) -> SpotifyRequest<'_, Vec<u8>, ()> {
+    pub(crate) fn save_tracks(&self, ids: &[&str]) -> SpotifyRequest<'_, (), ()> {
+        let query = make_query_params()
+            .append_pair("ids", &ids.join(","))
+            .finish();
         self.request()
+            .at_most(ids.len(), MAX_IDS_PER_REQUEST)
             .method(Method::PUT)
-            .uri("/v1/me/tracks".to_string(), None)
-            .json_body(Ids { ids })
+            .uri("/v1/me/tracks".to_string(), Some(&query))
     }
 
     pub(crate) fn remove_saved_album(&self, id: &str) -> SpotifyRequest<'_, (), ()> {
@@ -349,11 +1262,14 @@ impl SpotifyClient {
             .uri("/v1/me/albums".to_string(), Some(&query))
     }
 
-    pub(crate) fn remove_saved_tracks(&self, ids: Vec<String>) -> SpotifyRequest<'_, Vec<u8>, ()> {
+    pub(crate) fn remove_saved_tracks(&self, ids: &[&str]) -> SpotifyRequest<'_, (), ()> {
+        let query = make_query_params()
+            .append_pair("ids", &ids.join(","))
+            .finish();
         self.request()
+            .at_most(ids.len(), MAX_IDS_PER_REQUEST)
             .method(Method::DELETE)
-            .uri("/v1/me/tracks".to_string(), None)
-            .json_body(Ids { ids })
+            .uri("/v1/me/tracks".to_string(), Some(&query))
     }
 
     pub(crate) fn get_album(&self, id: &str) -> SpotifyRequest<'_, (), FullAlbum> {
@@ -378,12 +1294,108 @@ impl SpotifyClient {
             .uri(format!("/v1/albums/{}/tracks", id), Some(&query))
     }
 
+    pub(crate) fn get_track(&self, id: &str) -> SpotifyRequest<'_, (), TrackItem> {
+        let query = make_query_params()
+            .append_pair("market", &self.market())
+            .finish();
+
+        self.request()
+            .method(Method::GET)
+            .uri(format!("/v1/tracks/{}", id), Some(&query))
+    }
+
+    pub(crate) fn get_audio_features(&self, id: &str) -> SpotifyRequest<'_, (), AudioFeatures> {
+        self.request()
+            .method(Method::GET)
+            .uri(format!("/v1/audio-features/{}", id), None)
+    }
+
+    pub(crate) fn get_several_tracks(&self, ids: &[&str]) -> SpotifyRequest<'_, (), SeveralTracks> {
+        let query = make_query_params()
+            .append_pair("ids", &ids.join(","))
+            .append_pair("market", &self.market())
+            .finish();
+
+        self.request()
+            .at_most(ids.len(), MAX_IDS_PER_REQUEST)
+            .method(Method::GET)
+            .uri("/v1/tracks".to_string(), Some(&query))
+    }
+
+    pub(crate) fn get_several_albums(&self, ids: &[&str]) -> SpotifyRequest<'_, (), SeveralAlbums> {
+        let query = make_query_params()
+            .append_pair("ids", &ids.join(","))
+            .append_pair("market", &self.market())
+            .finish();
+
+        self.request()
+            .at_most(ids.len(), MAX_ALBUM_IDS_PER_REQUEST)
+            .method(Method::GET)
+            .uri("/v1/albums".to_string(), Some(&query))
+    }
+
+    pub(crate) fn get_several_artists(
+        &self,
+        ids: &[&str],
+    ) -> SpotifyRequest<'_, (), SeveralArtists> {
+        let query = make_query_params()
+            .append_pair("ids", &ids.join(","))
+            .finish();
+
+        self.request()
+            .at_most(ids.len(), MAX_IDS_PER_REQUEST)
+            .method(Method::GET)
+            .uri("/v1/artists".to_string(), Some(&query))
+    }
+
+    pub(crate) fn get_show(&self, id: &str) -> SpotifyRequest<'_, (), Show> {
+        let query = make_query_params()
+            .append_pair("market", &self.market())
+            .finish();
+
+        self.request()
+            .method(Method::GET)
+            .uri(format!("/v1/shows/{}", id), Some(&query))
+    }
+
+    pub(crate) fn save_shows(&self, ids: &[&str]) -> SpotifyRequest<'_, (), ()> {
+        let query = make_query_params()
+            .append_pair("ids", &ids.join(","))
+            .finish();
+        self.request()
+            .at_most(ids.len(), MAX_IDS_PER_REQUEST)
+            .method(Method::PUT)
+            .uri("/v1/me/shows".to_string(), Some(&query))
+    }
+
+    pub(crate) fn remove_saved_shows(&self, ids: &[&str]) -> SpotifyRequest<'_, (), ()> {
+        let query = make_query_params()
+            .append_pair("ids", &ids.join(","))
+            .finish();
+        self.request()
+            .at_most(ids.len(), MAX_IDS_PER_REQUEST)
+            .method(Method::DELETE)
+            .uri("/v1/me/shows".to_string(), Some(&query))
+    }
+
+    pub(crate) fn get_episode(&self, id: &str) -> SpotifyRequest<'_, (), Episode> {
+        let query = make_query_params()
+            .append_pair("market", &self.market())
+            .finish();
+
+        self.request()
+            .method(Method::GET)
+            .uri(format!("/v1/episodes/{}", id), Some(&query))
+    }
+
     pub(crate) fn get_playlist(&self, id: &str) -> SpotifyRequest<'_, (), Playlist> {
         let query = make_query_params()
             .append_pair(
                 "fields",
-                "id,name,images,owner,tracks(total,items(is_local,track(name,id,uri,duration_ms,artists(name,id),album(name,id,images,artists))))",
+                "id,name,images,owner,snapshot_id,followers(total),external_urls,tracks(total,items(added_at,added_by(id),is_local,track(name,id,uri,external_urls,duration_ms,explicit,is_playable,preview_url,artists(name,id),album(name,id,images,artists),images,show(name,id))))",
             )
+            .append_pair("additional_types", "track,episode")
+            .append_pair("market", &self.market())
             .finish();
         self.request()
             .method(Method::GET)
@@ -397,6 +1409,8 @@ impl SpotifyClient {
         limit: usize,
     ) -> SpotifyRequest<'_, (), Page<PlaylistTrack>> {
         let query = make_query_params()
+            .append_pair("additional_types", "track,episode")
+            .append_pair("market", &self.market())
             .append_pair("offset", &offset.to_string()[..])
             .append_pair("limit", &limit.to_string()[..])
             .finish();
@@ -406,12 +1420,82 @@ impl SpotifyClient {
             .uri(format!("/v1/playlists/{}/tracks", id), Some(&query))
     }
 
+    pub(crate) fn create_playlist(
+        &self,
+        user_id: &str,
+        name: &str,
+        public: bool,
+    ) -> SpotifyRequest<'_, Vec<u8>, Playlist> {
+        let user_id = utf8_percent_encode(user_id, PATH_ENCODE_SET);
+        self.request()
+            .method(Method::POST)
+            .uri(format!("/v1/users/{}/playlists", user_id), None)
+            .json_body(PlaylistDetails {
+                name: Some(name.to_string()),
+                public: Some(public),
+                ..Default::default()
+            })
+    }
+
+    pub(crate) fn update_playlist_details(
+        &self,
+        id: &str,
+        name: Option<&str>,
+        description: Option<&str>,
+        public: Option<bool>,
+    ) -> SpotifyRequest<'_, Vec<u8>, ()> {
+        self.request()
+            .method(Method::PUT)
+            .uri(format!("/v1/playlists/{}", id), None)
+            .json_body(PlaylistDetails {
+                name: name.map(str::to_string),
+                description: description.map(str::to_string),
+                public,
+            })
+    }
+
+    pub(crate) fn follow_playlist(
+        &self,
+        id: &str,
+        public: bool,
+    ) -> SpotifyRequest<'_, Vec<u8>, ()> {
+        self.request()
+            .method(Method::PUT)
+            .uri(format!("/v1/playlists/{}/followers", id), None)
+            .json_body(PlaylistFollow { public })
+    }
+
+    pub(crate) fn unfollow_playlist(&self, id: &str) -> SpotifyRequest<'_, (), ()> {
+        self.request()
+            .method(Method::DELETE)
+            .uri(format!("/v1/playlists/{}/followers", id), None)
+    }
+
+    pub(crate) fn do_users_follow_playlist(
+        &self,
+        playlist_id: &str,
+        user_ids: &[&str],
+    ) -> SpotifyRequest<'_, (), Vec<bool>> {
+        let query = make_query_params()
+            .append_pair("ids", &user_ids.join(","))
+            .finish();
+
+        self.request()
+            .at_most(user_ids.len(), MAX_PLAYLIST_FOLLOWERS_PER_REQUEST)
+            .method(Method::GET)
+            .uri(
+                format!("/v1/playlists/{}/followers/contains", playlist_id),
+                Some(&query),
+            )
+    }
+
     pub(crate) fn add_to_playlist(
         &self,
         playlist: &str,
         uris: Vec<String>,
-    ) -> SpotifyRequest<'_, Vec<u8>, ()> {
+    ) -> SpotifyRequest<'_, Vec<u8>, SnapshotResponse> {
         self.request()
+            .at_most(uris.len(), MAX_PLAYLIST_TRACKS_PER_REQUEST)
             .method(Method::POST)
             .uri(format!("/v1/playlists/{}/tracks", playlist), None)
             .json_body(Uris { uris })
@@ -421,11 +1505,37 @@ impl SpotifyClient {
         &self,
         playlist: &str,
         uris: Vec<String>,
-    ) -> SpotifyRequest<'_, Vec<u8>, ()> {
+        snapshot_id: Option<String>,
+    ) -> SpotifyRequest<'_, Vec<u8>, SnapshotResponse> {
+        let count = uris.len();
+        let tracks = uris.into_iter().map(|uri| TrackUri { uri }).collect();
         self.request()
+            .at_most(count, MAX_PLAYLIST_TRACKS_PER_REQUEST)
             .method(Method::DELETE)
             .uri(format!("/v1/playlists/{}/tracks", playlist), None)
-            .json_body(Uris { uris })
+            .json_body(Tracks {
+                tracks,
+                snapshot_id,
+            })
+    }
+
+    pub(crate) fn reorder_playlist_tracks(
+        &self,
+        playlist: &str,
+        range_start: usize,
+        insert_before: usize,
+        range_length: usize,
+        snapshot_id: Option<String>,
+    ) -> SpotifyRequest<'_, Vec<u8>, SnapshotResponse> {
+        self.request()
+            .method(Method::PUT)
+            .uri(format!("/v1/playlists/{}/tracks", playlist), None)
+            .json_body(PlaylistReorder {
+                range_start,
+                insert_before,
+                range_length,
+                snapshot_id,
+            })
     }
 
     pub(crate) fn get_saved_albums(
@@ -458,6 +1568,38 @@ impl SpotifyClient {
             .uri("/v1/me/tracks".to_string(), Some(&query))
     }
 
+    pub(crate) fn get_recently_played(
+        &self,
+        before: Option<&str>,
+        limit: usize,
+    ) -> SpotifyRequest<'_, (), CursorPage<PlayHistory>> {
+        let mut query = make_query_params();
+        query.append_pair("limit", &limit.to_string()[..]);
+        if let Some(before) = before {
+            query.append_pair("before", before);
+        }
+
+        self.request().method(Method::GET).uri(
+            "/v1/me/player/recently-played".to_string(),
+            Some(&query.finish()),
+        )
+    }
+
+    pub(crate) fn get_saved_shows(
+        &self,
+        offset: usize,
+        limit: usize,
+    ) -> SpotifyRequest<'_, (), Page<SavedShow>> {
+        let query = make_query_params()
+            .append_pair("offset", &offset.to_string()[..])
+            .append_pair("limit", &limit.to_string()[..])
+            .finish();
+
+        self.request()
+            .method(Method::GET)
+            .uri("/v1/me/shows".to_string(), Some(&query))
+    }
+
     pub(crate) fn get_saved_playlists(
         &self,
         offset: usize,
@@ -473,71 +1615,1044 @@ impl SpotifyClient {
             .uri("/v1/me/playlists".to_string(), Some(&query))
     }
 
-    pub(crate) fn search(
+    pub(crate) fn get_top_tracks(
         &self,
-        query: String,
+        time_range: TimeRange,
         offset: usize,
         limit: usize,
-    ) -> SpotifyRequest<'_, (), RawSearchResults> {
-        let query = SearchQuery {
-            query,
-            types: vec![SearchType::Album, SearchType::Artist],
-            limit,
-            offset,
-        };
+    ) -> SpotifyRequest<'_, (), Page<TrackItem>> {
+        self.get_top_items("tracks", time_range, offset, limit)
+    }
+
+    pub(crate) fn get_top_artists(
+        &self,
+        time_range: TimeRange,
+        offset: usize,
+        limit: usize,
+    ) -> SpotifyRequest<'_, (), Page<Artist>> {
+        self.get_top_items("artists", time_range, offset, limit)
+    }
+
+    fn get_top_items<T>(
+        &self,
+        kind: &str,
+        time_range: TimeRange,
+        offset: usize,
+        limit: usize,
+    ) -> SpotifyRequest<'_, (), Page<T>> {
+        let query = make_query_params()
+            .append_pair("time_range", time_range.into_string())
+            .append_pair("offset", &offset.to_string()[..])
+            .append_pair(
+                "limit",
+                &limit.min(MAX_TOP_ITEMS_PER_REQUEST).to_string()[..],
+            )
+            .finish();
 
         self.request()
             .method(Method::GET)
-            .uri("/v1/search".to_string(), Some(&query.into_query_string()))
+            .uri(format!("/v1/me/top/{}", kind), Some(&query))
     }
 
-    pub(crate) fn get_user(&self, id: &str) -> SpotifyRequest<'_, (), User> {
-        let id = utf8_percent_encode(id, PATH_ENCODE_SET);
+    pub(crate) fn get_new_releases(
+        &self,
+        offset: usize,
+        limit: usize,
+    ) -> SpotifyRequest<'_, (), NewReleases> {
+        let query = make_query_params()
+            .append_pair("country", &self.market())
+            .append_pair("offset", &offset.to_string()[..])
+            .append_pair("limit", &limit.to_string()[..])
+            .finish();
+
         self.request()
             .method(Method::GET)
-            .uri(format!("/v1/users/{}", id), None)
+            .uri("/v1/browse/new-releases".to_string(), Some(&query))
     }
 
-    pub(crate) fn get_user_playlists(
+    pub(crate) fn get_featured_playlists(
         &self,
-        id: &str,
         offset: usize,
         limit: usize,
-    ) -> SpotifyRequest<'_, (), Page<Playlist>> {
-        let id = utf8_percent_encode(id, PATH_ENCODE_SET);
+    ) -> SpotifyRequest<'_, (), FeaturedPlaylists> {
         let query = make_query_params()
+            .append_pair("country", &self.market())
             .append_pair("offset", &offset.to_string()[..])
             .append_pair("limit", &limit.to_string()[..])
             .finish();
 
         self.request()
             .method(Method::GET)
-            .uri(format!("/v1/users/{}/playlists", id), Some(&query))
+            .uri("/v1/browse/featured-playlists".to_string(), Some(&query))
     }
-}
 
-#[cfg(test)]
-pub mod tests {
+    pub(crate) fn get_categories(
+        &self,
+        offset: usize,
+        limit: usize,
+    ) -> SpotifyRequest<'_, (), Categories> {
+        let query = make_query_params()
+            .append_pair("offset", &offset.to_string()[..])
+            .append_pair("limit", &limit.to_string()[..])
+            .finish();
 
-    use super::*;
+        self.request()
+            .method(Method::GET)
+            .uri("/v1/browse/categories".to_string(), Some(&query))
+    }
 
-    #[test]
-    fn test_username_encoding() {
-        let username = "anna.lafuente❤";
-        let client = SpotifyClient::new();
-        let req = client.get_user(username);
-        assert_eq!(
-            req.request
-                .uri_ref()
-                .and_then(|u| u.path_and_query())
-                .unwrap()
-                .as_str(),
-            "/v1/users/anna.lafuente%E2%9D%A4"
-        );
+    pub(crate) fn get_category_playlists(
+        &self,
+        id: &str,
+        offset: usize,
+        limit: usize,
+    ) -> SpotifyRequest<'_, (), CategoryPlaylists> {
+        let id = utf8_percent_encode(id, PATH_ENCODE_SET);
+        let query = make_query_params()
+            .append_pair("offset", &offset.to_string()[..])
+            .append_pair("limit", &limit.to_string()[..])
+            .finish();
+
+        self.request().method(Method::GET).uri(
+            format!("/v1/browse/categories/{}/playlists", id),
+            Some(&query),
+        )
     }
 
-    #[test]
-    fn test_search_query() {
+    pub(crate) fn search(
+        &self,
+        query: String,
+        types: &[SearchType],
+        offset: usize,
+        limit: usize,
+    ) -> SpotifyRequest<'_, (), RawSearchResults> {
+        let query = SearchQuery {
+            query,
+            types: types.to_vec(),
+            limit,
+            offset,
+        };
+
+        self.request().method(Method::GET).uri(
+            "/v1/search".to_string(),
+            Some(&query.into_query_string_for_market(&self.market())),
+        )
+    }
+
+    pub(crate) fn get_devices(&self) -> SpotifyRequest<'_, (), Devices> {
+        self.request()
+            .method(Method::GET)
+            .uri("/v1/me/player/devices".to_string(), None)
+    }
+
+    pub(crate) fn get_playback_state(&self) -> SpotifyRequest<'_, (), Option<PlaybackState>> {
+        self.request()
+            .method(Method::GET)
+            .uri("/v1/me/player".to_string(), None)
+    }
+
+    // Cheaper than get_playback_state when only the playing item is needed
+    pub(crate) fn get_currently_playing(&self) -> SpotifyRequest<'_, (), Option<CurrentlyPlaying>> {
+        self.request()
+            .method(Method::GET)
+            .uri("/v1/me/player/currently-playing".to_string(), None)
+    }
+
+    pub(crate) fn get_queue(&self) -> SpotifyRequest<'_, (), PlayerQueue> {
+        self.request()
+            .method(Method::GET)
+            .uri("/v1/me/player/queue".to_string(), None)
+    }
+
+    pub(crate) fn add_to_queue(
+        &self,
+        uri: &str,
+        device_id: Option<&str>,
+    ) -> SpotifyRequest<'_, (), ()> {
+        let mut query = make_query_params();
+        query.append_pair("uri", uri);
+        if let Some(device_id) = device_id {
+            query.append_pair("device_id", device_id);
+        }
+
+        self.request()
+            .method(Method::POST)
+            .uri("/v1/me/player/queue".to_string(), Some(&query.finish()))
+    }
+
+    pub(crate) fn transfer_playback(
+        &self,
+        device_id: &str,
+        play: bool,
+    ) -> SpotifyRequest<'_, Vec<u8>, ()> {
+        self.request()
+            .method(Method::PUT)
+            .uri("/v1/me/player".to_string(), None)
+            .json_body(PlaybackTransfer {
+                device_ids: vec![device_id.to_string()],
+                play,
+            })
+    }
+
+    pub(crate) fn remote_play(&self, device_id: Option<&str>) -> SpotifyRequest<'_, (), ()> {
+        self.player_command(Method::PUT, "play", device_id)
+    }
+
+    pub(crate) fn play_context(
+        &self,
+        device_id: Option<&str>,
+        target: PlaybackTarget,
+    ) -> SpotifyRequest<'_, Vec<u8>, ()> {
+        self.remote_play(device_id)
+            .json_body(StartPlayback::from(target))
+    }
+
+    pub(crate) fn remote_pause(&self, device_id: Option<&str>) -> SpotifyRequest<'_, (), ()> {
+        self.player_command(Method::PUT, "pause", device_id)
+    }
+
+    pub(crate) fn remote_next(&self, device_id: Option<&str>) -> SpotifyRequest<'_, (), ()> {
+        self.player_command(Method::POST, "next", device_id)
+    }
+
+    pub(crate) fn remote_previous(&self, device_id: Option<&str>) -> SpotifyRequest<'_, (), ()> {
+        self.player_command(Method::POST, "previous", device_id)
+    }
+
+    pub(crate) fn set_volume(
+        &self,
+        percent: u8,
+        device_id: Option<&str>,
+    ) -> SpotifyRequest<'_, (), ()> {
+        let mut query = make_query_params();
+        query.append_pair("volume_percent", &percent.min(100).to_string()[..]);
+        if let Some(device_id) = device_id {
+            query.append_pair("device_id", device_id);
+        }
+
+        self.request()
+            .method(Method::PUT)
+            .uri("/v1/me/player/volume".to_string(), Some(&query.finish()))
+    }
+
+    pub(crate) fn set_repeat(
+        &self,
+        mode: RepeatMode,
+        device_id: Option<&str>,
+    ) -> SpotifyRequest<'_, (), ()> {
+        let mut query = make_query_params();
+        query.append_pair("state", &mode.to_string());
+        if let Some(device_id) = device_id {
+            query.append_pair("device_id", device_id);
+        }
+
+        self.request()
+            .method(Method::PUT)
+            .uri("/v1/me/player/repeat".to_string(), Some(&query.finish()))
+    }
+
+    pub(crate) fn set_shuffle(
+        &self,
+        state: ShuffleState,
+        device_id: Option<&str>,
+    ) -> SpotifyRequest<'_, (), ()> {
+        let mut query = make_query_params();
+        query.append_pair("state", &state.to_string());
+        if let Some(device_id) = device_id {
+            query.append_pair("device_id", device_id);
+        }
+
+        self.request()
+            .method(Method::PUT)
+            .uri("/v1/me/player/shuffle".to_string(), Some(&query.finish()))
+    }
+
+    pub(crate) fn seek(
+        &self,
+        position_ms: u32,
+        device_id: Option<&str>,
+    ) -> SpotifyRequest<'_, (), ()> {
+        let mut query = make_query_params();
+        query.append_pair("position_ms", &position_ms.to_string()[..]);
+        if let Some(device_id) = device_id {
+            query.append_pair("device_id", device_id);
+        }
+
+        self.request()
+            .method(Method::PUT)
+            .uri("/v1/me/player/seek".to_string(), Some(&query.finish()))
+    }
+
+    fn player_command(
+        &self,
+        method: Method,
+        command: &str,
+        device_id: Option<&str>,
+    ) -> SpotifyRequest<'_, (), ()> {
+        let query = device_id.map(|device_id| {
+            make_query_params()
+                .append_pair("device_id", device_id)
+                .finish()
+        });
+
+        self.request()
+            .method(method)
+            .uri(format!("/v1/me/player/{}", command), query.as_deref())
+    }
+
+    pub(crate) fn get_current_user(&self) -> SpotifyRequest<'_, (), User> {
+        self.request()
+            .method(Method::GET)
+            .uri("/v1/me".to_string(), None)
+    }
+
+    pub(crate) fn get_recommendations(
+        &self,
+        seed_artists: &[&str],
+        seed_tracks: &[&str],
+        seed_genres: &[&str],
+        limit: usize,
+        params: Option<&RecommendationParams>,
+    ) -> SpotifyRequest<'_, (), Recommendations> {
+        let seed_count = seed_artists.len() + seed_tracks.len() + seed_genres.len();
+
+        let mut query = make_query_params();
+        for (name, seeds) in [
+            ("seed_artists", seed_artists),
+            ("seed_tracks", seed_tracks),
+            ("seed_genres", seed_genres),
+        ] {
+            if !seeds.is_empty() {
+                query.append_pair(name, &seeds.join(","));
+            }
+        }
+        query.append_pair("limit", &limit.to_string()[..]);
+        for (name, value) in params.map(|p| p.query_pairs()).unwrap_or_default() {
+            query.append_pair(&name, &value);
+        }
+
+        self.request()
+            .invalid_if(
+                seed_count == 0 || seed_count > MAX_RECOMMENDATION_SEEDS,
+                || SpotifyApiError::InvalidSeedCount {
+                    given: seed_count,
+                    max: MAX_RECOMMENDATION_SEEDS,
+                },
+            )
+            .method(Method::GET)
+            .uri("/v1/recommendations".to_string(), Some(&query.finish()))
+    }
+
+    pub(crate) fn get_available_genre_seeds(&self) -> SpotifyRequest<'_, (), GenreSeeds> {
+        self.request().method(Method::GET).uri(
+            "/v1/recommendations/available-genre-seeds".to_string(),
+            None,
+        )
+    }
+
+    pub(crate) fn get_user(&self, id: &str) -> SpotifyRequest<'_, (), User> {
+        let id = utf8_percent_encode(id, PATH_ENCODE_SET);
+        self.request()
+            .method(Method::GET)
+            .uri(format!("/v1/users/{}", id), None)
+    }
+
+    pub(crate) fn get_user_playlists(
+        &self,
+        id: &str,
+        offset: usize,
+        limit: usize,
+    ) -> SpotifyRequest<'_, (), Page<Playlist>> {
+        let id = utf8_percent_encode(id, PATH_ENCODE_SET);
+        let query = make_query_params()
+            .append_pair("offset", &offset.to_string()[..])
+            .append_pair("limit", &limit.to_string()[..])
+            .finish();
+
+        self.request()
+            .method(Method::GET)
+            .uri(format!("/v1/users/{}/playlists", id), Some(&query))
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_username_encoding() {
+        let username = "anna.lafuente❤";
+        let client = SpotifyClient::new();
+        let req = client.get_user(username);
+        assert_eq!(
+            req.request
+                .uri_ref()
+                .and_then(|u| u.path_and_query())
+                .unwrap()
+                .as_str(),
+            "/v1/users/anna.lafuente%E2%9D%A4"
+        );
+    }
+
+    #[test]
+    fn test_save_tracks_ids() {
+        let client = SpotifyClient::new();
+        let req = client.save_tracks(&["a", "b"]);
+        assert_eq!(
+            req.request
+                .uri_ref()
+                .and_then(|u| u.path_and_query())
+                .unwrap()
+                .as_str(),
+            "/v1/me/tracks?ids=a%2Cb"
+        );
+    }
+
+    #[test]
+    fn test_are_albums_saved_ids() {
+        let client = SpotifyClient::new();
+        let req = client.are_albums_saved(&["a", "b"]);
+        assert_eq!(
+            req.request
+                .uri_ref()
+                .and_then(|u| u.path_and_query())
+                .unwrap()
+                .as_str(),
+            "/v1/me/albums/contains?ids=a%2Cb"
+        );
+    }
+
+    #[test]
+    fn test_remove_from_playlist_body() {
+        let client = SpotifyClient::new();
+        let req = client.remove_from_playlist("id", vec!["spotify:track:a".to_string()], None);
+        assert_eq!(req.request.method_ref(), Some(&Method::DELETE));
+        assert_eq!(
+            req.request
+                .headers_ref()
+                .and_then(|h| h.get("Content-Type"))
+                .unwrap(),
+            "application/json"
+        );
+        assert_eq!(
+            String::from_utf8(req.body).unwrap(),
+            r#"{"tracks":[{"uri":"spotify:track:a"}]}"#
+        );
+    }
+
+    #[test]
+    fn test_remove_from_playlist_snapshot() {
+        let client = SpotifyClient::new();
+        let req = client.remove_from_playlist(
+            "id",
+            vec!["spotify:track:a".to_string()],
+            Some("snap".to_string()),
+        );
+        assert_eq!(
+            String::from_utf8(req.body).unwrap(),
+            r#"{"tracks":[{"uri":"spotify:track:a"}],"snapshot_id":"snap"}"#
+        );
+    }
+
+    #[test]
+    fn test_is_premium() {
+        let client = SpotifyClient::new();
+        assert_eq!(client.is_premium(), None);
+        client.set_product(Some("free".to_string()));
+        assert_eq!(client.is_premium(), Some(false));
+        client.set_product(Some("premium".to_string()));
+        assert_eq!(client.is_premium(), Some(true));
+    }
+
+    #[test]
+    fn test_detected_market() {
+        let client = SpotifyClient::new();
+        let req = client.get_track("a").request.body(()).unwrap();
+        assert!(client.with_detected_market(&req).is_none());
+
+        client.set_detected_country(Some("BR".to_string()));
+        let retry = client.with_detected_market(&req).unwrap();
+        assert_eq!(
+            retry.uri().path_and_query().unwrap().as_str(),
+            "/v1/tracks/a?market=BR"
+        );
+
+        let error = SpotifyApiError::BadStatus(400, "Invalid market code".to_string(), None);
+        assert!(error.is_market_error());
+    }
+
+    #[test]
+    fn test_playlist_snapshots() {
+        let client = SpotifyClient::new();
+        assert_eq!(client.playlist_snapshot("a"), None);
+        client.set_playlist_snapshot("a", Some("snap".to_string()));
+        assert_eq!(client.playlist_snapshot("a").as_deref(), Some("snap"));
+        assert_eq!(client.playlist_snapshot("b"), None);
+        client.set_playlist_snapshot("a", None);
+        assert_eq!(client.playlist_snapshot("a"), None);
+    }
+
+    #[test]
+    fn test_update_playlist_details_body() {
+        let client = SpotifyClient::new();
+        let req = client.update_playlist_details("id", Some("name"), None, None);
+        assert_eq!(String::from_utf8(req.body).unwrap(), r#"{"name":"name"}"#);
+    }
+
+    #[test]
+    fn test_follow_playlist_body() {
+        let client = SpotifyClient::new();
+        let req = client.follow_playlist("a", false);
+        assert_eq!(String::from_utf8(req.body).unwrap(), r#"{"public":false}"#);
+    }
+
+    #[test]
+    fn test_do_users_follow_playlist() {
+        let client = SpotifyClient::new();
+        let req = client.do_users_follow_playlist("a", &["user"]);
+        assert_eq!(
+            req.request
+                .uri_ref()
+                .and_then(|u| u.path_and_query())
+                .unwrap()
+                .as_str(),
+            "/v1/playlists/a/followers/contains?ids=user"
+        );
+    }
+
+    #[test]
+    fn test_recommendations_seeds() {
+        let client = SpotifyClient::new();
+        let req = client.get_recommendations(&["a"], &[], &["rock", "jazz"], 10, None);
+        assert_eq!(
+            req.request
+                .uri_ref()
+                .and_then(|u| u.path_and_query())
+                .unwrap()
+                .as_str(),
+            "/v1/recommendations?seed_artists=a&seed_genres=rock%2Cjazz&limit=10"
+        );
+    }
+
+    #[test]
+    fn test_invalid_requests_fail_before_sending() {
+        let client = SpotifyClient::new();
+        let error =
+            futures::executor::block_on(client.get_recommendations(&[], &[], &[], 10, None).send())
+                .err();
+        assert!(matches!(
+            error,
+            Some(SpotifyApiError::InvalidSeedCount { given: 0, .. })
+        ));
+
+        let ids = ["a"; MAX_IDS_PER_REQUEST + 1];
+        let error = futures::executor::block_on(client.are_tracks_saved(&ids).send()).err();
+        assert!(matches!(
+            error,
+            Some(SpotifyApiError::TooManyItems { given, max })
+                if given == MAX_IDS_PER_REQUEST + 1 && max == MAX_IDS_PER_REQUEST
+        ));
+    }
+
+    #[test]
+    fn test_recommendations_params_clamped() {
+        let client = SpotifyClient::new();
+        let params = RecommendationParams {
+            target: TrackAttributes {
+                energy: Some(1.5),
+                ..Default::default()
+            },
+            min: TrackAttributes {
+                loudness: Some(-80.0),
+                popularity: Some(200),
+                ..Default::default()
+            },
+            max: TrackAttributes {
+                valence: Some(0.25),
+                ..Default::default()
+            },
+        };
+        let req = client.get_recommendations(&["a"], &[], &[], 10, Some(&params));
+        assert_eq!(
+            req.request
+                .uri_ref()
+                .and_then(|u| u.path_and_query())
+                .unwrap()
+                .as_str(),
+            "/v1/recommendations?seed_artists=a&limit=10&target_energy=1&min_loudness=-60&min_popularity=100&max_valence=0.25"
+        );
+    }
+
+    #[test]
+    fn test_deserialize_result_error() {
+        let response = SpotifyResponse::<Artist> {
+            kind: SpotifyResponseKind::Ok(r#"{"id":"a","name":3}"#.to_string(), PhantomData),
+            cache_control: CacheControl::default(),
+            etag: None,
+            received_at: Instant::now(),
+        };
+        match response.deserialize_result() {
+            Err(SpotifyApiError::UnexpectedResponse { near, .. }) => {
+                assert!(near.contains(r#""name":"#))
+            }
+            _ => panic!("expected a parse error"),
+        }
+    }
+
+    #[test]
+    fn test_request_id() {
+        let response = Response::builder()
+            .header("x-request-id", "abc")
+            .body(())
+            .unwrap();
+        let request_id = SpotifyClient::request_id(&response);
+        assert_eq!(request_id.as_deref(), Some("abc"));
+
+        let error = SpotifyApiError::BadStatus(500, "oops".to_string(), request_id);
+        assert_eq!(
+            error.to_string(),
+            "Request failed (500): oops (request id: abc)"
+        );
+        let error = SpotifyApiError::BadStatus(500, "oops".to_string(), None);
+        assert_eq!(error.to_string(), "Request failed (500): oops");
+    }
+
+    #[test]
+    fn test_resource_info() {
+        let response = Response::builder()
+            .header("content-length", "1234")
+            .header("etag", "\"abc\"")
+            .body(())
+            .unwrap();
+        assert_eq!(
+            SpotifyClient::resource_info(&response),
+            ResourceInfo {
+                content_length: Some(1234),
+                etag: Some("\"abc\"".to_string()),
+            }
+        );
+
+        let response = Response::builder().body(()).unwrap();
+        assert_eq!(
+            SpotifyClient::resource_info(&response),
+            ResourceInfo::default()
+        );
+    }
+
+    #[test]
+    fn test_forbidden_reason() {
+        let body = r#"{"error":{"status":403,"message":"Insufficient client scope"}}"#;
+        let error = SpotifyApiError::from_status(
+            StatusCode::FORBIDDEN,
+            body.to_string(),
+            Some("abc".to_string()),
+        );
+        assert!(error.is_missing_scope());
+        assert_eq!(
+            error.to_string(),
+            "Forbidden: Insufficient client scope (request id: abc)"
+        );
+        match error {
+            SpotifyApiError::Forbidden { reason, request_id } => {
+                assert_eq!(reason.as_deref(), Some("Insufficient client scope"));
+                assert_eq!(request_id.as_deref(), Some("abc"));
+            }
+            _ => panic!("expected a forbidden error"),
+        }
+
+        let error = SpotifyApiError::from_status(StatusCode::FORBIDDEN, "nope".to_string(), None);
+        assert!(matches!(
+            error,
+            SpotifyApiError::Forbidden { reason: None, .. }
+        ));
+        assert!(!error.is_missing_scope());
+    }
+
+    #[test]
+    fn test_bad_status_message() {
+        let body = r#"{"error":{"status":404,"message":"Playlist not found"}}"#;
+        let error = SpotifyApiError::from_status(StatusCode::NOT_FOUND, body.to_string(), None);
+        assert_eq!(
+            error.to_string(),
+            "Request failed (404): Playlist not found"
+        );
+
+        let error =
+            SpotifyApiError::from_status(StatusCode::BAD_GATEWAY, "<html>".to_string(), None);
+        assert_eq!(error.to_string(), "Request failed (502): <html>");
+    }
+
+    #[test]
+    fn test_stale_at() {
+        let received_at = Instant::now();
+        let mut response = SpotifyResponse::<()> {
+            kind: SpotifyResponseKind::NotModified,
+            cache_control: CacheControl::default(),
+            etag: None,
+            received_at,
+        };
+        assert_eq!(response.stale_at(), None);
+
+        response.cache_control.max_age = Some(30);
+        assert_eq!(
+            response.stale_at(),
+            Some(received_at + Duration::from_secs(30))
+        );
+
+        response.cache_control.no_cache = true;
+        assert_eq!(response.stale_at(), Some(received_at));
+    }
+
+    #[test]
+    fn test_currently_playing_no_content() {
+        let response = SpotifyResponse::<Option<CurrentlyPlaying>> {
+            kind: SpotifyResponseKind::Ok(String::new(), PhantomData),
+            cache_control: CacheControl::default(),
+            etag: None,
+            received_at: Instant::now(),
+        };
+        assert!(matches!(response.deserialize_result(), Ok(None)));
+    }
+
+    #[test]
+    fn test_available_genre_seeds() {
+        let client = SpotifyClient::new();
+        let req = client.get_available_genre_seeds();
+        assert_eq!(
+            req.request
+                .uri_ref()
+                .and_then(|u| u.path_and_query())
+                .unwrap()
+                .as_str(),
+            "/v1/recommendations/available-genre-seeds"
+        );
+    }
+
+    #[test]
+    fn test_album_tracks_page() {
+        let client = SpotifyClient::new();
+        let req = client.get_album_tracks("a", 50, 50);
+        assert_eq!(
+            req.request
+                .uri_ref()
+                .and_then(|u| u.path_and_query())
+                .unwrap()
+                .as_str(),
+            "/v1/albums/a/tracks?offset=50&limit=50"
+        );
+    }
+
+    #[test]
+    fn test_artist_albums_groups() {
+        let client = SpotifyClient::new();
+        let req =
+            client.get_artist_albums("a", &[AlbumType::AppearsOn, AlbumType::Compilation], 0, 20);
+        assert_eq!(
+            req.request
+                .uri_ref()
+                .and_then(|u| u.path_and_query())
+                .unwrap()
+                .as_str(),
+            "/v1/artists/a/albums?include_groups=appears_on%2Ccompilation&country=from_token&offset=0&limit=20"
+        );
+    }
+
+    #[test]
+    fn test_top_items_limit_clamped() {
+        let client = SpotifyClient::new();
+        let req = client.get_top_artists(TimeRange::ShortTerm, 0, 100);
+        assert_eq!(
+            req.request
+                .uri_ref()
+                .and_then(|u| u.path_and_query())
+                .unwrap()
+                .as_str(),
+            "/v1/me/top/artists?time_range=short_term&offset=0&limit=50"
+        );
+    }
+
+    #[test]
+    fn test_remote_command_device() {
+        let client = SpotifyClient::new();
+        let req = client.remote_next(Some("device"));
+        assert_eq!(
+            req.request
+                .uri_ref()
+                .and_then(|u| u.path_and_query())
+                .unwrap()
+                .as_str(),
+            "/v1/me/player/next?device_id=device"
+        );
+    }
+
+    #[test]
+    fn test_play_context_body() {
+        let client = SpotifyClient::new();
+        let req = client.play_context(
+            None,
+            PlaybackTarget::Context {
+                uri: "spotify:album:a".to_string(),
+                offset: Some(2),
+            },
+        );
+        assert_eq!(
+            String::from_utf8(req.body).unwrap(),
+            r#"{"context_uri":"spotify:album:a","offset":{"position":2}}"#
+        );
+
+        let req = client.play_context(
+            None,
+            PlaybackTarget::Tracks(vec!["spotify:track:b".to_string()]),
+        );
+        assert_eq!(
+            String::from_utf8(req.body).unwrap(),
+            r#"{"uris":["spotify:track:b"]}"#
+        );
+    }
+
+    #[test]
+    fn test_set_repeat_state() {
+        let client = SpotifyClient::new();
+        let req = client.set_repeat(RepeatMode::Context, None);
+        assert_eq!(
+            req.request
+                .uri_ref()
+                .and_then(|u| u.path_and_query())
+                .unwrap()
+                .as_str(),
+            "/v1/me/player/repeat?state=context"
+        );
+
+        let req = client.set_shuffle(ShuffleState::On, None);
+        assert_eq!(
+            req.request
+                .uri_ref()
+                .and_then(|u| u.path_and_query())
+                .unwrap()
+                .as_str(),
+            "/v1/me/player/shuffle?state=true"
+        );
+    }
+
+    #[test]
+    fn test_backoff_delay() {
+        let delay = SpotifyClient::backoff_delay(2);
+        assert!(delay >= Duration::from_millis(1000));
+        assert!(delay <= Duration::from_millis(1500));
+    }
+
+    #[test]
+    fn test_retry_delay() {
+        let response = Response::builder()
+            .status(StatusCode::SERVICE_UNAVAILABLE)
+            .header("retry-after", "2")
+            .body(())
+            .unwrap();
+        assert_eq!(
+            SpotifyClient::retry_delay(Some(&response), 0),
+            Some(Duration::from_secs(2))
+        );
+
+        let response = Response::builder()
+            .status(StatusCode::SERVICE_UNAVAILABLE)
+            .header("retry-after", "3600")
+            .body(())
+            .unwrap();
+        assert_eq!(SpotifyClient::retry_delay(Some(&response), 0), None);
+
+        let response = Response::builder()
+            .status(StatusCode::SERVICE_UNAVAILABLE)
+            .body(())
+            .unwrap();
+        let delay = SpotifyClient::retry_delay(Some(&response), 2).unwrap();
+        assert!(delay >= Duration::from_millis(1000));
+        assert!(delay <= Duration::from_millis(1500));
+
+        let delay = SpotifyClient::retry_delay::<()>(None, 0).unwrap();
+        assert!(delay <= Duration::from_millis(375));
+    }
+
+    #[test]
+    fn test_malformed_proxy() {
+        assert!(matches!(
+            SpotifyClient::with_proxy("not a proxy"),
+            Err(SpotifyApiError::InvalidProxy(_))
+        ));
+        assert!(SpotifyClient::with_proxy("socks5h://localhost:9050").is_ok());
+    }
+
+    #[test]
+    fn test_token_expiry() {
+        let client = SpotifyClient::new();
+        assert!(client.token_expires_in().is_none());
+
+        client.update_token("token".to_string());
+        assert!(client.token_expires_in().is_none());
+
+        client.update_token_with_expiry("token".to_string(), 3600);
+        let expires_in = client.token_expires_in().unwrap();
+        assert!(expires_in <= Duration::from_secs(3600));
+        assert!(expires_in > Duration::from_secs(3500));
+    }
+
+    #[test]
+    fn test_token_refreshed_on_expiry() {
+        let client = SpotifyClient::new();
+        client.update_token("stale".to_string());
+        let request = client.get_track("a").authenticated().unwrap();
+        let request = request.request.body(()).unwrap();
+        assert!(!futures::executor::block_on(client.refresh_token()));
+
+        client.set_on_token_expired(Some(Arc::new(|| -> BoxFuture<'static, Option<String>> {
+            Box::pin(async { Some("fresh".to_string()) })
+        })));
+        assert!(futures::executor::block_on(client.refresh_token()));
+        assert_eq!(
+            client.reauthenticated(request).unwrap().headers()["Authorization"],
+            "Bearer fresh"
+        );
+    }
+
+    #[test]
+    fn test_next_token() {
+        let client = SpotifyClient::new();
+        let next_token = client.next_token();
+        client.update_token_with_expiry("fresh".to_string(), 3600);
+        assert_eq!(
+            futures::executor::block_on(next_token).ok().as_deref(),
+            Some("fresh")
+        );
+
+        // setting the same token again keeps its expiry
+        client.set_on_token_expired(Some(Arc::new(|| -> BoxFuture<'static, Option<String>> {
+            Box::pin(async { Some("fresh".to_string()) })
+        })));
+        assert!(futures::executor::block_on(client.refresh_token()));
+        assert!(client.token_expires_in().is_some());
+    }
+
+    #[test]
+    fn test_custom_header() {
+        let client = SpotifyClient::new();
+        let req = client.get_album("a").header("Accept-Language", "de-DE, de");
+        assert_eq!(
+            req.request.headers_ref().unwrap()["Accept-Language"],
+            "de-DE, de"
+        );
+    }
+
+    #[test]
+    fn test_market_override() {
+        let client = SpotifyClient::new();
+        client.set_market(Some("SE".to_string()));
+        let req = client.get_track("a");
+        assert_eq!(
+            req.request
+                .uri_ref()
+                .and_then(|u| u.path_and_query())
+                .unwrap()
+                .as_str(),
+            "/v1/tracks/a?market=SE"
+        );
+    }
+
+    #[test]
+    fn test_cache_control_directives() {
+        assert_eq!(
+            SpotifyClient::parse_cache_control("private, max-age=3600, must-revalidate"),
+            CacheControl {
+                max_age: Some(3600),
+                must_revalidate: true,
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            SpotifyClient::parse_cache_control("no-cache, no-store"),
+            CacheControl {
+                no_cache: true,
+                no_store: true,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_cache_control_expiry() {
+        assert_eq!(
+            SpotifyClient::parse_cache_control("private, max-age=3600, must-revalidate")
+                .expires_in(),
+            3600
+        );
+        assert_eq!(
+            SpotifyClient::parse_cache_control("max-age=1").expires_in(),
+            10
+        );
+        assert_eq!(
+            SpotifyClient::parse_cache_control("max-age=1, must-revalidate").expires_in(),
+            1
+        );
+        assert_eq!(
+            SpotifyClient::parse_cache_control("no-cache").expires_in(),
+            0
+        );
+    }
+
+    #[test]
+    fn test_paginate_pages() {
+        let pages = [
+            r#"{"items":[1,2],"offset":0,"limit":2,"total":3,"next":"https://api.spotify.com/v1/me/albums?offset=2&limit=2"}"#,
+            r#"{"items":[3],"offset":2,"limit":2,"total":3,"next":null}"#,
+        ];
+        let mut requested = vec![];
+        let items = futures::executor::block_on(
+            paginate(0, 2, |offset, limit| {
+                requested.push((offset, limit));
+                let page = from_str::<Page<u32>>(pages[offset / 2]).map_err(|e| e.into());
+                async move { page }
+            })
+            .collect::<Vec<_>>(),
+        );
+        let items: Vec<u32> = items.into_iter().map(|item| item.unwrap()).collect();
+        assert_eq!(items, vec![1, 2, 3]);
+        assert_eq!(requested, vec![(0, 2), (2, 2)]);
+    }
+
+    #[test]
+    fn test_paginate_stops_without_next() {
+        // total says there are more items, but the API has no next page to give
+        let page = r#"{"items":[1,2],"offset":0,"limit":2,"total":10,"next":null}"#;
+        let mut requested = vec![];
+        let items = futures::executor::block_on(
+            paginate(0, 2, |offset, limit| {
+                requested.push((offset, limit));
+                let page = from_str::<Page<u32>>(page).map_err(|e| e.into());
+                async move { page }
+            })
+            .collect::<Vec<_>>(),
+        );
+        let items: Vec<u32> = items.into_iter().map(|item| item.unwrap()).collect();
+        assert_eq!(items, vec![1, 2]);
+        assert_eq!(requested, vec![(0, 2)]);
+    }
+
+    #[test]
+    fn test_coalescing_key() {
+        let client = SpotifyClient::new();
+        let get = client.get_track("a").etag(Some("tag".to_string()));
+        let request = get.request.body(()).unwrap();
+        assert_eq!(
+            SpotifyClient::coalescing_key(&request).unwrap(),
+            "GET https://api.spotify.com/v1/tracks/a?market=from_token tag"
+        );
+
+        let put = client.save_tracks(&["a"]);
+        let request = put.request.body(()).unwrap();
+        assert!(SpotifyClient::coalescing_key(&request).is_none());
+    }
+
+    #[test]
+    fn test_search_query() {
         let query = SearchQuery {
             query: "test".to_string(),
             types: vec![SearchType::Album, SearchType::Artist],
@@ -551,6 +2666,21 @@ pub mod tests {
         );
     }
 
+    #[test]
+    fn test_search_query_podcasts() {
+        let query = SearchQuery {
+            query: "test".to_string(),
+            types: vec![SearchType::Show, SearchType::Episode],
+            limit: 5,
+            offset: 0,
+        };
+
+        assert_eq!(
+            query.into_query_string(),
+            "type=show,episode&q=test&offset=0&limit=5&market=from_token"
+        );
+    }
+
     #[test]
     fn test_search_query_spaces_and_stuff() {
         let query = SearchQuery {
@@ -4,8 +4,11 @@ mod client;
 
 pub mod cache;
 
+pub use api_models::{
+    AlbumType, PlaybackTarget, RecommendationParams, SearchType, TimeRange, TrackAttributes,
+};
 pub use cached_client::{CachedSpotifyClient, SpotifyApiClient, SpotifyResult};
-pub use client::SpotifyApiError;
+pub use client::{accept_invalid_certs, OnTokenExpired, SpotifyApiError};
 
 pub async fn clear_user_cache() -> Option<()> {
     cache::CacheManager::for_dir("spot/net")?
@@ -13,3 +16,30 @@ pub async fn clear_user_cache() -> Option<()> {
         .await
         .ok()
 }
+
+pub fn log_cache_stats() {
+    for dir in ["spot/net", "spot/img"] {
+        if let Some(cache) = cache::CacheManager::for_dir(dir) {
+            info!("Cache stats for {}: {:?}", dir, cache.stats());
+        }
+    }
+}
+
+// How many bytes were freed, and whether everything could be removed
+pub async fn clear_cache() -> (u64, bool) {
+    let mut freed = 0;
+    let mut cleared = true;
+    for dir in ["spot/net", "spot/img"] {
+        match cache::CacheManager::for_dir(dir) {
+            Some(cache) => match cache.clear().await {
+                Ok(size) => freed += size,
+                Err(e) => {
+                    warn!("Could not clear cache in {}: {}", dir, e);
+                    cleared = false;
+                }
+            },
+            None => cleared = false,
+        }
+    }
+    (freed, cleared)
+}
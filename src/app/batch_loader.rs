@@ -1,13 +1,40 @@
+use futures::future::RemoteHandle;
+use futures::FutureExt;
 use gettextrs::gettext;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 
-use crate::api::SpotifyApiClient;
+use crate::api::{SpotifyApiClient, SpotifyResult};
 use crate::app::models::*;
 use crate::app::AppAction;
 
+// A page requested ahead of time, dropping it cancels the request if it's still running
+struct PrefetchedBatch {
+    source: SongsSource,
+    batch: Batch,
+    result: RemoteHandle<Option<SongBatch>>,
+}
+
+impl PrefetchedBatch {
+    fn is_for(&self, query: &BatchQuery) -> bool {
+        self.source == query.source
+            && self.batch.offset == query.batch.offset
+            && self.batch.batch_size == query.batch.batch_size
+    }
+}
+
+#[derive(Default)]
+struct Prefetch {
+    enabled: bool,
+    // at most one page is kept around, to keep memory use bounded
+    batch: Option<PrefetchedBatch>,
+    // the page last handed to query(), so it isn't fetched again before the list catches up
+    used: Option<(SongsSource, usize)>,
+}
+
 #[derive(Clone)]
 pub struct BatchLoader {
     api: Arc<dyn SpotifyApiClient + Send + Sync>,
+    prefetch: Arc<Mutex<Prefetch>>,
 }
 
 #[derive(Clone, Debug)]
@@ -48,7 +75,66 @@ impl BatchQuery {
 
 impl BatchLoader {
     pub fn new(api: Arc<dyn SpotifyApiClient + Send + Sync>) -> Self {
-        Self { api }
+        Self {
+            api,
+            prefetch: Default::default(),
+        }
+    }
+
+    // Off by default, see prefetch()
+    pub fn set_prefetch(&self, enabled: bool) {
+        if let Ok(mut prefetch) = self.prefetch.lock() {
+            prefetch.enabled = enabled;
+            if !enabled {
+                prefetch.batch = None;
+            }
+        }
+    }
+
+    // Starts fetching a page before it's needed, so that a later query() for it doesn't wait.
+    // A previously prefetched page that wasn't used is dropped.
+    pub fn prefetch(&self, query: BatchQuery) {
+        let mut prefetch = match self.prefetch.lock() {
+            Ok(prefetch) if prefetch.enabled => prefetch,
+            _ => return,
+        };
+        let used = Some((query.source.clone(), query.batch.offset));
+        if prefetch.used == used || prefetch.batch.as_ref().map(|b| b.is_for(&query)) == Some(true)
+        {
+            return;
+        }
+
+        let api = Arc::clone(&self.api);
+        let source = query.source.clone();
+        let batch = query.batch;
+        let (task, handle) = async move { Self::fetch(api, query).await.ok() }.remote_handle();
+        glib::MainContext::default().spawn(task);
+
+        prefetch.batch = Some(PrefetchedBatch {
+            source,
+            batch,
+            result: handle,
+        });
+    }
+
+    // Forgets what was prefetched for a list that's going away, so opening it again starts over
+    pub fn cancel_prefetch(&self, source: &SongsSource) {
+        if let Ok(mut prefetch) = self.prefetch.lock() {
+            if prefetch.batch.as_ref().map(|b| &b.source) == Some(source) {
+                prefetch.batch = None;
+            }
+            if prefetch.used.as_ref().map(|(s, _)| s) == Some(source) {
+                prefetch.used = None;
+            }
+        }
+    }
+
+    // The prefetched page for this query, any other one is given up on
+    fn take_prefetched(&self, query: &BatchQuery) -> Option<RemoteHandle<Option<SongBatch>>> {
+        let mut prefetch = self.prefetch.lock().ok()?;
+        let batch = prefetch.batch.take().filter(|batch| batch.is_for(query))?;
+        prefetch.used = Some((batch.source, batch.batch.offset));
+        Some(batch.result)
     }
 
     pub async fn query<ActionCreator>(
@@ -59,9 +145,45 @@ impl BatchLoader {
     where
         ActionCreator: FnOnce(SongBatch) -> AppAction,
     {
-        let api = Arc::clone(&self.api);
+        if let Some(prefetched) = self.take_prefetched(&query) {
+            if let Some(batch) = prefetched.await {
+                return create_action(batch);
+            }
+        }
+
+        match Self::fetch(Arc::clone(&self.api), query).await {
+            Ok(batch) => create_action(batch),
+            Err(err) => {
+                error!("Spotify API error: {}", err);
+                AppAction::ShowNotification(gettext(
+                    // translators: This notification is the default message for unhandled errors. Logs refer to console output.
+                    "An error occured. Check logs for details!",
+                ))
+            }
+        }
+    }
 
-        let result = match query.source {
+    // The songs of this page and all the ones after it, for when the whole list is needed at once
+    pub async fn query_remaining(&self, query: BatchQuery) -> SpotifyResult<Vec<SongDescription>> {
+        let mut songs = vec![];
+        let mut next_query = Some(query);
+        while let Some(query) = next_query.take() {
+            let source = query.source.clone();
+            let SongBatch {
+                songs: batch_songs,
+                batch,
+            } = Self::fetch(Arc::clone(&self.api), query).await?;
+            songs.extend(batch_songs);
+            next_query = BatchQuery { source, batch }.next();
+        }
+        Ok(songs)
+    }
+
+    async fn fetch(
+        api: Arc<dyn SpotifyApiClient + Send + Sync>,
+        query: BatchQuery,
+    ) -> SpotifyResult<SongBatch> {
+        match query.source {
             SongsSource::Playlist(id) => {
                 let Batch {
                     offset, batch_size, ..
@@ -80,17 +202,6 @@ impl BatchLoader {
                 } = query.batch;
                 api.get_album_tracks(&id, offset, batch_size).await
             }
-        };
-
-        match result {
-            Ok(batch) => create_action(batch),
-            Err(err) => {
-                error!("Spotify API error: {}", err);
-                AppAction::ShowNotification(gettext(
-                    // translators: This notification is the default message for unhandled errors. Logs refer to console output.
-                    "An error occured. Check logs for details!",
-                ))
-            }
         }
     }
 }
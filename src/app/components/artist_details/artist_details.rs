@@ -4,7 +4,7 @@ use gtk::CompositeTemplate;
 use std::rc::Rc;
 
 use crate::app::components::{
-    display_add_css_provider, AlbumWidget, Component, EventListener, Playlist,
+    display_add_css_provider, labels, AlbumWidget, Component, EventListener, Playlist,
 };
 use crate::app::{models::*, ListStore};
 use crate::app::{AppEvent, BrowserEvent, Worker};
@@ -21,11 +21,26 @@ mod imp {
         #[template_child]
         pub scrolled_window: TemplateChild<gtk::ScrolledWindow>,
 
+        #[template_child]
+        pub follow_button: TemplateChild<gtk::Button>,
+
         #[template_child]
         pub top_tracks: TemplateChild<gtk::ListView>,
 
         #[template_child]
         pub artist_releases: TemplateChild<gtk::FlowBox>,
+
+        #[template_child]
+        pub appears_on_section: TemplateChild<gtk::Expander>,
+
+        #[template_child]
+        pub appears_on: TemplateChild<gtk::FlowBox>,
+
+        #[template_child]
+        pub compilations_section: TemplateChild<gtk::Expander>,
+
+        #[template_child]
+        pub compilations: TemplateChild<gtk::FlowBox>,
     }
 
     #[glib::object_subclass]
@@ -71,6 +86,21 @@ impl ArtistDetailsWidget {
         context.add_class("artist__loaded");
     }
 
+    fn connect_follow<F>(&self, f: F)
+    where
+        F: Fn() + 'static,
+    {
+        self.widget().follow_button.connect_clicked(move |_| f());
+    }
+
+    fn set_followed(&self, is_followed: bool) {
+        self.widget().follow_button.set_label(if is_followed {
+            &*labels::FOLLOWING
+        } else {
+            &*labels::FOLLOW
+        });
+    }
+
     fn connect_bottom_edge<F>(&self, f: F)
     where
         F: Fn() + 'static,
@@ -84,27 +114,31 @@ impl ArtistDetailsWidget {
             });
     }
 
-    fn bind_artist_releases<F>(
-        &self,
+    fn set_other_releases_visible(&self, appears_on: bool, compilations: bool) {
+        let widget = self.widget();
+        widget.appears_on_section.set_visible(appears_on);
+        widget.compilations_section.set_visible(compilations);
+    }
+
+    fn bind_releases<F>(
+        flowbox: &gtk::FlowBox,
         worker: Worker,
         store: &ListStore<AlbumModel>,
         on_album_pressed: F,
     ) where
         F: Fn(String) + Clone + 'static,
     {
-        self.widget()
-            .artist_releases
-            .bind_model(Some(store.unsafe_store()), move |item| {
-                let item = item.downcast_ref::<AlbumModel>().unwrap();
-                let child = gtk::FlowBoxChild::new();
-                let album = AlbumWidget::for_model(item, worker.clone());
-                let f = on_album_pressed.clone();
-                album.connect_album_pressed(clone!(@weak item => move |_| {
-                    f(item.uri());
-                }));
-                child.set_child(Some(&album));
-                child.upcast::<gtk::Widget>()
-            });
+        flowbox.bind_model(Some(store.unsafe_store()), move |item| {
+            let item = item.downcast_ref::<AlbumModel>().unwrap();
+            let child = gtk::FlowBoxChild::new();
+            let album = AlbumWidget::for_model(item, worker.clone());
+            let f = on_album_pressed.clone();
+            album.connect_album_pressed(clone!(@weak item => move |_| {
+                f(item.uri());
+            }));
+            child.set_child(Some(&album));
+            child.upcast::<gtk::Widget>()
+        });
     }
 }
 
@@ -124,8 +158,35 @@ impl ArtistDetails {
             model.load_more();
         }));
 
+        widget.connect_follow(clone!(@weak model => move || {
+            model.toggle_follow();
+        }));
+
         if let Some(store) = model.get_list_store() {
-            widget.bind_artist_releases(
+            ArtistDetailsWidget::bind_releases(
+                &widget.widget().artist_releases,
+                worker.clone(),
+                &*store,
+                clone!(@weak model => move |id| {
+                    model.open_album(id);
+                }),
+            );
+        }
+
+        if let Some(store) = model.get_appears_on_store() {
+            ArtistDetailsWidget::bind_releases(
+                &widget.widget().appears_on,
+                worker.clone(),
+                &*store,
+                clone!(@weak model => move |id| {
+                    model.open_album(id);
+                }),
+            );
+        }
+
+        if let Some(store) = model.get_compilations_store() {
+            ArtistDetailsWidget::bind_releases(
+                &widget.widget().compilations,
                 worker.clone(),
                 &*store,
                 clone!(@weak model => move |id| {
@@ -165,6 +226,16 @@ impl EventListener for ArtistDetails {
                 if id == &self.model.id =>
             {
                 self.widget.set_loaded();
+                self.widget.set_followed(self.model.is_followed());
+                self.widget.set_other_releases_visible(
+                    self.model.has_appears_on(),
+                    self.model.has_compilations(),
+                );
+            }
+            AppEvent::BrowserEvent(BrowserEvent::ArtistFollowChanged(id))
+                if id == &self.model.id =>
+            {
+                self.widget.set_followed(self.model.is_followed());
             }
             _ => {}
         }
@@ -3,7 +3,8 @@ use gio::SimpleActionGroup;
 use std::ops::Deref;
 use std::rc::Rc;
 
-use crate::api::SpotifyApiError;
+use crate::api::{AlbumType, SpotifyApiError};
+use crate::app::components::utils::CancellableRequests;
 use crate::app::components::SimpleHeaderBarModel;
 use crate::app::components::{labels, PlaylistModel};
 use crate::app::models::*;
@@ -17,6 +18,7 @@ pub struct ArtistDetailsModel {
     pub id: String,
     app_model: Rc<AppModel>,
     dispatcher: Box<dyn ActionDispatcher>,
+    requests: CancellableRequests,
 }
 
 impl ArtistDetailsModel {
@@ -25,6 +27,7 @@ impl ArtistDetailsModel {
             id,
             app_model,
             dispatcher,
+            requests: Default::default(),
         }
     }
 
@@ -33,20 +36,73 @@ impl ArtistDetailsModel {
             .map_state_opt(|s| s.browser.artist_state(&self.id)?.artist.as_ref())
     }
 
+    pub fn is_followed(&self) -> bool {
+        self.app_model
+            .get_state()
+            .browser
+            .artist_state(&self.id)
+            .map(|s| s.is_followed)
+            .unwrap_or(false)
+    }
+
+    pub fn toggle_follow(&self) {
+        let id = self.id.clone();
+        let is_followed = self.is_followed();
+        let api = self.app_model.get_spotify();
+
+        self.dispatcher
+            .call_spotify_and_dispatch(move || async move {
+                if !is_followed {
+                    api.follow_artists(vec![id.clone()])
+                        .await
+                        .map(|_| BrowserAction::FollowArtist(id).into())
+                } else {
+                    api.unfollow_artists(vec![id.clone()])
+                        .await
+                        .map(|_| BrowserAction::UnfollowArtist(id).into())
+                }
+            });
+    }
+
     pub fn get_list_store(&self) -> Option<impl Deref<Target = ListStore<AlbumModel>> + '_> {
         self.app_model
             .map_state_opt(|s| Some(&s.browser.artist_state(&self.id)?.albums))
     }
 
+    pub fn get_appears_on_store(&self) -> Option<impl Deref<Target = ListStore<AlbumModel>> + '_> {
+        self.app_model
+            .map_state_opt(|s| Some(&s.browser.artist_state(&self.id)?.appears_on))
+    }
+
+    pub fn get_compilations_store(
+        &self,
+    ) -> Option<impl Deref<Target = ListStore<AlbumModel>> + '_> {
+        self.app_model
+            .map_state_opt(|s| Some(&s.browser.artist_state(&self.id)?.compilations))
+    }
+
+    pub fn has_appears_on(&self) -> bool {
+        self.get_appears_on_store()
+            .map(|store| store.len() > 0)
+            .unwrap_or(false)
+    }
+
+    pub fn has_compilations(&self) -> bool {
+        self.get_compilations_store()
+            .map(|store| store.len() > 0)
+            .unwrap_or(false)
+    }
+
     pub fn load_artist_details(&self, id: String) {
+        self.load_other_releases(id.clone());
         let api = self.app_model.get_spotify();
         self.dispatcher
-            .call_spotify_and_dispatch(move || async move {
+            .call_spotify_and_dispatch_cancellable(&self.requests, move || async move {
                 let artist = api.get_artist(&id).await;
                 match artist {
                     Ok(artist) => Ok(BrowserAction::SetArtistDetails(Box::new(artist)).into()),
-                    Err(SpotifyApiError::BadStatus(400, _))
-                    | Err(SpotifyApiError::BadStatus(404, _)) => {
+                    Err(SpotifyApiError::BadStatus(400, _, _))
+                    | Err(SpotifyApiError::BadStatus(404, _, _)) => {
                         Ok(BrowserAction::NavigationPop.into())
                     }
                     Err(e) => Err(e),
@@ -54,6 +110,26 @@ impl ArtistDetailsModel {
             });
     }
 
+    // Only the first page of these is shown, they are secondary to the artist's own releases
+    fn load_other_releases(&self, id: String) {
+        let api = self.app_model.get_spotify();
+        let appears_on_id = id.clone();
+        self.dispatcher
+            .call_spotify_and_dispatch_cancellable(&self.requests, move || async move {
+                api.get_artist_albums(&appears_on_id, &[AlbumType::AppearsOn], 0, 20)
+                    .await
+                    .map(|albums| BrowserAction::SetArtistAppearsOn(appears_on_id, albums).into())
+            });
+
+        let api = self.app_model.get_spotify();
+        self.dispatcher
+            .call_spotify_and_dispatch_cancellable(&self.requests, move || async move {
+                api.get_artist_albums(&id, &[AlbumType::Compilation], 0, 20)
+                    .await
+                    .map(|albums| BrowserAction::SetArtistCompilations(id, albums).into())
+            });
+    }
+
     pub fn open_album(&self, id: String) {
         self.dispatcher.dispatch(AppAction::ViewAlbum(id));
     }
@@ -68,8 +144,8 @@ impl ArtistDetailsModel {
         let offset = next_page.next_offset?;
 
         self.dispatcher
-            .call_spotify_and_dispatch(move || async move {
-                api.get_artist_albums(&id, offset, batch_size)
+            .call_spotify_and_dispatch_cancellable(&self.requests, move || async move {
+                api.get_artist_albums(&id, AlbumType::DEFAULT_GROUPS, offset, batch_size)
                     .await
                     .map(|albums| BrowserAction::AppendArtistReleases(id, albums).into())
             });
@@ -110,7 +186,9 @@ impl PlaylistModel for ArtistDetailsModel {
         for view_artist in song.make_artist_actions(self.dispatcher.box_clone(), None) {
             group.add_action(&view_artist);
         }
-        group.add_action(&song.make_album_action(self.dispatcher.box_clone(), None));
+        if let Some(view_album) = song.make_album_action(self.dispatcher.box_clone(), None) {
+            group.add_action(&view_album);
+        }
         group.add_action(&song.make_link_action(None));
         group.add_action(&song.make_queue_action(self.dispatcher.box_clone(), None));
 
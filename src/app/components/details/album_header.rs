@@ -19,6 +19,12 @@ mod imp {
         #[template_child]
         pub like_button: TemplateChild<gtk::Button>,
 
+        #[template_child]
+        pub export_button: TemplateChild<gtk::Button>,
+
+        #[template_child]
+        pub import_button: TemplateChild<gtk::Button>,
+
         #[template_child]
         pub info_button: TemplateChild<gtk::Button>,
 
@@ -76,6 +82,20 @@ impl AlbumHeaderWidget {
         self.widget().like_button.connect_clicked(move |_| f());
     }
 
+    pub fn connect_export<F>(&self, f: F)
+    where
+        F: Fn() + 'static,
+    {
+        self.widget().export_button.connect_clicked(move |_| f());
+    }
+
+    pub fn connect_import<F>(&self, f: F)
+    where
+        F: Fn() + 'static,
+    {
+        self.widget().import_button.connect_clicked(move |_| f());
+    }
+
     pub fn connect_info<F>(&self, f: F)
     where
         F: Fn() + 'static,
@@ -123,6 +143,18 @@ impl AlbumHeaderWidget {
         widget.year_label.set_halign(gtk::Align::Center);
     }
 
+    pub fn set_like_visible(&self, visible: bool) {
+        self.widget().like_button.set_visible(visible);
+    }
+
+    pub fn set_export_visible(&self, visible: bool) {
+        self.widget().export_button.set_visible(visible);
+    }
+
+    pub fn set_import_visible(&self, visible: bool) {
+        self.widget().import_button.set_visible(visible);
+    }
+
     pub fn hide_actions(&self) {
         self.widget().like_button.set_visible(false);
         self.widget().info_button.set_visible(false);
@@ -12,6 +12,7 @@ use crate::app::components::{
 };
 use crate::app::dispatch::Worker;
 use crate::app::loader::ImageLoader;
+use crate::app::models::ArtistModel;
 use crate::app::{AppEvent, BrowserEvent};
 
 mod imp {
@@ -203,6 +204,7 @@ pub struct Details {
     worker: Worker,
     widget: AlbumDetailsWidget,
     modal: ReleaseDetailsWindow,
+    artists_model: gio::ListStore,
     children: Vec<Box<dyn EventListener>>,
 }
 
@@ -229,6 +231,16 @@ impl Details {
 
         let modal = ReleaseDetailsWindow::new();
 
+        let artists_model = gio::ListStore::new(ArtistModel::static_type());
+        modal.bind_artists(
+            worker.clone(),
+            &artists_model,
+            clone!(@weak model, @weak modal => move |id| {
+                modal.close();
+                model.view_artist_with_id(id);
+            }),
+        );
+
         widget.connect_liked(clone!(@weak model => move || model.toggle_save_album()));
 
         widget.connect_header_visibility();
@@ -254,10 +266,25 @@ impl Details {
             worker,
             widget,
             modal,
+            artists_model,
             children: vec![playlist, headerbar],
         }
     }
 
+    fn update_artists(&self) {
+        if let Some(artists) = self.model.get_album_artists() {
+            self.artists_model.remove_all();
+            for artist in artists.iter() {
+                self.artists_model.append(&ArtistModel::new(
+                    &artist.name,
+                    &artist.photo,
+                    &artist.id,
+                ));
+            }
+            self.modal.set_artists_visible(!artists.is_empty());
+        }
+    }
+
     fn update_liked(&self) {
         if let Some(info) = self.model.get_album_info() {
             let is_liked = info.description.is_liked;
@@ -328,6 +355,12 @@ impl EventListener for Details {
                 if id == &self.model.id =>
             {
                 self.update_details();
+                self.model.load_album_artists();
+            }
+            AppEvent::BrowserEvent(BrowserEvent::AlbumArtistsLoaded(id))
+                if id == &self.model.id =>
+            {
+                self.update_artists();
             }
             AppEvent::BrowserEvent(BrowserEvent::AlbumSaved(id))
             | AppEvent::BrowserEvent(BrowserEvent::AlbumUnsaved(id))
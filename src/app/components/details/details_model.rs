@@ -6,6 +6,7 @@ use std::rc::Rc;
 
 use crate::api::SpotifyApiError;
 use crate::app::components::labels;
+use crate::app::components::utils::CancellableRequests;
 use crate::app::components::HeaderBarModel;
 use crate::app::components::PlaylistModel;
 use crate::app::components::SimpleHeaderBarModel;
@@ -20,6 +21,8 @@ pub struct DetailsModel {
     pub id: String,
     app_model: Rc<AppModel>,
     dispatcher: Box<dyn ActionDispatcher>,
+    // loading the album and its tracks, given up on when the view goes away
+    requests: CancellableRequests,
 }
 
 impl DetailsModel {
@@ -28,6 +31,7 @@ impl DetailsModel {
             id,
             app_model,
             dispatcher,
+            requests: Default::default(),
         }
     }
 
@@ -56,12 +60,12 @@ impl DetailsModel {
         let id = self.id.clone();
         let api = self.app_model.get_spotify();
         self.dispatcher
-            .call_spotify_and_dispatch(move || async move {
+            .call_spotify_and_dispatch_cancellable(&self.requests, move || async move {
                 let album = api.get_album(&id).await;
                 match album {
                     Ok(album) => Ok(BrowserAction::SetAlbumDetails(Box::new(album)).into()),
-                    Err(SpotifyApiError::BadStatus(400, _))
-                    | Err(SpotifyApiError::BadStatus(404, _)) => {
+                    Err(SpotifyApiError::BadStatus(400, _, _))
+                    | Err(SpotifyApiError::BadStatus(404, _, _)) => {
                         Ok(BrowserAction::NavigationPop.into())
                     }
                     Err(e) => Err(e),
@@ -69,6 +73,44 @@ impl DetailsModel {
             });
     }
 
+    pub fn get_album_artists(&self) -> Option<impl Deref<Target = Vec<ArtistSummary>> + '_> {
+        self.app_model
+            .map_state_opt(|s| Some(&s.browser.details_state(&self.id)?.artists))
+    }
+
+    // Fetches everyone credited on the album and its loaded tracks, all at once
+    pub fn load_album_artists(&self) {
+        let mut ids: Vec<String> = vec![];
+        if let Some(album) = self.get_album_description() {
+            let songs: Vec<SongDescription> = self.song_list_model().collect();
+            let artists = album
+                .artists
+                .iter()
+                .chain(songs.iter().flat_map(|song| song.artists.iter()));
+            for artist in artists {
+                if !ids.contains(&artist.id) {
+                    ids.push(artist.id.clone());
+                }
+            }
+        }
+        if ids.is_empty() {
+            return;
+        }
+
+        let id = self.id.clone();
+        let api = self.app_model.get_spotify();
+        self.dispatcher
+            .call_spotify_and_dispatch_cancellable(&self.requests, move || async move {
+                api.get_several_artists(ids)
+                    .await
+                    .map(|artists| BrowserAction::SetAlbumArtists(id, artists).into())
+            });
+    }
+
+    pub fn view_artist_with_id(&self, id: String) {
+        self.dispatcher.dispatch(AppAction::ViewArtist(id));
+    }
+
     pub fn view_artist(&self) {
         if let Some(album) = self.get_album_description() {
             let artist = &album.artists.first().unwrap().id;
@@ -110,14 +152,15 @@ impl DetailsModel {
         let next_query = query.next()?;
         let loader = self.app_model.get_batch_loader();
 
-        self.dispatcher.dispatch_async(Box::pin(async move {
-            let action = loader
-                .query(next_query, |song_batch| {
-                    BrowserAction::AppendAlbumTracks(id, Box::new(song_batch)).into()
-                })
-                .await;
-            Some(action)
-        }));
+        self.dispatcher
+            .dispatch_async(Box::pin(self.requests.wrap(async move {
+                let action = loader
+                    .query(next_query, |song_batch| {
+                        BrowserAction::AppendAlbumTracks(id, Box::new(song_batch)).into()
+                    })
+                    .await;
+                Some(action)
+            })));
 
         Some(())
     }
@@ -3,7 +3,10 @@ use gtk::subclass::prelude::*;
 use gtk::CompositeTemplate;
 use libadwaita::subclass::prelude::*;
 
-use crate::app::components::labels;
+use crate::app::components::utils::wrap_flowbox_item;
+use crate::app::components::{labels, ArtistWidget};
+use crate::app::dispatch::Worker;
+use crate::app::models::ArtistModel;
 
 mod imp {
 
@@ -26,6 +29,12 @@ mod imp {
 
         #[template_child]
         pub copyright: TemplateChild<gtk::Label>,
+
+        #[template_child]
+        pub artists_section: TemplateChild<gtk::Box>,
+
+        #[template_child]
+        pub artists: TemplateChild<gtk::FlowBox>,
     }
 
     #[glib::object_subclass]
@@ -88,4 +97,24 @@ impl ReleaseDetailsWindow {
         widget.tracks.set_text(&track_count.to_string());
         widget.copyright.set_text(copyright);
     }
+
+    pub fn bind_artists<F>(&self, worker: Worker, store: &gio::ListStore, on_artist_pressed: F)
+    where
+        F: Fn(String) + Clone + 'static,
+    {
+        self.widget().artists.bind_model(Some(store), move |item| {
+            wrap_flowbox_item(item, |artist_model| {
+                let f = on_artist_pressed.clone();
+                let artist = ArtistWidget::for_model(artist_model, worker.clone());
+                artist.connect_artist_pressed(clone!(@weak artist_model => move |_| {
+                    f(artist_model.id());
+                }));
+                artist
+            })
+        });
+    }
+
+    pub fn set_artists_visible(&self, visible: bool) {
+        self.widget().artists_section.set_visible(visible);
+    }
 }
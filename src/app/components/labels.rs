@@ -12,6 +12,33 @@ lazy_static! {
 
     // translators: This is part of a contextual menu attached to a single track; this entry removes a track from the play queue.
     pub static ref REMOVE_FROM_QUEUE: String = gettext("Remove from queue");
+
+    // translators: This is a button on an artist page, to follow an artist the user does not follow yet.
+    pub static ref FOLLOW: String = gettext("Follow");
+
+    // translators: This is a button on an artist page, shown when the user already follows the artist.
+    pub static ref FOLLOWING: String = gettext("Following");
+
+    // translators: This is the title of the file dialog used to export a playlist's tracks to a file.
+    pub static ref EXPORT_PLAYLIST: String = gettext("Export Playlist");
+
+    // translators: This is a file type offered when exporting a playlist.
+    pub static ref M3U_PLAYLIST: String = gettext("M3U playlist");
+
+    // translators: This is a file type offered when exporting a playlist.
+    pub static ref JSON_FILE: String = gettext("JSON file");
+
+    // translators: This is the title of the dialog used to paste a list of track links to add to a playlist.
+    pub static ref IMPORT_TRACKS: String = gettext("Import Tracks");
+
+    // translators: This is the confirmation button of the dialog used to add pasted track links to a playlist.
+    pub static ref IMPORT: String = gettext("Import");
+
+    // translators: This is the cancel button of the dialog used to add pasted track links to a playlist.
+    pub static ref CANCEL: String = gettext("Cancel");
+
+    // translators: This notification shows up when saving the play queue as a playlist, but none of its tracks (e.g. local files) can be added to one.
+    pub static ref NOTHING_TO_SAVE: String = gettext("None of these songs can be saved to a playlist");
 }
 
 pub fn add_to_playlist_label(playlist: &str) -> String {
@@ -53,3 +80,108 @@ pub fn album_by_artist_label(album: &str, artist: &str) -> String {
         glib::markup_escape_text(artist)
     )
 }
+
+pub fn cache_cleared_label(freed: u64) -> String {
+    // this is just to fool xgettext, it doesn't like macros (or rust for that matter) :(
+    if cfg!(debug_assertions) {
+        // translators: This notification shows up after clearing the cache, e.g. "Cache cleared, 12.3 MB freed"
+        gettext("Cache cleared, {} freed");
+    }
+    gettext!("Cache cleared, {} freed", glib::format_size(freed))
+}
+
+pub fn cache_partly_cleared_label(freed: u64) -> String {
+    // this is just to fool xgettext, it doesn't like macros (or rust for that matter) :(
+    if cfg!(debug_assertions) {
+        // translators: This notification shows up when some of the cache couldn't be removed, e.g. "Some of the cache couldn't be cleared, 12.3 MB freed"
+        gettext("Some of the cache couldn't be cleared, {} freed");
+    }
+    gettext!(
+        "Some of the cache couldn't be cleared, {} freed",
+        glib::format_size(freed)
+    )
+}
+
+pub fn queue_playlist_title(date: &str) -> String {
+    // this is just to fool xgettext, it doesn't like macros (or rust for that matter) :(
+    if cfg!(debug_assertions) {
+        // translators: This is the name given to a playlist created from the play queue, e.g. "Queue of 10/14/26"
+        gettext("Queue of {}");
+    }
+    gettext!("Queue of {}", date)
+}
+
+pub fn queue_saved_label(saved: usize, skipped: usize) -> String {
+    // this is just to fool xgettext, it doesn't like macros (or rust for that matter) :(
+    if cfg!(debug_assertions) {
+        // translators: This notification shows up after saving the play queue as a playlist.
+        ngettext(
+            "{} song saved to a new playlist",
+            "{} songs saved to a new playlist",
+            saved as u32,
+        );
+        // translators: This notification shows up after saving the play queue as a playlist, when some of its tracks (e.g. local files) could not be added.
+        ngettext(
+            "{} song saved to a new playlist, {} skipped",
+            "{} songs saved to a new playlist, {} skipped",
+            saved as u32,
+        );
+    }
+    if skipped == 0 {
+        ngettext!(
+            "{} song saved to a new playlist",
+            "{} songs saved to a new playlist",
+            saved as u32,
+            saved
+        )
+    } else {
+        ngettext!(
+            "{} song saved to a new playlist, {} skipped",
+            "{} songs saved to a new playlist, {} skipped",
+            saved as u32,
+            saved,
+            skipped
+        )
+    }
+}
+
+pub fn playlist_exported_label(n: usize) -> String {
+    // this is just to fool xgettext, it doesn't like macros (or rust for that matter) :(
+    if cfg!(debug_assertions) {
+        // translators: This notification shows up after exporting a playlist to a file.
+        ngettext("{} song exported", "{} songs exported", n as u32);
+    }
+    ngettext!("{} song exported", "{} songs exported", n as u32, n)
+}
+
+// Failed lines beyond this are only counted, so that the notification stays readable
+const MAX_REPORTED_LINES: usize = 3;
+
+pub fn tracks_imported_label(n: usize, failed: &[String]) -> String {
+    // this is just to fool xgettext, it doesn't like macros (or rust for that matter) :(
+    if cfg!(debug_assertions) {
+        // translators: This notification shows up after adding pasted track links to a playlist.
+        ngettext("{} song imported", "{} songs imported", n as u32);
+        // translators: This notification shows up after adding pasted track links to a playlist, when some lines were not valid track links. The second placeholder lists these lines.
+        ngettext(
+            "{} song imported, could not read: {}",
+            "{} songs imported, could not read: {}",
+            n as u32,
+        );
+    }
+    if failed.is_empty() {
+        return ngettext!("{} song imported", "{} songs imported", n as u32, n);
+    }
+
+    let mut lines = failed[..failed.len().min(MAX_REPORTED_LINES)].join(", ");
+    if failed.len() > MAX_REPORTED_LINES {
+        lines.push_str(&format!(" (+{})", failed.len() - MAX_REPORTED_LINES));
+    }
+    ngettext!(
+        "{} song imported, could not read: {}",
+        "{} songs imported, could not read: {}",
+        n as u32,
+        n,
+        lines
+    )
+}
@@ -11,7 +11,8 @@ use std::collections::HashSet;
 use std::future::Future;
 
 use crate::api::SpotifyApiError;
-use crate::app::{state::LoginAction, ActionDispatcher, AppAction, AppEvent};
+use crate::app::components::utils::CancellableRequests;
+use crate::app::{ActionDispatcher, AppAction, AppEvent};
 
 mod navigation;
 pub use navigation::*;
@@ -31,6 +32,9 @@ pub use settings::*;
 mod player_notifier;
 pub use player_notifier::PlayerNotifier;
 
+mod preview_player;
+pub use preview_player::PreviewPlayer;
+
 mod library;
 pub use library::*;
 
@@ -93,37 +97,69 @@ pub fn expose_widgets() {
 impl dyn ActionDispatcher {
     fn call_spotify_and_dispatch<F, C>(&self, call: C)
     where
-        C: 'static + Send + Clone + FnOnce() -> F,
+        C: 'static + Send + FnOnce() -> F,
         F: Send + Future<Output = Result<AppAction, SpotifyApiError>>,
     {
         self.call_spotify_and_dispatch_many(move || async { call().await.map(|a| vec![a]) })
     }
 
+    // Same as call_spotify_and_dispatch, but nothing happens if requests are cancelled first
+    fn call_spotify_and_dispatch_cancellable<F, C>(&self, requests: &CancellableRequests, call: C)
+    where
+        C: 'static + Send + FnOnce() -> F,
+        F: Send + Future<Output = Result<AppAction, SpotifyApiError>>,
+    {
+        let actions = spotify_call_actions(move || async { call().await.map(|a| vec![a]) });
+        self.dispatch_many_async(Box::pin(requests.wrap(actions)))
+    }
+
     fn call_spotify_and_dispatch_many<F, C>(&self, call: C)
     where
-        C: 'static + Send + Clone + FnOnce() -> F,
+        C: 'static + Send + FnOnce() -> F,
         F: Send + Future<Output = Result<Vec<AppAction>, SpotifyApiError>>,
     {
-        self.dispatch_many_async(Box::pin(async move {
-            let first_call = call.clone();
-            let result = first_call().await;
-            match result {
-                Ok(actions) => actions,
-                Err(SpotifyApiError::NoToken) => vec![],
-                Err(SpotifyApiError::InvalidToken) => {
-                    let mut retried = call().await.unwrap_or_else(|_| Vec::new());
-                    retried.push(LoginAction::RefreshToken.into());
-                    retried
-                }
-                Err(err) => {
-                    error!("Spotify API error: {}", err);
-                    vec![AppAction::ShowNotification(gettext(
-                        // translators: This notification is the default message for unhandled errors. Logs refer to console output.
-                        "An error occured. Check logs for details!",
-                    ))]
-                }
+        self.dispatch_many_async(Box::pin(spotify_call_actions(call)))
+    }
+}
+
+// Runs the call, turning the errors we know how to handle into actions
+fn spotify_call_actions<F, C>(call: C) -> impl Future<Output = Vec<AppAction>> + Send
+where
+    C: 'static + Send + FnOnce() -> F,
+    F: Send + Future<Output = Result<Vec<AppAction>, SpotifyApiError>>,
+{
+    async move {
+        let result = call().await;
+        match result {
+            Ok(actions) => actions,
+            Err(SpotifyApiError::NoToken) => vec![],
+            Err(SpotifyApiError::Offline) => {
+                vec![AppAction::ShowNotification(gettext(
+                    // translators: This notification is shown when a request fails because there is no network connection.
+                    "You're offline. Check your network connection!",
+                ))]
+            }
+            Err(SpotifyApiError::StaleSnapshot) => {
+                vec![AppAction::ShowNotification(gettext(
+                    // translators: This notification is shown when editing a playlist fails because it was changed from another device.
+                    "This playlist was changed elsewhere. Reload it and try again!",
+                ))]
             }
-        }))
+            Err(err) if err.is_missing_scope() => {
+                warn!("Spotify API error: {}", err);
+                vec![AppAction::ShowNotification(gettext(
+                    // translators: This notification is shown when Spot wasn't granted a permission it needs, logging in again asks for it.
+                    "Spot isn't allowed to do this. Log out and back in to grant the missing permission!",
+                ))]
+            }
+            Err(err) => {
+                error!("Spotify API error: {}", err);
+                vec![AppAction::ShowNotification(gettext(
+                    // translators: This notification is the default message for unhandled errors. Logs refer to console output.
+                    "An error occured. Check logs for details!",
+                ))]
+            }
+        }
     }
 }
 
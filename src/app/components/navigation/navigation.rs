@@ -148,6 +148,9 @@ impl EventListener for Navigation {
             AppEvent::BrowserEvent(BrowserEvent::NavigationPoppedTo(name)) => {
                 self.pop_to(name);
             }
+            AppEvent::TrackOpened(id) => {
+                self.model.open_track(id);
+            }
             _ => {}
         };
         for child in self.children.iter_mut() {
@@ -1,5 +1,5 @@
 use crate::app::state::ScreenName;
-use crate::app::{ActionDispatcher, AppModel, BrowserAction};
+use crate::app::{ActionDispatcher, AppAction, AppModel, BrowserAction};
 use std::ops::Deref;
 use std::rc::Rc;
 
@@ -21,6 +21,18 @@ impl NavigationModel {
             .dispatch(BrowserAction::NavigationPopTo(ScreenName::Home).into())
     }
 
+    // Tracks have no screen of their own, so we show the album they belong to
+    pub fn open_track(&self, id: &str) {
+        let id = id.to_owned();
+        let api = self.app_model.get_spotify();
+        self.dispatcher
+            .call_spotify_and_dispatch(move || async move {
+                api.get_track(&id)
+                    .await
+                    .map(|song| AppAction::ViewAlbum(song.album.id))
+            });
+    }
+
     pub fn visible_child_name(&self) -> impl Deref<Target = ScreenName> + '_ {
         self.app_model.map_state(|s| s.browser.current_screen())
     }
@@ -20,6 +20,9 @@ mod imp {
 
         #[template_child]
         pub scrolled_window: TemplateChild<gtk::ScrolledWindow>,
+
+        #[template_child]
+        pub save_as_playlist: TemplateChild<gtk::Button>,
     }
 
     #[glib::object_subclass]
@@ -64,6 +67,15 @@ impl NowPlayingWidget {
             });
     }
 
+    fn connect_save_as_playlist<F>(&self, f: F)
+    where
+        F: Fn() + 'static,
+    {
+        imp::NowPlayingWidget::from_instance(self)
+            .save_as_playlist
+            .connect_clicked(move |_| f());
+    }
+
     fn song_list_widget(&self) -> &gtk::ListView {
         imp::NowPlayingWidget::from_instance(self)
             .song_list
@@ -85,6 +97,10 @@ impl NowPlaying {
             model.load_more();
         }));
 
+        widget.connect_save_as_playlist(clone!(@weak model => move || {
+            model.save_as_playlist();
+        }));
+
         let playlist = Playlist::new(widget.song_list_widget().clone(), model.clone(), worker);
 
         Self {
@@ -6,10 +6,12 @@ use std::rc::Rc;
 
 use crate::app::components::SimpleHeaderBarModel;
 use crate::app::components::{labels, PlaylistModel};
-use crate::app::models::SongDescription;
 use crate::app::models::SongListModel;
+use crate::app::models::{PlaylistSummary, SongDescription};
 use crate::app::state::SelectionContext;
-use crate::app::state::{PlaybackAction, PlaybackState, SelectionAction, SelectionState};
+use crate::app::state::{
+    LoginAction, PlaybackAction, PlaybackState, SelectionAction, SelectionState,
+};
 use crate::app::{ActionDispatcher, AppAction, AppEvent, AppModel};
 
 pub struct NowPlayingModel {
@@ -47,6 +49,61 @@ impl NowPlayingModel {
 
         Some(())
     }
+
+    // Pages of the queue not loaded yet are fetched first, local files and unplayable tracks are left out
+    pub fn save_as_playlist(&self) -> Option<()> {
+        let api = self.app_model.get_spotify();
+        let loader = self.app_model.get_batch_loader();
+        let state = self.app_model.get_state();
+        let user = state.logged_user.user.clone()?;
+        let playlists = state.logged_user.playlists.clone();
+
+        let mut songs: Vec<SongDescription> = self.queue().songs().collect();
+        let remaining = self.queue().next_query();
+
+        let date = glib::DateTime::now_local()
+            .and_then(|now| now.format("%x"))
+            .map(|date| date.to_string())
+            .unwrap_or_default();
+        let title = labels::queue_playlist_title(&date);
+
+        self.dispatcher
+            .call_spotify_and_dispatch_many(move || async move {
+                if let Some(query) = remaining {
+                    songs.extend(loader.query_remaining(query).await?);
+                }
+                let uris: Vec<String> = songs
+                    .iter()
+                    .filter(|song| song.is_playable && !song.uri.starts_with("spotify:local:"))
+                    .map(|song| song.uri.clone())
+                    .collect();
+                let saved = uris.len();
+                let skipped = songs.len() - saved;
+                if uris.is_empty() {
+                    return Ok(vec![AppAction::ShowNotification(
+                        labels::NOTHING_TO_SAVE.clone(),
+                    )]);
+                }
+
+                let playlist = api.create_playlist(&user, &title, false).await?;
+                api.add_to_playlist(&playlist.id, uris).await?;
+
+                let mut playlists = playlists;
+                playlists.insert(
+                    0,
+                    PlaylistSummary {
+                        id: playlist.id,
+                        title: playlist.title,
+                    },
+                );
+                Ok(vec![
+                    LoginAction::SetUserPlaylists(playlists).into(),
+                    AppAction::ShowNotification(labels::queue_saved_label(saved, skipped)),
+                ])
+            });
+
+        Some(())
+    }
 }
 
 impl PlaylistModel for NowPlayingModel {
@@ -76,7 +133,9 @@ impl PlaylistModel for NowPlayingModel {
         for view_artist in song.make_artist_actions(self.dispatcher.box_clone(), None) {
             group.add_action(&view_artist);
         }
-        group.add_action(&song.make_album_action(self.dispatcher.box_clone(), None));
+        if let Some(view_album) = song.make_album_action(self.dispatcher.box_clone(), None) {
+            group.add_action(&view_album);
+        }
         group.add_action(&song.make_link_action(None));
         group.add_action(&song.make_dequeue_action(self.dispatcher.box_clone(), None));
 
@@ -89,12 +148,14 @@ impl PlaylistModel for NowPlayingModel {
         let song = song.description();
 
         let menu = gio::Menu::new();
-        menu.append(Some(&*labels::VIEW_ALBUM), Some("song.view_album"));
-        for artist in song.artists.iter() {
-            menu.append(
-                Some(&labels::more_from_label(&artist.name)),
-                Some(&format!("song.view_artist_{}", artist.id)),
-            );
+        if !song.is_episode() {
+            menu.append(Some(&*labels::VIEW_ALBUM), Some("song.view_album"));
+            for artist in song.artists.iter() {
+                menu.append(
+                    Some(&labels::more_from_label(&artist.name)),
+                    Some(&format!("song.view_artist_{}", artist.id)),
+                );
+            }
         }
 
         menu.append(Some(&*labels::COPY_LINK), Some("song.copy_link"));
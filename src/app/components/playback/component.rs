@@ -2,8 +2,8 @@ use std::ops::Deref;
 use std::rc::Rc;
 
 use crate::app::components::EventListener;
-use crate::app::models::SongDescription;
-use crate::app::state::{PlaybackAction, PlaybackEvent, RepeatMode, ScreenName, SelectionEvent};
+use crate::app::models::{RepeatMode, SongDescription};
+use crate::app::state::{PlaybackAction, PlaybackEvent, ScreenName, SelectionEvent};
 use crate::app::{
     ActionDispatcher, AppAction, AppEvent, AppModel, AppState, BrowserAction, Worker,
 };
@@ -41,6 +41,10 @@ impl PlaybackModel {
         self.state().playback.is_shuffled()
     }
 
+    fn is_preview(&self) -> bool {
+        self.state().playback.is_preview()
+    }
+
     fn current_song(&self) -> Option<SongDescription> {
         self.app_model.get_state().playback.current_song()
     }
@@ -122,6 +126,7 @@ impl PlaybackControl {
         } else {
             self.widget.reset_info();
         }
+        self.widget.set_preview(self.model.is_preview());
     }
 
     fn sync_seek(&self, pos: u32) {
@@ -145,6 +150,9 @@ impl EventListener for PlaybackControl {
             AppEvent::PlaybackEvent(PlaybackEvent::TrackChanged(_)) => {
                 self.update_current_info();
             }
+            AppEvent::PlaybackEvent(PlaybackEvent::PreviewStarted(_)) => {
+                self.widget.set_preview(self.model.is_preview());
+            }
             AppEvent::PlaybackEvent(PlaybackEvent::PlaybackStopped) => {
                 self.update_playing();
                 self.update_current_info();
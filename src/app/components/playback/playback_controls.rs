@@ -3,7 +3,7 @@ use gtk::prelude::*;
 use gtk::subclass::prelude::*;
 use gtk::{glib, CompositeTemplate};
 
-use crate::app::state::RepeatMode;
+use crate::app::models::RepeatMode;
 
 mod imp {
 
@@ -81,9 +81,9 @@ impl PlaybackControlsWidget {
 
     pub fn set_repeat_mode(&self, mode: RepeatMode) {
         let repeat_mode_icon = match mode {
-            RepeatMode::Song => "media-playlist-repeat-song-symbolic",
-            RepeatMode::Playlist => "media-playlist-repeat-symbolic",
-            RepeatMode::None => "media-playlist-consecutive-symbolic",
+            RepeatMode::Track => "media-playlist-repeat-song-symbolic",
+            RepeatMode::Context => "media-playlist-repeat-symbolic",
+            RepeatMode::Off => "media-playlist-consecutive-symbolic",
         };
 
         imp::PlaybackControlsWidget::from_instance(self)
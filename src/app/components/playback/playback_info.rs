@@ -15,6 +15,9 @@ mod imp {
 
         #[template_child]
         pub current_song_info: TemplateChild<gtk::Label>,
+
+        #[template_child]
+        pub preview_badge: TemplateChild<gtk::Label>,
     }
 
     #[glib::object_subclass]
@@ -70,6 +73,12 @@ impl PlaybackInfoWidget {
             .set_visible(visible);
     }
 
+    pub fn set_preview(&self, is_preview: bool) {
+        imp::PlaybackInfoWidget::from_instance(self)
+            .preview_badge
+            .set_visible(is_preview);
+    }
+
     pub fn set_artwork(&self, art: &gdk_pixbuf::Pixbuf) {
         imp::PlaybackInfoWidget::from_instance(self)
             .playing_image
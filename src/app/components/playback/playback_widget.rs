@@ -5,7 +5,7 @@ use gtk::{glib, CompositeTemplate};
 use crate::app::components::display_add_css_provider;
 use crate::app::components::utils::{format_duration, Clock, Debouncer};
 use crate::app::loader::ImageLoader;
-use crate::app::state::RepeatMode;
+use crate::app::models::RepeatMode;
 use crate::app::Worker;
 
 use super::playback_controls::PlaybackControlsWidget;
@@ -87,6 +87,12 @@ impl PlaybackWidget {
         self.set_song_duration(None);
     }
 
+    pub fn set_preview(&self, is_preview: bool) {
+        let widget = imp::PlaybackWidget::from_instance(self);
+        widget.now_playing.set_preview(is_preview);
+        widget.now_playing_mobile.set_preview(is_preview);
+    }
+
     fn set_artwork(&self, image: &gdk_pixbuf::Pixbuf) {
         let widget = imp::PlaybackWidget::from_instance(self);
         widget.now_playing.set_artwork(image);
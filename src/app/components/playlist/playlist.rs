@@ -123,7 +123,7 @@ where
             let selection_enabled = model.is_selection_enabled();
             if selection_enabled {
                 model.toggle_select(&song.id);
-            } else {
+            } else if song.is_playable {
                 model.play_song_at(position as usize, &song.id);
             }
         }));
@@ -35,6 +35,9 @@ mod imp {
         #[template_child]
         pub song_length: TemplateChild<gtk::Label>,
 
+        #[template_child]
+        pub song_explicit: TemplateChild<gtk::Label>,
+
         #[template_child]
         pub menu_btn: TemplateChild<gtk::MenuButton>,
 
@@ -173,6 +176,16 @@ impl SongWidget {
         }
     }
 
+    fn set_playable(&self, is_playable: bool) {
+        let song_class = "song--unplayable";
+        let context = self.style_context();
+        if is_playable {
+            context.remove_class(song_class);
+        } else {
+            context.add_class(song_class);
+        }
+    }
+
     fn set_image(&self, pixbuf: Option<&gdk_pixbuf::Pixbuf>) {
         self.widget().song_cover.set_from_pixbuf(pixbuf);
     }
@@ -198,6 +211,10 @@ impl SongWidget {
         model.bind_duration(&*widget.song_length, "label");
         model.bind_playing(self, "playing");
         model.bind_selected(self, "selected");
+        widget
+            .song_explicit
+            .set_visible(model.description().explicit);
+        self.set_playable(model.description().is_playable);
 
         self.set_show_cover(show_cover);
         if show_cover {
@@ -33,10 +33,17 @@ impl SongDescription {
     }
 
     pub fn make_link_action(&self, name: Option<&str>) -> SimpleAction {
-        let track_id = self.id.clone();
+        let kind = if self.is_episode() {
+            "episode"
+        } else {
+            "track"
+        };
+        let link = self
+            .external_url
+            .clone()
+            .unwrap_or_else(|| format!("https://open.spotify.com/{}/{}", kind, &self.id));
         let copy_link = SimpleAction::new(name.unwrap_or("copy_link"), None);
         copy_link.connect_activate(move |_, _| {
-            let link = format!("https://open.spotify.com/track/{}", &track_id);
             let clipboard = gdk::Display::default().unwrap().clipboard();
             clipboard
                 .set_content(Some(&gdk::ContentProvider::for_value(&link.to_value())))
@@ -45,17 +52,21 @@ impl SongDescription {
         copy_link
     }
 
+    // An episode's show is neither an album nor an artist, so episodes get no actions to view them
     pub fn make_album_action(
         &self,
         dispatcher: Box<dyn ActionDispatcher>,
         name: Option<&str>,
-    ) -> SimpleAction {
+    ) -> Option<SimpleAction> {
+        if self.is_episode() {
+            return None;
+        }
         let album_id = self.album.id.clone();
         let view_album = SimpleAction::new(name.unwrap_or("view_album"), None);
         view_album.connect_activate(move |_, _| {
             dispatcher.dispatch(AppAction::ViewAlbum(album_id.clone()));
         });
-        view_album
+        Some(view_album)
     }
 
     pub fn make_artist_actions(
@@ -63,6 +74,9 @@ impl SongDescription {
         dispatcher: Box<dyn ActionDispatcher>,
         prefix: Option<&str>,
     ) -> Vec<SimpleAction> {
+        if self.is_episode() {
+            return vec![];
+        }
         self.artists
             .iter()
             .map(|artist| {
@@ -1,17 +1,21 @@
 use gtk::prelude::*;
 use gtk::subclass::prelude::*;
 use gtk::CompositeTemplate;
+use std::path::PathBuf;
 use std::rc::Rc;
 
 use super::PlaylistDetailsModel;
 use crate::app::components::AlbumHeaderWidget;
 
-use crate::app::components::{Component, EventListener, Playlist};
+use crate::app::components::{labels, Component, EventListener, Playlist};
 use crate::app::dispatch::Worker;
 use crate::app::loader::ImageLoader;
 use crate::app::{AppEvent, BrowserEvent};
 use libadwaita::subclass::prelude::BinImpl;
 
+// Fraction of the list that has to be scrolled through before the next page is prefetched
+const PREFETCH_THRESHOLD: f64 = 0.75;
+
 mod imp {
 
     use super::*;
@@ -56,6 +60,7 @@ mod imp {
             self.header_mobile.set_centered();
             self.header_mobile.hide_actions();
             self.header_widget.hide_actions();
+            self.header_widget.set_export_visible(true);
         }
     }
 
@@ -93,6 +98,22 @@ impl PlaylistDetailsWidget {
             });
     }
 
+    // Called once most of what's loaded has been scrolled through
+    fn connect_near_bottom<F>(&self, f: F)
+    where
+        F: Fn() + 'static,
+    {
+        self.widget()
+            .scrolled_window
+            .vadjustment()
+            .connect_value_changed(move |adj| {
+                let seen = adj.value() + adj.page_size();
+                if adj.upper() > 0.0 && seen >= PREFETCH_THRESHOLD * adj.upper() {
+                    f()
+                }
+            });
+    }
+
     fn set_header_visible(&self, visible: bool) -> bool {
         let widget = self.widget();
         let is_up_to_date = widget.header_revealer.reveals_child() == visible;
@@ -136,6 +157,121 @@ impl PlaylistDetailsWidget {
         self.widget().header_mobile.set_artwork(art);
     }
 
+    fn set_followed(&self, is_followed: Option<bool>) {
+        let widget = self.widget();
+        widget.header_widget.set_like_visible(is_followed.is_some());
+        widget.header_widget.set_liked(is_followed.unwrap_or(false));
+    }
+
+    fn connect_followed<F>(&self, f: F)
+    where
+        F: Fn() + 'static,
+    {
+        self.widget().header_widget.connect_liked(f);
+    }
+
+    // Asks where to save the playlist, the file's extension picks the format
+    fn connect_export<F>(&self, f: F)
+    where
+        F: Fn(PathBuf) + Clone + 'static,
+    {
+        self.widget().header_widget.connect_export(
+            clone!(@weak self as _self => move || _self.choose_export_path(f.clone())),
+        );
+    }
+
+    fn choose_export_path<F>(&self, f: F)
+    where
+        F: Fn(PathBuf) + 'static,
+    {
+        let window = self
+            .root()
+            .and_then(|root| root.downcast::<gtk::Window>().ok());
+        let dialog = gtk::FileChooserNative::new(
+            Some(&*labels::EXPORT_PLAYLIST),
+            window.as_ref(),
+            gtk::FileChooserAction::Save,
+            None,
+            None,
+        );
+        dialog.set_modal(true);
+
+        let title = self.widget().header_widget.widget().album_label.label();
+        dialog.set_current_name(&format!("{}.m3u", title));
+
+        let m3u = gtk::FileFilter::new();
+        m3u.set_name(Some(&*labels::M3U_PLAYLIST));
+        m3u.add_pattern("*.m3u");
+        m3u.add_pattern("*.m3u8");
+        dialog.add_filter(&m3u);
+
+        let json = gtk::FileFilter::new();
+        json.set_name(Some(&*labels::JSON_FILE));
+        json.add_pattern("*.json");
+        dialog.add_filter(&json);
+
+        // the dialog is only kept alive by this handler until it gets a response
+        dialog.connect_response(clone!(@strong dialog => move |_, response| {
+            if response == gtk::ResponseType::Accept {
+                if let Some(path) = dialog.file().and_then(|file| file.path()) {
+                    f(path);
+                }
+            }
+            dialog.destroy();
+        }));
+        dialog.show();
+    }
+
+    // Asks for a list of track links, one per line
+    fn connect_import<F>(&self, f: F)
+    where
+        F: Fn(String) + Clone + 'static,
+    {
+        self.widget()
+            .header_widget
+            .connect_import(clone!(@weak self as _self => move || _self.ask_track_list(f.clone())));
+    }
+
+    fn ask_track_list<F>(&self, f: F)
+    where
+        F: Fn(String) + 'static,
+    {
+        let window = self
+            .root()
+            .and_then(|root| root.downcast::<gtk::Window>().ok());
+        let dialog = gtk::Dialog::with_buttons(
+            Some(&*labels::IMPORT_TRACKS),
+            window.as_ref(),
+            gtk::DialogFlags::MODAL | gtk::DialogFlags::DESTROY_WITH_PARENT,
+            &[
+                (labels::CANCEL.as_str(), gtk::ResponseType::Cancel),
+                (labels::IMPORT.as_str(), gtk::ResponseType::Accept),
+            ],
+        );
+        dialog.set_default_size(480, 320);
+
+        let text_view = gtk::TextView::new();
+        text_view.set_monospace(true);
+        let scrolled_window = gtk::ScrolledWindow::new();
+        scrolled_window.set_vexpand(true);
+        scrolled_window.set_child(Some(&text_view));
+        dialog.content_area().append(&scrolled_window);
+
+        dialog.connect_response(move |dialog, response| {
+            if response == gtk::ResponseType::Accept {
+                let buffer = text_view.buffer();
+                let (start, end) = buffer.bounds();
+                f(buffer.text(&start, &end, false).to_string());
+            }
+            dialog.destroy();
+        });
+        dialog.show();
+    }
+
+    fn set_import_visible(&self, visible: bool) {
+        self.widget().header_widget.set_import_visible(visible);
+    }
+
     fn connect_artist_clicked<F>(&self, f: F)
     where
         F: Fn() + Clone + 'static,
@@ -159,6 +295,7 @@ impl PlaylistDetails {
         if model.get_playlist_info().is_none() {
             model.load_playlist_info();
         }
+        model.load_follow_state();
         let widget = PlaylistDetailsWidget::new();
         let playlist = Box::new(Playlist::new(
             widget.playlist_tracks_widget().clone(),
@@ -172,10 +309,26 @@ impl PlaylistDetails {
             model.load_more_tracks();
         }));
 
+        widget.connect_near_bottom(clone!(@weak model => move || {
+            model.prefetch_more_tracks();
+        }));
+
         widget.connect_artist_clicked(clone!(@weak model => move || {
             model.view_owner();
         }));
 
+        widget.connect_followed(clone!(@weak model => move || {
+            model.toggle_follow();
+        }));
+
+        widget.connect_export(clone!(@weak model => move |path| {
+            model.export(path);
+        }));
+
+        widget.connect_import(clone!(@weak model => move |text| {
+            model.import(&text);
+        }));
+
         Self {
             model,
             worker,
@@ -226,7 +379,15 @@ impl EventListener for PlaylistDetails {
             AppEvent::BrowserEvent(BrowserEvent::PlaylistDetailsLoaded(id))
                 if id == &self.model.id =>
             {
-                self.update_details()
+                self.update_details();
+                self.widget.set_followed(self.model.is_followed());
+                self.widget
+                    .set_import_visible(self.model.is_playlist_editable());
+            }
+            AppEvent::BrowserEvent(BrowserEvent::PlaylistFollowChanged(id))
+                if id == &self.model.id =>
+            {
+                self.widget.set_followed(self.model.is_followed());
             }
             _ => {}
         }
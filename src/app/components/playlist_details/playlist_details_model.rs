@@ -1,9 +1,15 @@
+use async_std::fs::File;
+use futures::io::BufWriter;
 use gio::prelude::*;
 use gio::SimpleActionGroup;
 use std::ops::Deref;
+use std::path::PathBuf;
 use std::rc::Rc;
 
+use super::playlist_export::{write_playlist, PlaylistExportFormat};
+use super::playlist_import::{parse_track_list, ParsedImport};
 use crate::api::SpotifyApiError;
+use crate::app::components::utils::CancellableRequests;
 use crate::app::components::SimpleHeaderBarModel;
 use crate::app::components::{labels, PlaylistModel};
 use crate::app::models::*;
@@ -16,6 +22,7 @@ pub struct PlaylistDetailsModel {
     _editable_selection_context: SelectionContext,
     app_model: Rc<AppModel>,
     dispatcher: Box<dyn ActionDispatcher>,
+    requests: CancellableRequests,
 }
 
 impl PlaylistDetailsModel {
@@ -25,10 +32,11 @@ impl PlaylistDetailsModel {
             _editable_selection_context: SelectionContext::EditablePlaylist(id),
             app_model,
             dispatcher,
+            requests: Default::default(),
         }
     }
 
-    fn is_playlist_editable(&self) -> bool {
+    pub fn is_playlist_editable(&self) -> bool {
         let state = self.app_model.get_state();
         state.logged_user.playlists.iter().any(|p| p.id == self.id)
     }
@@ -46,14 +54,14 @@ impl PlaylistDetailsModel {
         let api = self.app_model.get_spotify();
         let id = self.id.clone();
         self.dispatcher
-            .call_spotify_and_dispatch(move || async move {
+            .call_spotify_and_dispatch_cancellable(&self.requests, move || async move {
                 let playlist = api.get_playlist(&id).await;
                 match playlist {
                     Ok(playlist) => {
                         Ok(BrowserAction::SetPlaylistDetails(Box::new(playlist)).into())
                     }
-                    Err(SpotifyApiError::BadStatus(400, _))
-                    | Err(SpotifyApiError::BadStatus(404, _)) => {
+                    Err(SpotifyApiError::BadStatus(400, _, _))
+                    | Err(SpotifyApiError::BadStatus(404, _, _)) => {
                         Ok(BrowserAction::NavigationPop.into())
                     }
                     Err(e) => Err(e),
@@ -61,30 +69,138 @@ impl PlaylistDetailsModel {
             });
     }
 
-    pub fn load_more_tracks(&self) -> Option<()> {
+    // Only meaningful for playlists owned by someone else
+    pub fn is_followed(&self) -> Option<bool> {
+        let state = self.app_model.get_state();
+        let playlist_state = state.browser.playlist_details_state(&self.id)?;
+        let is_owned =
+            playlist_state.playlist.as_ref()?.owner.id == *state.logged_user.user.as_ref()?;
+        playlist_state.is_followed.filter(|_| !is_owned)
+    }
+
+    pub fn load_follow_state(&self) {
+        let api = self.app_model.get_spotify();
+        let id = self.id.clone();
+        if let Some(user) = self.app_model.get_state().logged_user.user.clone() {
+            self.dispatcher
+                .call_spotify_and_dispatch(move || async move {
+                    api.do_users_follow_playlist(&id, vec![user])
+                        .await
+                        .map(|following| {
+                            let is_followed = following.first().cloned().unwrap_or(false);
+                            BrowserAction::SetPlaylistFollowed(id, is_followed).into()
+                        })
+                });
+        }
+    }
+
+    pub fn toggle_follow(&self) {
+        let id = self.id.clone();
+        let is_followed = self.is_followed().unwrap_or(false);
+        let api = self.app_model.get_spotify();
+
+        self.dispatcher
+            .call_spotify_and_dispatch(move || async move {
+                if !is_followed {
+                    api.follow_playlist(&id, true)
+                        .await
+                        .map(|_| BrowserAction::SetPlaylistFollowed(id, true).into())
+                } else {
+                    api.unfollow_playlist(&id)
+                        .await
+                        .map(|_| BrowserAction::SetPlaylistFollowed(id, false).into())
+                }
+            });
+    }
+
+    fn next_tracks_query(&self) -> Option<BatchQuery> {
         let last_batch = self.song_list_model().last_batch()?;
         let query = BatchQuery {
             source: SongsSource::Playlist(self.id.clone()),
             batch: last_batch,
         };
+        query.next()
+    }
+
+    // Only does something if prefetching is enabled in the settings
+    pub fn prefetch_more_tracks(&self) -> Option<()> {
+        let next_query = self.next_tracks_query()?;
+        self.app_model.get_batch_loader().prefetch(next_query);
+        Some(())
+    }
 
+    pub fn load_more_tracks(&self) -> Option<()> {
         let id = self.id.clone();
-        let next_query = query.next()?;
+        let next_query = self.next_tracks_query()?;
         debug!("next_query = {:?}", &next_query);
         let loader = self.app_model.get_batch_loader();
 
-        self.dispatcher.dispatch_async(Box::pin(async move {
-            let action = loader
-                .query(next_query, |song_batch| {
-                    BrowserAction::AppendPlaylistTracks(id, Box::new(song_batch)).into()
-                })
-                .await;
-            Some(action)
-        }));
+        self.dispatcher
+            .dispatch_async(Box::pin(self.requests.wrap(async move {
+                let action = loader
+                    .query(next_query, |song_batch| {
+                        BrowserAction::AppendPlaylistTracks(id, Box::new(song_batch)).into()
+                    })
+                    .await;
+                Some(action)
+            })));
 
         Some(())
     }
 
+    pub fn export(&self, path: PathBuf) {
+        let api = self.app_model.get_spotify();
+        let id = self.id.clone();
+        let title = self
+            .get_playlist_info()
+            .map(|playlist| playlist.title.clone())
+            .unwrap_or_default();
+        let format = PlaylistExportFormat::for_path(&path);
+
+        self.dispatcher
+            .call_spotify_and_dispatch(move || async move {
+                let mut out = BufWriter::new(File::create(&path).await?);
+                let tracks = api.get_all_playlist_tracks(&id);
+                let count = write_playlist(format, &title, tracks, &mut out).await?;
+                Ok(AppAction::ShowNotification(
+                    labels::playlist_exported_label(count),
+                ))
+            });
+    }
+
+    // Lines that aren't track links, or tracks that can't be found or played, are left out
+    pub fn import(&self, text: &str) {
+        let api = self.app_model.get_spotify();
+        let id = self.id.clone();
+        let ParsedImport { ids, failed } = parse_track_list(text);
+
+        self.dispatcher
+            .call_spotify_and_dispatch_many(move || async move {
+                let songs = if ids.is_empty() {
+                    vec![]
+                } else {
+                    api.get_several_tracks(ids).await?
+                };
+                let uris: Vec<String> = songs
+                    .into_iter()
+                    .filter(|song| song.is_playable)
+                    .map(|song| song.uri)
+                    .collect();
+                let notification =
+                    AppAction::ShowNotification(labels::tracks_imported_label(uris.len(), &failed));
+                if uris.is_empty() {
+                    return Ok(vec![notification]);
+                }
+
+                api.add_to_playlist(&id, uris).await?;
+                let playlist = api.get_playlist(&id).await?;
+                Ok(vec![
+                    BrowserAction::SetPlaylistDetails(Box::new(playlist)).into(),
+                    notification,
+                ])
+            });
+    }
+
     pub fn view_owner(&self) {
         if let Some(playlist) = self.get_playlist_info() {
             let owner = &playlist.owner.id;
@@ -94,6 +210,14 @@ impl PlaylistDetailsModel {
     }
 }
 
+impl Drop for PlaylistDetailsModel {
+    fn drop(&mut self) {
+        self.app_model
+            .get_batch_loader()
+            .cancel_prefetch(&SongsSource::Playlist(self.id.clone()));
+    }
+}
+
 impl PlaylistModel for PlaylistDetailsModel {
     fn song_list_model(&self) -> SongListModel {
         self.app_model
@@ -129,7 +253,9 @@ impl PlaylistModel for PlaylistDetailsModel {
         for view_artist in song.make_artist_actions(self.dispatcher.box_clone(), None) {
             group.add_action(&view_artist);
         }
-        group.add_action(&song.make_album_action(self.dispatcher.box_clone(), None));
+        if let Some(view_album) = song.make_album_action(self.dispatcher.box_clone(), None) {
+            group.add_action(&view_album);
+        }
         group.add_action(&song.make_link_action(None));
         group.add_action(&song.make_queue_action(self.dispatcher.box_clone(), None));
 
@@ -141,12 +267,15 @@ impl PlaylistModel for PlaylistDetailsModel {
         let song = song.description();
 
         let menu = gio::Menu::new();
-        menu.append(Some(&*labels::VIEW_ALBUM), Some("song.view_album"));
-        for artist in song.artists.iter() {
-            menu.append(
-                Some(&labels::more_from_label(&artist.name)),
-                Some(&format!("song.view_artist_{}", artist.id)),
-            );
+        // an episode's show is no album or artist we could open
+        if !song.is_episode() {
+            menu.append(Some(&*labels::VIEW_ALBUM), Some("song.view_album"));
+            for artist in song.artists.iter() {
+                menu.append(
+                    Some(&labels::more_from_label(&artist.name)),
+                    Some(&format!("song.view_artist_{}", artist.id)),
+                );
+            }
         }
 
         menu.append(Some(&*labels::COPY_LINK), Some("song.copy_link"));
@@ -0,0 +1,181 @@
+use futures::io::{AsyncWrite, AsyncWriteExt};
+use futures::stream::{Stream, StreamExt};
+use serde_json::json;
+use std::path::Path;
+
+use crate::api::SpotifyResult;
+use crate::app::models::SongDescription;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PlaylistExportFormat {
+    // extended M3U, with an #EXTINF line per track
+    M3u,
+    // the playlist's name and its tracks' URIs and metadata
+    Json,
+}
+
+impl PlaylistExportFormat {
+    // Anything but a .json file gets an M3U playlist
+    pub fn for_path(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("json") => Self::Json,
+            _ => Self::M3u,
+        }
+    }
+
+    fn header(self, title: &str) -> String {
+        match self {
+            Self::M3u => format!("#EXTM3U\n#PLAYLIST:{}\n", one_line(title)),
+            Self::Json => format!("{{\"name\":{},\"tracks\":[", json!(title)),
+        }
+    }
+
+    fn entry(self, index: usize, song: &SongDescription) -> String {
+        match self {
+            Self::M3u => format!(
+                "#EXTINF:{},{} - {}\n{}\n",
+                song.duration / 1000,
+                one_line(&song.artists_name()),
+                one_line(&song.title),
+                song.uri
+            ),
+            Self::Json => {
+                let track = json!({
+                    "uri": song.uri,
+                    "title": song.title,
+                    "artists": song.artists.iter().map(|a| &a.name).collect::<Vec<_>>(),
+                    "album": song.album.name,
+                    "duration_ms": song.duration,
+                });
+                let separator = if index == 0 { "" } else { "," };
+                format!("{}\n{}", separator, track)
+            }
+        }
+    }
+
+    fn footer(self) -> &'static str {
+        match self {
+            Self::M3u => "",
+            Self::Json => "\n]}\n",
+        }
+    }
+}
+
+// A line break in a title would end the #EXTINF line early
+fn one_line(s: &str) -> String {
+    s.replace(&['\r', '\n'][..], " ")
+}
+
+// Songs are written as they arrive, so that only a page of a large playlist is ever held in
+// memory. Returns how many were written.
+pub async fn write_playlist<S, W>(
+    format: PlaylistExportFormat,
+    title: &str,
+    mut songs: S,
+    out: &mut W,
+) -> SpotifyResult<usize>
+where
+    S: Stream<Item = SpotifyResult<SongDescription>> + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    out.write_all(format.header(title).as_bytes()).await?;
+    let mut count = 0;
+    while let Some(song) = songs.next().await {
+        out.write_all(format.entry(count, &song?).as_bytes())
+            .await?;
+        count += 1;
+    }
+    out.write_all(format.footer().as_bytes()).await?;
+    out.flush().await?;
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::app::models::{AlbumRef, ArtistRef};
+    use futures::stream;
+
+    fn song(id: &str, title: &str) -> SongDescription {
+        SongDescription {
+            id: id.to_string(),
+            uri: format!("spotify:track:{}", id),
+            title: title.to_string(),
+            artists: vec![ArtistRef {
+                id: "a".to_string(),
+                name: "Artist".to_string(),
+            }],
+            album: AlbumRef {
+                id: "b".to_string(),
+                name: "Album".to_string(),
+            },
+            duration: 61500,
+            art: None,
+            track_number: None,
+            explicit: false,
+            preview_url: None,
+            is_playable: true,
+            external_url: None,
+        }
+    }
+
+    fn export(format: PlaylistExportFormat, songs: Vec<SongDescription>) -> (usize, String) {
+        let mut out = vec![];
+        let songs = stream::iter(songs.into_iter().map(Ok));
+        let count =
+            futures::executor::block_on(write_playlist(format, "Mix", songs, &mut out)).unwrap();
+        (count, String::from_utf8(out).unwrap())
+    }
+
+    #[test]
+    fn test_format_for_path() {
+        assert_eq!(
+            PlaylistExportFormat::for_path(Path::new("/tmp/mix.JSON")),
+            PlaylistExportFormat::Json
+        );
+        assert_eq!(
+            PlaylistExportFormat::for_path(Path::new("/tmp/mix.m3u8")),
+            PlaylistExportFormat::M3u
+        );
+        assert_eq!(
+            PlaylistExportFormat::for_path(Path::new("/tmp/mix")),
+            PlaylistExportFormat::M3u
+        );
+    }
+
+    #[test]
+    fn test_write_m3u() {
+        let (count, out) = export(
+            PlaylistExportFormat::M3u,
+            vec![song("1", "First"), song("2", "Two\nlines")],
+        );
+        assert_eq!(count, 2);
+        assert_eq!(
+            out,
+            "#EXTM3U\n#PLAYLIST:Mix\n\
+             #EXTINF:61,Artist - First\nspotify:track:1\n\
+             #EXTINF:61,Artist - Two lines\nspotify:track:2\n"
+        );
+    }
+
+    #[test]
+    fn test_write_json() {
+        let (count, out) = export(
+            PlaylistExportFormat::Json,
+            vec![song("1", "First"), song("2", "Second")],
+        );
+        assert_eq!(count, 2);
+
+        let value: serde_json::Value = serde_json::from_str(&out).unwrap();
+        assert_eq!(value["name"], "Mix");
+        assert_eq!(value["tracks"][1]["uri"], "spotify:track:2");
+        assert_eq!(value["tracks"][0]["artists"][0], "Artist");
+        assert_eq!(value["tracks"][0]["duration_ms"], 61500);
+
+        let (count, out) = export(PlaylistExportFormat::Json, vec![]);
+        assert_eq!(count, 0);
+        let value: serde_json::Value = serde_json::from_str(&out).unwrap();
+        assert_eq!(value["tracks"], json!([]));
+    }
+}
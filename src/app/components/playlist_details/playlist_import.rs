@@ -0,0 +1,66 @@
+use crate::app::models::{SpotifyId, SpotifyIdKind};
+
+// What came out of a pasted list: the track ids to look up, and the lines that weren't tracks
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ParsedImport {
+    pub ids: Vec<String>,
+    pub failed: Vec<String>,
+}
+
+// Spotify ids are 22 base62 characters, anything else would fail the whole lookup request
+fn is_valid_id(id: &str) -> bool {
+    id.len() == 22 && id.chars().all(|c| c.is_ascii_alphanumeric())
+}
+
+// One spotify:track: URI or open.spotify.com link per line, blank lines are ignored
+pub fn parse_track_list(text: &str) -> ParsedImport {
+    let mut parsed = ParsedImport::default();
+    for line in text.lines().map(str::trim).filter(|line| !line.is_empty()) {
+        match line.parse::<SpotifyId>() {
+            Ok(SpotifyId {
+                kind: SpotifyIdKind::Track,
+                id,
+            }) if is_valid_id(&id) => parsed.ids.push(id),
+            _ => parsed.failed.push(line.to_string()),
+        }
+    }
+    parsed
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_parse_track_list() {
+        let parsed = parse_track_list(
+            "spotify:track:6rqhFgbbKwnb9MLmUQDhG6\n\
+             \n\
+             https://open.spotify.com/track/4uLU6hMCjMI75M1A2tKUQC?si=abc\n   \
+             spotify:album:6rqhFgbbKwnb9MLmUQDhG6\n\
+             not a link\n\
+             spotify:track:short",
+        );
+        assert_eq!(
+            parsed.ids,
+            vec![
+                "6rqhFgbbKwnb9MLmUQDhG6".to_string(),
+                "4uLU6hMCjMI75M1A2tKUQC".to_string()
+            ]
+        );
+        assert_eq!(
+            parsed.failed,
+            vec![
+                "spotify:album:6rqhFgbbKwnb9MLmUQDhG6".to_string(),
+                "not a link".to_string(),
+                "spotify:track:short".to_string()
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_empty_track_list() {
+        assert_eq!(parse_track_list("\n  \n"), ParsedImport::default());
+    }
+}
@@ -0,0 +1,78 @@
+use gtk::prelude::*;
+
+use crate::app::components::EventListener;
+use crate::app::state::{PlaybackAction, PlaybackEvent};
+use crate::app::{ActionDispatcher, AppEvent};
+
+// Plays the 30 second mp3 previews through GTK, for tracks librespot can't stream
+pub struct PreviewPlayer {
+    dispatcher: Box<dyn ActionDispatcher>,
+    stream: Option<gtk::MediaFile>,
+    volume: f64,
+}
+
+impl PreviewPlayer {
+    pub fn new(dispatcher: Box<dyn ActionDispatcher>) -> Self {
+        Self {
+            dispatcher,
+            stream: None,
+            volume: 1.0,
+        }
+    }
+
+    fn start(&mut self, url: &str) {
+        self.stop();
+        let stream = gtk::MediaFile::for_file(&gio::File::for_uri(url));
+        stream.set_volume(self.volume);
+
+        let dispatcher = self.dispatcher.box_clone();
+        stream.connect_ended_notify(move |stream| {
+            if stream.is_ended() {
+                dispatcher.dispatch(PlaybackAction::Next.into());
+            }
+        });
+
+        stream.play();
+        self.stream = Some(stream);
+    }
+
+    fn stop(&mut self) {
+        if let Some(stream) = self.stream.take() {
+            stream.pause();
+            stream.clear();
+        }
+    }
+}
+
+impl EventListener for PreviewPlayer {
+    fn on_event(&mut self, event: &AppEvent) {
+        match event {
+            AppEvent::PlaybackEvent(PlaybackEvent::PreviewStarted(url)) => self.start(url),
+            AppEvent::PlaybackEvent(PlaybackEvent::TrackChanged(_))
+            | AppEvent::PlaybackEvent(PlaybackEvent::PlaybackStopped) => self.stop(),
+            AppEvent::PlaybackEvent(PlaybackEvent::PlaybackPaused) => {
+                if let Some(stream) = self.stream.as_ref() {
+                    stream.pause();
+                }
+            }
+            AppEvent::PlaybackEvent(PlaybackEvent::PlaybackResumed) => {
+                if let Some(stream) = self.stream.as_ref() {
+                    stream.play();
+                }
+            }
+            AppEvent::PlaybackEvent(PlaybackEvent::TrackSeeked(position)) => {
+                if let Some(stream) = self.stream.as_ref() {
+                    // position is in ms, media streams want µs
+                    stream.seek(*position as i64 * 1000);
+                }
+            }
+            AppEvent::PlaybackEvent(PlaybackEvent::VolumeSet(volume)) => {
+                self.volume = *volume;
+                if let Some(stream) = self.stream.as_ref() {
+                    stream.set_volume(*volume);
+                }
+            }
+            _ => {}
+        }
+    }
+}
@@ -95,7 +95,9 @@ impl PlaylistModel for SavedTracksModel {
         for view_artist in song.make_artist_actions(self.dispatcher.box_clone(), None) {
             group.add_action(&view_artist);
         }
-        group.add_action(&song.make_album_action(self.dispatcher.box_clone(), None));
+        if let Some(view_album) = song.make_album_action(self.dispatcher.box_clone(), None) {
+            group.add_action(&view_album);
+        }
         group.add_action(&song.make_link_action(None));
 
         Some(group.upcast())
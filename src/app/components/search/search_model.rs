@@ -1,6 +1,7 @@
 use std::ops::Deref;
 use std::rc::Rc;
 
+use crate::api::SearchType;
 use crate::app::dispatch::ActionDispatcher;
 use crate::app::models::*;
 use crate::app::state::{AppAction, AppModel, BrowserAction};
@@ -39,7 +40,7 @@ impl SearchResultsModel {
             let query = query.to_owned();
             self.dispatcher
                 .call_spotify_and_dispatch(move || async move {
-                    api.search(&query, 0, 5)
+                    api.search(&query, &[SearchType::Album, SearchType::Artist], 0, 5)
                         .await
                         .map(|results| BrowserAction::SetSearchResults(Box::new(results)).into())
                 });
@@ -6,6 +6,7 @@ use gtk::prelude::*;
 use gtk::subclass::prelude::*;
 use gtk::CompositeTemplate;
 use libadwaita::prelude::*;
+use std::rc::Rc;
 
 use super::SettingsModel;
 
@@ -31,11 +32,23 @@ mod imp {
         #[template_child]
         pub audio_backend: TemplateChild<libadwaita::ComboRow>,
 
+        #[template_child]
+        pub volume_normalisation: TemplateChild<libadwaita::ComboRow>,
+
+        #[template_child]
+        pub gapless_playback: TemplateChild<gtk::Switch>,
+
+        #[template_child]
+        pub fade_duration: TemplateChild<gtk::SpinButton>,
+
         #[template_child]
         pub ap_port: TemplateChild<gtk::Entry>,
 
         #[template_child]
         pub theme: TemplateChild<libadwaita::ComboRow>,
+
+        #[template_child]
+        pub clear_cache: TemplateChild<gtk::Button>,
     }
 
     #[glib::object_subclass]
@@ -162,6 +175,54 @@ impl SettingsWindow {
             })
             .build();
 
+        let volume_normalisation = widget
+            .volume_normalisation
+            .downcast_ref::<libadwaita::ComboRow>()
+            .unwrap();
+        settings
+            .bind("volume-normalisation", volume_normalisation, "selected")
+            .mapping(|variant, _| {
+                variant.str().map(|s| {
+                    match s {
+                        "off" => 0,
+                        "track" => 1,
+                        "album" => 2,
+                        _ => unreachable!(),
+                    }
+                    .to_value()
+                })
+            })
+            .set_mapping(|value, _| {
+                value.get::<u32>().ok().map(|u| {
+                    match u {
+                        0 => "off",
+                        1 => "track",
+                        2 => "album",
+                        _ => unreachable!(),
+                    }
+                    .to_variant()
+                })
+            })
+            .build();
+
+        let gapless_playback = widget
+            .gapless_playback
+            .downcast_ref::<gtk::Switch>()
+            .unwrap();
+        settings
+            .bind("gapless-playback", gapless_playback, "active")
+            .build();
+
+        let fade_duration = widget
+            .fade_duration
+            .downcast_ref::<gtk::SpinButton>()
+            .unwrap();
+        settings
+            .bind("fade-duration", fade_duration, "value")
+            .mapping(|variant, _| variant.get::<u32>().map(|s| f64::from(s).to_value()))
+            .set_mapping(|value, _| value.get::<f64>().ok().map(|v| (v as u32).to_variant()))
+            .build();
+
         let ap_port = widget.ap_port.downcast_ref::<gtk::Entry>().unwrap();
         settings
             .bind("ap-port", ap_port, "text")
@@ -196,6 +257,14 @@ impl SettingsWindow {
         });
     }
 
+    fn connect_clear_cache<F>(&self, on_clear: F)
+    where
+        F: Fn() + 'static,
+    {
+        let widget = imp::SettingsWindow::from_instance(self);
+        widget.clear_cache.connect_clicked(move |_| on_clear());
+    }
+
     fn connect_close<F>(&self, on_close: F)
     where
         F: Fn() + 'static,
@@ -219,6 +288,11 @@ pub struct Settings {
 impl Settings {
     pub fn new(parent: gtk::Window, model: SettingsModel) -> Self {
         let settings_window = SettingsWindow::new();
+        let model = Rc::new(model);
+
+        settings_window.connect_clear_cache(clone!(@weak model => move || {
+            model.clear_cache();
+        }));
 
         settings_window.connect_close(move || {
             let new_settings = SpotSettings::new_from_gsettings().unwrap_or_default();
@@ -1,5 +1,7 @@
+use crate::api::clear_cache;
+use crate::app::components::labels;
 use crate::app::state::{PlaybackAction, SettingsAction};
-use crate::app::{ActionDispatcher, AppModel};
+use crate::app::{ActionDispatcher, AppAction, AppModel};
 use crate::settings::SpotSettings;
 use std::rc::Rc;
 
@@ -25,6 +27,17 @@ impl SettingsModel {
             .dispatch(SettingsAction::ChangeSettings.into());
     }
 
+    pub fn clear_cache(&self) {
+        self.app_model.get_spotify().clear_memory_cache();
+        self.dispatcher.dispatch_async(Box::pin(async {
+            let label = match clear_cache().await {
+                (freed, true) => labels::cache_cleared_label(freed),
+                (freed, false) => labels::cache_partly_cleared_label(freed),
+            };
+            Some(AppAction::ShowNotification(label))
+        }));
+    }
+
     pub fn settings(&self) -> SpotSettings {
         let state = self.app_model.get_state();
         state.settings.settings.clone()
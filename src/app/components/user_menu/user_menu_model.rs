@@ -33,13 +33,23 @@ impl UserMenuModel {
         }));
     }
 
+    pub fn fetch_account(&self) {
+        let api = self.app_model.get_spotify();
+        self.dispatcher
+            .call_spotify_and_dispatch(move || async move {
+                api.get_current_user().await?;
+                let is_premium = api.is_premium().unwrap_or(false);
+                Ok(LoginAction::SetIsPremium(is_premium).into())
+            });
+    }
+
     pub fn fetch_user_playlists(&self) {
         let api = self.app_model.get_spotify();
         if let Some(current_user) = self.username() {
             let current_user = current_user.clone();
             self.dispatcher
                 .call_spotify_and_dispatch(move || async move {
-                    api.get_saved_playlists(0, 30).await.map(|playlists| {
+                    api.get_all_saved_playlists().await.map(|playlists| {
                         let summaries = playlists
                             .into_iter()
                             .filter(|p| p.owner.id == current_user)
@@ -1,6 +1,8 @@
+use futures::future::{AbortHandle, Abortable, Future, FutureExt};
 use gtk::prelude::*;
-use std::cell::Cell;
+use std::cell::{Cell, RefCell};
 use std::rc::Rc;
+use std::sync::Arc;
 use std::time::Duration;
 
 #[derive(Clone)]
@@ -76,6 +78,43 @@ impl Debouncer {
     }
 }
 
+// Requests made on behalf of a view, aborted once it goes away (e.g. on navigating back).
+// Dropping the future of a request is what makes isahc abort the transfer.
+// Each handle comes with a token the request holds on to until it's done, so finished ones can be let go.
+#[derive(Default)]
+pub struct CancellableRequests(RefCell<Vec<(AbortHandle, Arc<()>)>>);
+
+impl CancellableRequests {
+    // Resolves to the default value (no actions) if cancelled
+    pub fn wrap<T, F>(&self, future: F) -> impl Future<Output = T>
+    where
+        T: Default,
+        F: Future<Output = T>,
+    {
+        let (handle, registration) = AbortHandle::new_pair();
+        let running = Arc::new(());
+        let mut handles = self.0.borrow_mut();
+        handles.retain(|(_, running)| Arc::strong_count(running) > 1);
+        handles.push((handle, Arc::clone(&running)));
+        Abortable::new(future, registration).map(move |result| {
+            drop(running);
+            result.unwrap_or_default()
+        })
+    }
+
+    pub fn cancel_all(&self) {
+        for (handle, _) in self.0.borrow_mut().drain(..) {
+            handle.abort();
+        }
+    }
+}
+
+impl Drop for CancellableRequests {
+    fn drop(&mut self) {
+        self.cancel_all();
+    }
+}
+
 pub struct Animator<EasingFn> {
     progress: Rc<Cell<u16>>,
     ease_fn: EasingFn,
@@ -163,3 +202,21 @@ pub fn format_duration(duration: f64) -> String {
         format!("{}∶{:02}", minutes, seconds)
     }
 }
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_finished_requests_are_dropped() {
+        let requests = CancellableRequests::default();
+        let first = requests.wrap(async { 1 });
+        assert_eq!(futures::executor::block_on(first), 1);
+        let _second = requests.wrap(async { 2 });
+        assert_eq!(requests.0.borrow().len(), 1);
+
+        requests.cancel_all();
+        assert!(requests.0.borrow().is_empty());
+    }
+}
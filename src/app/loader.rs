@@ -1,12 +1,17 @@
+use crate::api::accept_invalid_certs;
 use crate::api::cache::*;
 use gdk_pixbuf::traits::PixbufLoaderExt;
 use gdk_pixbuf::{Pixbuf, PixbufLoader};
+use gio::prelude::*;
 use isahc::config::Configurable;
 use isahc::{AsyncBody, AsyncReadResponseExt, HttpClient, Response};
 use std::collections::hash_map::DefaultHasher;
 use std::hash::Hasher;
 use std::io::{Error, ErrorKind, Write};
 
+// Cached images past this size (in bytes) get evicted, least recently used first
+const MAX_CACHE_SIZE: u64 = 200 * 1024 * 1024;
+
 struct LocalPixbufLoader<'a>(&'a PixbufLoader);
 
 impl<'a> Write for LocalPixbufLoader<'a> {
@@ -31,9 +36,9 @@ pub struct ImageLoader {
 
 impl ImageLoader {
     pub fn new() -> Self {
-        Self {
-            cache: CacheManager::for_dir("spot/img").unwrap(),
-        }
+        let cache = CacheManager::for_dir("spot/img").unwrap();
+        cache.set_max_size(MAX_CACHE_SIZE);
+        Self { cache }
     }
 
     fn resource_for(url: &str, ext: &str) -> String {
@@ -43,9 +48,15 @@ impl ImageLoader {
         hashed + "." + ext
     }
 
+    // A file:// uri for an image that has been cached already
+    pub fn cached_uri(&self, url: &str, ext: &str) -> Option<String> {
+        let path = self.cache.file_path(&Self::resource_for(url, ext))?;
+        Some(gio::File::for_path(&path).uri().to_string())
+    }
+
     async fn get_image(url: &str) -> Option<Response<AsyncBody>> {
         let mut builder = HttpClient::builder();
-        if cfg!(debug_assertions) {
+        if accept_invalid_certs() {
             builder = builder.ssl_options(isahc::config::SslOption::DANGER_ACCEPT_INVALID_CERTS);
         }
         let client = builder.build().unwrap();
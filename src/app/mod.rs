@@ -1,10 +1,13 @@
-use crate::api::CachedSpotifyClient;
+use crate::api::{CachedSpotifyClient, SpotifyApiClient};
 use crate::app::components::sidebar_listbox::{build_sidebar_listbox, SideBarItem};
+use crate::app::state::LoginAction;
 use crate::glib::StaticType;
 use crate::settings::SpotSettings;
 use futures::channel::mpsc::UnboundedSender;
+use futures::future::BoxFuture;
 use std::rc::Rc;
 use std::sync::Arc;
+use std::time::Duration;
 
 pub mod dispatch;
 pub use dispatch::{ActionDispatcher, ActionDispatcherImpl, DispatchLoop, Worker};
@@ -29,6 +32,9 @@ pub mod loader;
 pub mod rng;
 pub use rng::LazyRandomIndex;
 
+// The player can take a while to get a token, but requests shouldn't hang forever if it can't
+const TOKEN_REFRESH_TIMEOUT: Duration = Duration::from_secs(10);
+
 pub struct App {
     settings: SpotSettings,
     builder: gtk::Builder,
@@ -47,7 +53,12 @@ impl App {
     ) -> Self {
         let state = AppState::new();
         let spotify_client = Arc::new(CachedSpotifyClient::new());
+        spotify_client.set_content_language(settings.content_language.clone());
+        App::refresh_token_on_expiry(&spotify_client, sender.clone());
         let model = Rc::new(AppModel::new(state, spotify_client));
+        model
+            .get_batch_loader()
+            .set_prefetch(settings.prefetch_pages);
 
         let components: Vec<Box<dyn EventListener>> = vec![
             App::make_player_notifier(&settings, sender.clone()),
@@ -64,6 +75,31 @@ impl App {
         }
     }
 
+    // Requests rejected for their token get it refreshed by the player, then are sent again
+    fn refresh_token_on_expiry(
+        spotify_client: &Arc<CachedSpotifyClient>,
+        sender: UnboundedSender<AppAction>,
+    ) {
+        let api = Arc::downgrade(spotify_client);
+        spotify_client.set_on_token_expired(Some(Arc::new(
+            move || -> BoxFuture<'static, Option<String>> {
+                let next_token = api.upgrade().map(|api| api.next_token());
+                let sent = sender
+                    .unbounded_send(LoginAction::RefreshToken.into())
+                    .is_ok();
+                Box::pin(async move {
+                    if !sent {
+                        return None;
+                    }
+                    async_std::future::timeout(TOKEN_REFRESH_TIMEOUT, next_token?)
+                        .await
+                        .ok()
+                        .flatten()
+                })
+            },
+        )));
+    }
+
     fn add_ui_components(&mut self) {
         let builder = &self.builder;
         let model = &self.model;
@@ -90,6 +126,7 @@ impl App {
             App::make_search_button(builder, dispatcher.box_clone()),
             App::make_user_menu(builder, Rc::clone(model), dispatcher),
             App::make_notification(builder),
+            App::make_preview_player(dispatcher.box_clone()),
         ];
 
         self.components.append(&mut components);
@@ -209,6 +246,10 @@ impl App {
         Box::new(Notification::new(toast_overlay))
     }
 
+    fn make_preview_player(dispatcher: Box<dyn ActionDispatcher>) -> Box<PreviewPlayer> {
+        Box::new(PreviewPlayer::new(dispatcher))
+    }
+
     fn handle(&mut self, message: AppAction) {
         let starting = matches!(&message, &AppAction::Start);
 
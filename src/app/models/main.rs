@@ -1,4 +1,6 @@
+use std::fmt;
 use std::str::FromStr;
+use std::time::Instant;
 
 #[derive(Clone, Copy, Debug)]
 pub struct Batch {
@@ -54,6 +56,10 @@ pub struct AlbumRef {
 pub struct SearchResults {
     pub albums: Vec<AlbumDescription>,
     pub artists: Vec<ArtistSummary>,
+    pub playlists: Vec<PlaylistDescription>,
+    pub songs: Vec<SongDescription>,
+    pub shows: Vec<ShowDescription>,
+    pub episodes: Vec<EpisodeDescription>,
 }
 
 #[derive(Clone, Debug)]
@@ -106,6 +112,151 @@ pub struct PlaylistDescription {
     pub owner: UserRef,
 }
 
+#[derive(Clone, Debug)]
+pub struct FeaturedPlaylistsDescription {
+    pub message: Option<String>,
+    pub playlists: Vec<PlaylistDescription>,
+}
+
+#[derive(Clone, Debug)]
+pub struct AudioFeaturesDescription {
+    pub id: String,
+    pub tempo: Option<f32>,
+    // pitch class, 0 = C, 1 = C♯/D♭, ...
+    pub key: Option<i32>,
+    // 1 = major, 0 = minor
+    pub mode: Option<i32>,
+    pub time_signature: Option<i32>,
+    pub loudness: Option<f32>,
+    pub energy: Option<f32>,
+    pub danceability: Option<f32>,
+    pub valence: Option<f32>,
+    pub acousticness: Option<f32>,
+    pub instrumentalness: Option<f32>,
+    pub liveness: Option<f32>,
+    pub speechiness: Option<f32>,
+}
+
+#[derive(Clone, Debug)]
+pub struct ShowDescription {
+    pub id: String,
+    pub title: String,
+    pub description: String,
+    pub art: Option<String>,
+}
+
+#[derive(Clone, Debug)]
+pub struct EpisodeDescription {
+    pub id: String,
+    pub title: String,
+    pub description: String,
+    pub art: Option<String>,
+    pub duration: u32,
+    pub release_date: Option<String>,
+}
+
+#[derive(Clone, Debug)]
+pub struct ConnectDevice {
+    pub id: String,
+    pub label: String,
+    pub kind: String,
+    pub is_active: bool,
+    pub volume: Option<u32>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RepeatMode {
+    Off,
+    Track,
+    Context,
+}
+
+// Spotify calls these track, context and off
+impl FromStr for RepeatMode {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "track" => Ok(Self::Track),
+            "context" => Ok(Self::Context),
+            "off" => Ok(Self::Off),
+            _ => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for RepeatMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = match self {
+            Self::Track => "track",
+            Self::Context => "context",
+            Self::Off => "off",
+        };
+        f.write_str(state)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ShuffleState {
+    On,
+    Off,
+}
+
+impl ShuffleState {
+    pub fn is_on(self) -> bool {
+        self == Self::On
+    }
+}
+
+impl From<bool> for ShuffleState {
+    fn from(shuffled: bool) -> Self {
+        if shuffled {
+            Self::On
+        } else {
+            Self::Off
+        }
+    }
+}
+
+// Spotify takes these as the shuffle endpoint's state
+impl fmt::Display for ShuffleState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(if self.is_on() { "true" } else { "false" })
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ConnectPlaybackState {
+    pub device: Option<ConnectDevice>,
+    pub is_playing: bool,
+    pub progress_ms: Option<u32>,
+    pub shuffle: ShuffleState,
+    pub repeat: RepeatMode,
+    pub song: Option<SongDescription>,
+}
+
+#[derive(Clone, Debug)]
+pub struct CurrentlyPlayingDescription {
+    pub is_playing: bool,
+    pub progress_ms: Option<u32>,
+    pub song: Option<SongDescription>,
+    // when to poll again, if the server said so
+    pub stale_at: Option<Instant>,
+}
+
+#[derive(Clone, Debug)]
+pub struct PlayerQueueDescription {
+    pub currently_playing: Option<SongDescription>,
+    pub queue: Vec<SongDescription>,
+}
+
+#[derive(Clone, Debug)]
+pub struct CategoryDescription {
+    pub id: String,
+    pub name: String,
+    pub icon: Option<String>,
+}
+
 #[derive(Clone, Debug)]
 pub struct PlaylistSummary {
     pub id: String,
@@ -122,6 +273,12 @@ pub struct SongDescription {
     pub album: AlbumRef,
     pub duration: u32,
     pub art: Option<String>,
+    pub explicit: bool,
+    pub preview_url: Option<String>,
+    // false for tracks that aren't available in the user's market
+    pub is_playable: bool,
+    // the link Spotify gives for this song, if any
+    pub external_url: Option<String>,
 }
 
 impl SongDescription {
@@ -132,6 +289,11 @@ impl SongDescription {
             .collect::<Vec<String>>()
             .join(", ")
     }
+
+    // Playlists can hold podcast episodes, which are listed like songs
+    pub fn is_episode(&self) -> bool {
+        self.uri.starts_with("spotify:episode:")
+    }
 }
 
 #[derive(Copy, Clone, Default)]
@@ -194,6 +356,7 @@ pub struct ArtistDescription {
     pub name: String,
     pub albums: Vec<AlbumDescription>,
     pub top_tracks: Vec<SongDescription>,
+    pub is_followed: bool,
 }
 
 #[derive(Clone, Debug)]
@@ -210,11 +373,34 @@ pub struct UserDescription {
     pub playlists: Vec<PlaylistDescription>,
 }
 
+#[derive(Clone, Debug)]
+pub struct CurrentUserDescription {
+    pub id: String,
+    pub display_name: String,
+    pub avatar: Option<String>,
+    pub product: Option<String>,
+}
+
 #[cfg(test)]
 mod tests {
 
     use super::*;
 
+    #[test]
+    fn test_repeat_mode_strings() {
+        for mode in [RepeatMode::Track, RepeatMode::Context, RepeatMode::Off] {
+            assert_eq!(mode.to_string().parse::<RepeatMode>(), Ok(mode));
+        }
+        assert_eq!("context".parse::<RepeatMode>(), Ok(RepeatMode::Context));
+        assert!("Playlist".parse::<RepeatMode>().is_err());
+    }
+
+    #[test]
+    fn test_shuffle_state_strings() {
+        assert_eq!(ShuffleState::from(true).to_string(), "true");
+        assert_eq!(ShuffleState::from(false).to_string(), "false");
+    }
+
     fn song(id: &str) -> SongDescription {
         SongDescription {
             id: id.to_string(),
@@ -228,6 +414,10 @@ mod tests {
             duration: 1000,
             art: None,
             track_number: None,
+            explicit: false,
+            preview_url: None,
+            is_playable: true,
+            external_url: None,
         }
     }
 
@@ -10,6 +10,9 @@ pub use album_model::*;
 mod artist_model;
 pub use artist_model::*;
 
+mod spotify_id;
+pub use spotify_id::*;
+
 impl From<&AlbumDescription> for AlbumModel {
     fn from(album: &AlbumDescription) -> Self {
         AlbumModel::new(
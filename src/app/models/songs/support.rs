@@ -432,6 +432,10 @@ mod tests {
             duration: 1000,
             art: None,
             track_number: None,
+            explicit: false,
+            preview_url: None,
+            is_playable: true,
+            external_url: None,
         }
     }
 
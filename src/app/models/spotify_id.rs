@@ -0,0 +1,153 @@
+use std::str::FromStr;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SpotifyIdKind {
+    Track,
+    Album,
+    Artist,
+    Playlist,
+    User,
+    Episode,
+    Show,
+}
+
+impl FromStr for SpotifyIdKind {
+    type Err = ();
+
+    fn from_str(kind: &str) -> Result<Self, Self::Err> {
+        match kind {
+            "track" => Ok(Self::Track),
+            "album" => Ok(Self::Album),
+            "artist" => Ok(Self::Artist),
+            "playlist" => Ok(Self::Playlist),
+            "user" => Ok(Self::User),
+            "episode" => Ok(Self::Episode),
+            "show" => Ok(Self::Show),
+            _ => Err(()),
+        }
+    }
+}
+
+// A Spotify object reference, parsed from either a spotify: URI or an open.spotify.com link
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SpotifyId {
+    pub kind: SpotifyIdKind,
+    pub id: String,
+}
+
+impl SpotifyId {
+    // Takes the path segments after the scheme or host, e.g. ["track", "id"].
+    // Older links nest playlists under their owner ("user", "name", "playlist", "id"),
+    // so the last kind/id pair wins.
+    fn from_segments<'a>(segments: impl Iterator<Item = &'a str>) -> Option<Self> {
+        let segments: Vec<&str> = segments.filter(|s| !s.is_empty()).collect();
+        segments.windows(2).rev().find_map(|pair| {
+            let kind = pair[0].parse::<SpotifyIdKind>().ok()?;
+            Some(Self {
+                kind,
+                id: pair[1].to_string(),
+            })
+        })
+    }
+
+    fn from_uri(uri: &str) -> Option<Self> {
+        // Might start with /// because of https://gitlab.gnome.org/GNOME/glib/-/issues/1886/
+        let rest = uri.strip_prefix("spotify:")?.trim_start_matches('/');
+        Self::from_segments(rest.split(':'))
+    }
+
+    fn from_web_link(link: &str) -> Option<Self> {
+        let rest = link
+            .strip_prefix("https://")
+            .or_else(|| link.strip_prefix("http://"))?;
+        let path = rest.strip_prefix("open.spotify.com/")?;
+        // Shared links carry a ?si= tracking suffix, which isn't part of the id
+        let path = path.split(&['?', '#'][..]).next()?;
+        Self::from_segments(path.split('/'))
+    }
+}
+
+impl FromStr for SpotifyId {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        Self::from_uri(s)
+            .or_else(|| Self::from_web_link(s))
+            .ok_or(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn parse(s: &str) -> Option<SpotifyId> {
+        s.parse().ok()
+    }
+
+    #[test]
+    fn test_parse_uri() {
+        assert_eq!(
+            parse("spotify:track:6rqhFgbbKwnb9MLmUQDhG6"),
+            Some(SpotifyId {
+                kind: SpotifyIdKind::Track,
+                id: "6rqhFgbbKwnb9MLmUQDhG6".to_string()
+            })
+        );
+        assert_eq!(
+            parse("spotify:///album:abc"),
+            Some(SpotifyId {
+                kind: SpotifyIdKind::Album,
+                id: "abc".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_legacy_playlist_uri() {
+        assert_eq!(
+            parse("spotify:user:someone:playlist:abc"),
+            Some(SpotifyId {
+                kind: SpotifyIdKind::Playlist,
+                id: "abc".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_web_link() {
+        assert_eq!(
+            parse("https://open.spotify.com/track/abc?si=0123456789abcdef"),
+            Some(SpotifyId {
+                kind: SpotifyIdKind::Track,
+                id: "abc".to_string()
+            })
+        );
+        assert_eq!(
+            parse("https://open.spotify.com/intl-fr/artist/abc"),
+            Some(SpotifyId {
+                kind: SpotifyIdKind::Artist,
+                id: "abc".to_string()
+            })
+        );
+        assert_eq!(
+            parse("  https://open.spotify.com/user/someone  "),
+            Some(SpotifyId {
+                kind: SpotifyIdKind::User,
+                id: "someone".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        assert_eq!(parse(""), None);
+        assert_eq!(parse("spotify:"), None);
+        assert_eq!(parse("spotify:track:"), None);
+        assert_eq!(parse("spotify:unknown:abc"), None);
+        assert_eq!(parse("https://example.com/track/abc"), None);
+        assert_eq!(parse("https://open.spotify.com/track"), None);
+    }
+}
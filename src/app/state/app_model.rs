@@ -3,6 +3,7 @@ use crate::app::{state::*, BatchLoader};
 use ref_filter_map::*;
 use std::cell::{Ref, RefCell};
 use std::sync::Arc;
+use std::time::SystemTime;
 
 pub struct AppServices {
     pub spotify_api: Arc<dyn SpotifyApiClient + Send + Sync>,
@@ -47,20 +48,33 @@ impl AppModel {
         ref_filter_map(self.state.borrow(), map)
     }
 
+    fn update_token(&self, token: &str, expiry_time: Option<SystemTime>) {
+        let api = &self.services.spotify_api;
+        match expiry_time.and_then(|time| time.duration_since(SystemTime::now()).ok()) {
+            Some(expires_in) => {
+                api.update_token_with_expiry(token.to_owned(), expires_in.as_secs())
+            }
+            None => api.update_token(token.to_owned()),
+        }
+    }
+
     pub fn update_state(&self, message: AppAction) -> Vec<AppEvent> {
         match &message {
             AppAction::LoginAction(LoginAction::SetLoginSuccess(
                 SetLoginSuccessAction::Password(creds),
             )) => {
-                self.services.spotify_api.update_token(creds.token.clone());
+                self.update_token(&creds.token, creds.token_expiry_time);
             }
             AppAction::LoginAction(LoginAction::SetLoginSuccess(
                 SetLoginSuccessAction::Token { token, .. },
             )) => {
                 self.services.spotify_api.update_token(token.clone());
             }
-            AppAction::LoginAction(LoginAction::SetRefreshedToken { token, .. }) => {
-                self.services.spotify_api.update_token(token.clone());
+            AppAction::LoginAction(LoginAction::SetRefreshedToken {
+                token,
+                token_expiry_time,
+            }) => {
+                self.update_token(token, Some(*token_expiry_time));
             }
             _ => {}
         }
@@ -1,5 +1,7 @@
+use gettextrs::*;
 use std::borrow::Cow;
 
+use crate::app::models::{SpotifyId, SpotifyIdKind};
 use crate::app::state::{
     browser_state::{BrowserAction, BrowserEvent, BrowserState},
     login_state::{LoginAction, LoginEvent, LoginState},
@@ -19,7 +21,10 @@ pub enum AppAction {
     Start,
     Raise,
     ShowNotification(String),
+    // the player could not load the current track
+    ReportTrackUnavailable,
     ViewNowPlaying,
+    OpenTrack(String),
     // cross-state actions
     QueueSelection,
     DequeueSelection,
@@ -35,23 +40,14 @@ impl AppAction {
     #[allow(non_snake_case)]
     pub fn OpenURI(uri: String) -> Option<Self> {
         debug!("parsing {}", &uri);
-        let mut parts = uri.split(':');
-        if parts.next()? != "spotify" {
-            return None;
-        }
-
-        // Might start with /// because of https://gitlab.gnome.org/GNOME/glib/-/issues/1886/
-        let action = parts
-            .next()?
-            .strip_prefix("///")
-            .filter(|p| !p.is_empty())?;
-        let data = parts.next()?;
+        let SpotifyId { kind, id } = uri.parse().ok()?;
 
-        match action {
-            "album" => Some(Self::ViewAlbum(data.to_string())),
-            "artist" => Some(Self::ViewArtist(data.to_string())),
-            "playlist" => Some(Self::ViewPlaylist(data.to_string())),
-            "user" => Some(Self::ViewUser(data.to_string())),
+        match kind {
+            SpotifyIdKind::Album => Some(Self::ViewAlbum(id)),
+            SpotifyIdKind::Artist => Some(Self::ViewArtist(id)),
+            SpotifyIdKind::Playlist => Some(Self::ViewPlaylist(id)),
+            SpotifyIdKind::User => Some(Self::ViewUser(id)),
+            SpotifyIdKind::Track => Some(Self::OpenTrack(id)),
             _ => None,
         }
     }
@@ -92,6 +88,7 @@ pub enum AppEvent {
     Raised,
     NotificationShown(String),
     NowPlayingShown,
+    TrackOpened(String),
     SettingsEvent(SettingsEvent),
 }
 
@@ -123,7 +120,26 @@ impl AppState {
                 vec![AppEvent::Started]
             }
             AppAction::ShowNotification(c) => vec![AppEvent::NotificationShown(c)],
+            AppAction::ReportTrackUnavailable => {
+                let mut events = forward_action(PlaybackAction::PlayPreview, &mut self.playback);
+                if !events.is_empty() {
+                    // translators: This notification is shown when only a short preview of a track can be played.
+                    events.push(AppEvent::NotificationShown(gettext(
+                        "Only a preview of this track is available",
+                    )));
+                    return events;
+                }
+                let message = if self.logged_user.is_premium == Some(false) {
+                    // translators: This notification is shown when a track can't be played because the account isn't a premium one.
+                    gettext("Full tracks can only be played with Spotify Premium")
+                } else {
+                    // translators: This notification is shown when a track can't be played, e.g. because it isn't available in the user's country.
+                    gettext("This track can't be played")
+                };
+                vec![AppEvent::NotificationShown(message)]
+            }
             AppAction::ViewNowPlaying => vec![AppEvent::NowPlayingShown],
+            AppAction::OpenTrack(id) => vec![AppEvent::TrackOpened(id)],
             AppAction::Raise => vec![AppEvent::Raised],
             AppAction::QueueSelection => {
                 self.playback.queue(self.selection.take_selection());
@@ -201,7 +217,20 @@ impl AppState {
                     vec![]
                 }
             }
-            AppAction::PlaybackAction(a) => forward_action(a, &mut self.playback),
+            AppAction::PlaybackAction(a) => {
+                let mut events = forward_action(a, &mut self.playback);
+                let track_changed = events
+                    .iter()
+                    .any(|e| matches!(e, AppEvent::PlaybackEvent(PlaybackEvent::TrackChanged(_))));
+                // free accounts can't stream full tracks, go straight to the preview
+                if track_changed && self.logged_user.is_premium == Some(false) {
+                    events.append(&mut forward_action(
+                        PlaybackAction::PlayPreview,
+                        &mut self.playback,
+                    ));
+                }
+                events
+            }
             AppAction::BrowserAction(a) => forward_action(a, &mut self.browser),
             AppAction::SelectionAction(a) => forward_action(a, &mut self.selection),
             AppAction::LoginAction(a) => forward_action(a, &mut self.logged_user),
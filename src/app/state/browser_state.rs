@@ -17,12 +17,18 @@ pub enum BrowserAction {
     RemoveTracksFromPlaylist(String, Vec<String>),
     SetAlbumDetails(Box<AlbumFullDescription>),
     AppendAlbumTracks(String, Box<SongBatch>),
+    SetAlbumArtists(String, Vec<ArtistSummary>),
     SetPlaylistDetails(Box<PlaylistDescription>),
     AppendPlaylistTracks(String, Box<SongBatch>),
+    SetPlaylistFollowed(String, bool),
     Search(String),
     SetSearchResults(Box<SearchResults>),
     SetArtistDetails(Box<ArtistDescription>),
     AppendArtistReleases(String, Vec<AlbumDescription>),
+    SetArtistAppearsOn(String, Vec<AlbumDescription>),
+    SetArtistCompilations(String, Vec<AlbumDescription>),
+    FollowArtist(String),
+    UnfollowArtist(String),
     NavigationPush(ScreenName),
     NavigationPop,
     NavigationPopTo(ScreenName),
@@ -49,12 +55,15 @@ pub enum BrowserEvent {
     SavedPlaylistsUpdated,
     AlbumDetailsLoaded(String),
     AlbumTracksAppended(String),
+    AlbumArtistsLoaded(String),
     PlaylistDetailsLoaded(String),
     PlaylistTracksAppended(String),
     PlaylistTracksRemoved(String),
+    PlaylistFollowChanged(String),
     SearchUpdated,
     SearchResultsUpdated,
     ArtistDetailsUpdated(String),
+    ArtistFollowChanged(String),
     NavigationPushed(ScreenName),
     NavigationPopped,
     NavigationPoppedTo(ScreenName),
@@ -23,6 +23,7 @@ pub enum LoginAction {
     TryLogin(TryLoginAction),
     SetLoginSuccess(SetLoginSuccessAction),
     SetUserPlaylists(Vec<PlaylistSummary>),
+    SetIsPremium(bool),
     SetLoginFailure,
     RefreshToken,
     SetRefreshedToken {
@@ -74,6 +75,8 @@ impl From<LoginEvent> for AppEvent {
 pub struct LoginState {
     pub user: Option<String>,
     pub playlists: Vec<PlaylistSummary>,
+    // None until the account has been looked up
+    pub is_premium: Option<bool>,
 }
 
 impl UpdatableState for LoginState {
@@ -117,12 +120,17 @@ impl UpdatableState for LoginState {
             }
             LoginAction::Logout => {
                 self.user = None;
+                self.is_premium = None;
                 vec![LoginEvent::LogoutCompleted.into()]
             }
             LoginAction::SetUserPlaylists(playlists) => {
                 self.playlists = playlists;
                 vec![LoginEvent::UserPlaylistsLoaded.into()]
             }
+            LoginAction::SetIsPremium(is_premium) => {
+                self.is_premium = Some(is_premium);
+                vec![]
+            }
         }
     }
 }
@@ -1,6 +1,8 @@
 use std::borrow::Cow;
 
-use crate::app::models::{SongBatch, SongDescription, SongListModel, SongListModelPending};
+use crate::app::models::{
+    RepeatMode, SongBatch, SongDescription, SongListModel, SongListModelPending,
+};
 use crate::app::state::{AppAction, AppEvent, UpdatableState};
 use crate::app::{BatchQuery, LazyRandomIndex, SongsSource};
 
@@ -13,6 +15,8 @@ pub struct PlaybackState {
     repeat: RepeatMode,
     is_playing: bool,
     is_shuffled: bool,
+    // id of the song whose preview is playing instead of the full track
+    preview: Option<String>,
 }
 
 impl PlaybackState {
@@ -28,6 +32,10 @@ impl PlaybackState {
         self.is_shuffled
     }
 
+    pub fn is_preview(&self) -> bool {
+        self.preview.is_some() && self.preview == self.current_song_id()
+    }
+
     pub fn repeat_mode(&self) -> RepeatMode {
         self.repeat
     }
@@ -65,6 +73,21 @@ impl PlaybackState {
         self.index(self.position?)
     }
 
+    // Whatever plays next, be it from the queue or the rest of the album or playlist
+    pub fn next_song_id(&self) -> Option<String> {
+        Some(self.index(self.next_index()?)?.id)
+    }
+
+    fn start_preview(&mut self) -> Option<String> {
+        if self.is_preview() {
+            return None;
+        }
+        let song = self.current_song()?;
+        let url = song.preview_url?;
+        self.preview = Some(song.id);
+        Some(url)
+    }
+
     fn clear(&mut self, source: Option<SongsSource>) -> SongListModelPending {
         self.source = source;
         self.index = Default::default();
@@ -151,10 +174,12 @@ impl PlaybackState {
     fn stop(&mut self) {
         self.position = None;
         self.is_playing = false;
+        self.preview = None;
     }
 
     fn play_index(&mut self, index: usize) -> Option<String> {
         self.is_playing = true;
+        self.preview = None;
         self.position.replace(index);
         self.index.next_until(index + 1);
         self.current_song_id()
@@ -164,12 +189,19 @@ impl PlaybackState {
         self.next_index().and_then(move |i| self.play_index(i))
     }
 
+    // Songs known to be unplayable are skipped, those not loaded yet are assumed to play
+    fn is_playable_at(&self, i: usize) -> bool {
+        self.index(i).map(|song| song.is_playable).unwrap_or(true)
+    }
+
     pub fn next_index(&self) -> Option<usize> {
         let len = self.songs.len();
         self.position.and_then(|p| match self.repeat {
-            RepeatMode::Song => Some(p),
-            RepeatMode::Playlist if len != 0 => Some((p + 1) % len),
-            RepeatMode::None => Some(p + 1).filter(|&i| i < len),
+            RepeatMode::Track => Some(p),
+            RepeatMode::Context if len != 0 => (1..=len)
+                .map(|i| (p + i) % len)
+                .find(|&i| self.is_playable_at(i)),
+            RepeatMode::Off => (p + 1..len).find(|&i| self.is_playable_at(i)),
             _ => None,
         })
     }
@@ -181,9 +213,11 @@ impl PlaybackState {
     pub fn prev_index(&self) -> Option<usize> {
         let len = self.songs.len();
         self.position.and_then(|p| match self.repeat {
-            RepeatMode::Song => Some(p),
-            RepeatMode::Playlist if len != 0 => Some((if p == 0 { len } else { p }) - 1),
-            RepeatMode::None => Some(p).filter(|&i| i > 0).map(|i| i - 1),
+            RepeatMode::Track => Some(p),
+            RepeatMode::Context if len != 0 => (1..=len)
+                .map(|i| (p + len - i) % len)
+                .find(|&i| self.is_playable_at(i)),
+            RepeatMode::Off => (0..p).rev().find(|&i| self.is_playable_at(i)),
             _ => None,
         })
     }
@@ -211,9 +245,10 @@ impl Default for PlaybackState {
             songs: SongListModel::new(50),
             position: None,
             source: None,
-            repeat: RepeatMode::None,
+            repeat: RepeatMode::Off,
             is_playing: false,
             is_shuffled: false,
+            preview: None,
         }
     }
 }
@@ -227,6 +262,7 @@ pub enum PlaybackAction {
     SetRepeatMode(RepeatMode),
     ToggleRepeat,
     ToggleShuffle,
+    SetShuffled(bool),
     Seek(u32),
     SyncSeek(u32),
     Load(String),
@@ -237,6 +273,9 @@ pub enum PlaybackAction {
     Previous,
     Queue(Vec<SongDescription>),
     Dequeue(String),
+    PlayPreview,
+    // the player is about to finish the current track
+    PreloadNext,
 }
 
 impl From<PlaybackAction> for AppAction {
@@ -257,13 +296,8 @@ pub enum PlaybackEvent {
     ShuffleChanged,
     PlaylistChanged,
     PlaybackStopped,
-}
-
-#[derive(Clone, Copy, Debug)]
-pub enum RepeatMode {
-    Song,
-    Playlist,
-    None,
+    PreviewStarted(String),
+    PreloadRequested(String),
 }
 
 impl From<PlaybackEvent> for AppEvent {
@@ -309,9 +343,9 @@ impl UpdatableState for PlaybackState {
             }
             PlaybackAction::ToggleRepeat => {
                 self.repeat = match self.repeat {
-                    RepeatMode::Song => RepeatMode::None,
-                    RepeatMode::Playlist => RepeatMode::Song,
-                    RepeatMode::None => RepeatMode::Playlist,
+                    RepeatMode::Track => RepeatMode::Off,
+                    RepeatMode::Context => RepeatMode::Track,
+                    RepeatMode::Off => RepeatMode::Context,
                 };
                 vec![PlaybackEvent::RepeatModeChanged(self.repeat)]
             }
@@ -323,6 +357,15 @@ impl UpdatableState for PlaybackState {
                 self.toggle_shuffle();
                 vec![PlaybackEvent::ShuffleChanged]
             }
+            PlaybackAction::PreloadNext => self
+                .next_song_id()
+                .map(|id| vec![PlaybackEvent::PreloadRequested(id)])
+                .unwrap_or_default(),
+            // e.g. from MPRIS, which sets the value rather than toggling it
+            PlaybackAction::SetShuffled(shuffled) if shuffled != self.is_shuffled => {
+                self.toggle_shuffle();
+                vec![PlaybackEvent::ShuffleChanged]
+            }
             PlaybackAction::Next => {
                 if let Some(id) = self.play_next() {
                     make_events(vec![
@@ -388,6 +431,9 @@ impl UpdatableState for PlaybackState {
             PlaybackAction::Seek(pos) => vec![PlaybackEvent::TrackSeeked(pos)],
             PlaybackAction::SyncSeek(pos) => vec![PlaybackEvent::SeekSynced(pos)],
             PlaybackAction::SetVolume(volume) => vec![PlaybackEvent::VolumeSet(volume)],
+            PlaybackAction::PlayPreview => make_events(vec![self
+                .start_preview()
+                .map(PlaybackEvent::PreviewStarted)]),
             _ => vec![],
         }
     }
@@ -412,6 +458,10 @@ mod tests {
             duration: 1000,
             art: None,
             track_number: None,
+            explicit: false,
+            preview_url: None,
+            is_playable: true,
+            external_url: None,
         }
     }
 
@@ -521,6 +571,51 @@ mod tests {
         assert_eq!(state.current_song_id(), Some("1".to_string()));
     }
 
+    #[test]
+    fn test_skip_unplayable() {
+        let mut state = PlaybackState::default();
+        let unplayable = SongDescription {
+            is_playable: false,
+            ..song("2")
+        };
+        state.queue(vec![song("1"), unplayable, song("3")]);
+
+        state.play("1");
+        assert_eq!(state.next_id(), Some("3".to_string()));
+        state.play_next();
+        assert_eq!(state.current_song_id(), Some("3".to_string()));
+        assert_eq!(state.prev_id(), Some("1".to_string()));
+        assert!(state.next_index().is_none());
+
+        state.update_with(Cow::Owned(PlaybackAction::SetRepeatMode(
+            RepeatMode::Context,
+        )));
+        assert_eq!(state.next_id(), Some("1".to_string()));
+        state.play_prev();
+        assert_eq!(state.current_song_id(), Some("1".to_string()));
+        assert_eq!(state.prev_id(), Some("3".to_string()));
+    }
+
+    #[test]
+    fn test_preload_next() {
+        let mut state = PlaybackState::default();
+        state.queue(vec![song("1"), song("2")]);
+        state.play("1");
+
+        let events = state.update_with(Cow::Owned(PlaybackAction::PreloadNext));
+        assert!(matches!(&events[..], [PlaybackEvent::PreloadRequested(id)] if id == "2"));
+
+        // queued songs come after the rest
+        state.play("2");
+        state.queue(vec![song("3")]);
+        let events = state.update_with(Cow::Owned(PlaybackAction::PreloadNext));
+        assert!(matches!(&events[..], [PlaybackEvent::PreloadRequested(id)] if id == "3"));
+
+        state.play("3");
+        let events = state.update_with(Cow::Owned(PlaybackAction::PreloadNext));
+        assert!(events.is_empty());
+    }
+
     #[test]
     fn test_shuffle() {
         let mut state = PlaybackState::default();
@@ -553,6 +648,20 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_set_shuffled() {
+        let mut state = PlaybackState::default();
+        state.queue(vec![song("1"), song("2")]);
+
+        let events = state.update_with(Cow::Owned(PlaybackAction::SetShuffled(true)));
+        assert!(matches!(events[..], [PlaybackEvent::ShuffleChanged]));
+        assert!(state.is_shuffled());
+
+        let events = state.update_with(Cow::Owned(PlaybackAction::SetShuffled(true)));
+        assert!(events.is_empty());
+        assert!(state.is_shuffled());
+    }
+
     #[test]
     fn test_shuffle_queue() {
         let mut state = PlaybackState::default();
@@ -651,4 +760,26 @@ mod tests {
         state.dequeue(&["3".to_string()]);
         assert_eq!(state.current_song_id(), None);
     }
+
+    #[test]
+    fn test_preview() {
+        let mut state = PlaybackState::default();
+        let mut with_preview = song("1");
+        with_preview.preview_url = Some("https://p.scdn.co/mp3-preview/1".to_string());
+        state.queue(vec![with_preview, song("2")]);
+
+        state.play("1");
+        let events = state.update_with(Cow::Owned(PlaybackAction::PlayPreview));
+        assert!(matches!(&events[..], [PlaybackEvent::PreviewStarted(_)]));
+        assert!(state.is_preview());
+
+        // already previewing
+        let events = state.update_with(Cow::Owned(PlaybackAction::PlayPreview));
+        assert!(events.is_empty());
+
+        state.play_next();
+        assert!(!state.is_preview());
+        let events = state.update_with(Cow::Owned(PlaybackAction::PlayPreview));
+        assert!(events.is_empty());
+    }
 }
@@ -41,6 +41,8 @@ pub struct DetailsState {
     pub name: ScreenName,
     pub content: Option<AlbumFullDescription>,
     pub songs: SongListModel,
+    // everyone credited on the album or its tracks, with their photos
+    pub artists: Vec<ArtistSummary>,
 }
 
 impl DetailsState {
@@ -50,6 +52,7 @@ impl DetailsState {
             name: ScreenName::AlbumDetails(id),
             content: None,
             songs: SongListModel::new(100),
+            artists: vec![],
         }
     }
 }
@@ -70,6 +73,10 @@ impl UpdatableState for DetailsState {
                 self.songs.add(*batch.clone()).commit();
                 vec![BrowserEvent::AlbumTracksAppended(id.clone())]
             }
+            BrowserAction::SetAlbumArtists(id, artists) if id == &self.id => {
+                self.artists = artists.clone();
+                vec![BrowserEvent::AlbumArtistsLoaded(id.clone())]
+            }
             BrowserAction::SaveAlbum(album) if album.id == self.id => {
                 let id = album.id.clone();
                 if let Some(mut album) = self.content.as_mut() {
@@ -96,6 +103,7 @@ pub struct PlaylistDetailsState {
     pub id: String,
     pub name: ScreenName,
     pub playlist: Option<PlaylistDescription>,
+    pub is_followed: Option<bool>,
     pub songs: SongListModel,
 }
 
@@ -105,6 +113,7 @@ impl PlaylistDetailsState {
             id: id.clone(),
             name: ScreenName::PlaylistDetails(id),
             playlist: None,
+            is_followed: None,
             songs: SongListModel::new(100),
         }
     }
@@ -130,6 +139,10 @@ impl UpdatableState for PlaylistDetailsState {
                 self.songs.remove(&uris[..]).commit();
                 vec![BrowserEvent::PlaylistTracksRemoved(self.id.clone())]
             }
+            BrowserAction::SetPlaylistFollowed(id, is_followed) if id == &self.id => {
+                self.is_followed = Some(*is_followed);
+                vec![BrowserEvent::PlaylistFollowChanged(id.clone())]
+            }
             _ => vec![],
         }
     }
@@ -139,8 +152,11 @@ pub struct ArtistState {
     pub id: String,
     pub name: ScreenName,
     pub artist: Option<String>,
+    pub is_followed: bool,
     pub next_page: Pagination<String>,
     pub albums: ListStore<AlbumModel>,
+    pub appears_on: ListStore<AlbumModel>,
+    pub compilations: ListStore<AlbumModel>,
     pub top_tracks: SongListModel,
 }
 
@@ -150,8 +166,11 @@ impl ArtistState {
             id: id.clone(),
             name: ScreenName::Artist(id.clone()),
             artist: None,
+            is_followed: false,
             next_page: Pagination::new(id, 20),
             albums: ListStore::new(),
+            appears_on: ListStore::new(),
+            compilations: ListStore::new(),
             top_tracks: SongListModel::new(10),
         }
     }
@@ -169,8 +188,10 @@ impl UpdatableState for ArtistState {
                     name,
                     albums,
                     mut top_tracks,
+                    is_followed,
                 } = *details.clone();
                 self.artist = Some(name);
+                self.is_followed = is_followed;
                 self.albums
                     .replace_all(albums.into_iter().map(|a| a.into()));
                 self.next_page.reset_count(self.albums.len());
@@ -185,6 +206,23 @@ impl UpdatableState for ArtistState {
                 self.albums.extend(albums.iter().map(|a| a.into()));
                 vec![BrowserEvent::ArtistDetailsUpdated(self.id.clone())]
             }
+            BrowserAction::SetArtistAppearsOn(id, albums) if id == &self.id => {
+                self.appears_on.replace_all(albums.iter().map(|a| a.into()));
+                vec![BrowserEvent::ArtistDetailsUpdated(self.id.clone())]
+            }
+            BrowserAction::SetArtistCompilations(id, albums) if id == &self.id => {
+                self.compilations
+                    .replace_all(albums.iter().map(|a| a.into()));
+                vec![BrowserEvent::ArtistDetailsUpdated(self.id.clone())]
+            }
+            BrowserAction::FollowArtist(id) if id == &self.id => {
+                self.is_followed = true;
+                vec![BrowserEvent::ArtistFollowChanged(id.clone())]
+            }
+            BrowserAction::UnfollowArtist(id) if id == &self.id => {
+                self.is_followed = false;
+                vec![BrowserEvent::ArtistFollowChanged(id.clone())]
+            }
             _ => vec![],
         }
     }
@@ -401,6 +439,7 @@ mod tests {
                 name: "Foo".to_owned(),
                 albums: vec![],
                 top_tracks: vec![],
+                is_followed: false,
             },
         ))));
 
@@ -427,6 +466,7 @@ mod tests {
                 name: "Foo".to_owned(),
                 albums: (0..20).map(|_| fake_album.clone()).collect(),
                 top_tracks: vec![],
+                is_followed: false,
             },
         ))));
 
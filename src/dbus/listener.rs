@@ -2,9 +2,7 @@ use futures::channel::mpsc::UnboundedSender;
 use std::rc::Rc;
 
 use crate::app::{
-    components::EventListener,
-    models::SongDescription,
-    state::{PlaybackEvent, RepeatMode},
+    components::EventListener, loader::ImageLoader, models::SongDescription, state::PlaybackEvent,
     AppEvent, AppModel,
 };
 
@@ -19,6 +17,8 @@ pub enum MprisStateUpdate {
         has_next: bool,
     },
     SetPositionMs(u128),
+    // like SetPositionMs, but the position jumped and clients have to be told with Seeked
+    Seeked(u128),
     SetLoopStatus {
         has_prev: bool,
         loop_status: LoopStatus,
@@ -54,7 +54,8 @@ impl AppPlaybackStateListener {
             title,
             album: album.name,
             artist: artists.into_iter().map(|a| a.name).collect(),
-            art,
+            // a local copy is preferred, not every client can fetch remote art
+            art: art.map(|url| ImageLoader::new().cached_uri(&url, "jpg").unwrap_or(url)),
         })
     }
 
@@ -73,11 +74,7 @@ impl AppPlaybackStateListener {
 
     fn loop_status(&self) -> LoopStatus {
         let state = self.app_model.get_state();
-        match state.playback.repeat_mode() {
-            RepeatMode::None => LoopStatus::None,
-            RepeatMode::Song => LoopStatus::Track,
-            RepeatMode::Playlist => LoopStatus::Playlist,
-        }
+        state.playback.repeat_mode().into()
     }
 
     fn update_for(&self, event: &PlaybackEvent) -> Option<MprisStateUpdate> {
@@ -112,7 +109,11 @@ impl AppPlaybackStateListener {
             PlaybackEvent::ShuffleChanged => {
                 Some(MprisStateUpdate::SetShuffled(self.is_shuffled()))
             }
-            PlaybackEvent::TrackSeeked(pos) | PlaybackEvent::SeekSynced(pos) => {
+            PlaybackEvent::TrackSeeked(pos) => {
+                let pos = 1000 * (*pos as u128);
+                Some(MprisStateUpdate::Seeked(pos))
+            }
+            PlaybackEvent::SeekSynced(pos) => {
                 let pos = 1000 * (*pos as u128);
                 Some(MprisStateUpdate::SetPositionMs(pos))
             }
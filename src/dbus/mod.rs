@@ -61,6 +61,10 @@ async fn dbus_server(
                         player.state_mut().set_position(position);
                         Ok(())
                     }
+                    MprisStateUpdate::Seeked(position) => {
+                        player.state_mut().set_position(position);
+                        SpotMprisPlayer::seeked(ctxt, position as i64).await
+                    }
                     MprisStateUpdate::SetLoopStatus {
                         has_prev,
                         has_next,
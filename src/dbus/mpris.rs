@@ -10,7 +10,6 @@ use zbus::{dbus_interface, Interface, SignalContext};
 use zvariant::{ObjectPath, Value};
 
 use super::types::*;
-use crate::app::state::RepeatMode;
 use crate::app::{state::PlaybackAction, AppAction};
 
 #[derive(Clone)]
@@ -63,7 +62,7 @@ impl SpotMpris {
 
     #[dbus_interface(property)]
     fn supported_uri_schemes(&self) -> Vec<String> {
-        vec![]
+        vec!["spotify".to_string()]
     }
 
     #[dbus_interface(property)]
@@ -135,7 +134,11 @@ impl SpotMprisPlayer {
     }
 
     pub fn open_uri(&self, Uri: &str) -> Result<()> {
-        Err(Error::NotSupported("Not implemented".to_string()))
+        let action = AppAction::OpenURI(Uri.to_string())
+            .ok_or_else(|| Error::InvalidArgs("Unsupported URI".to_string()))?;
+        self.sender
+            .unbounded_send(action)
+            .map_err(|_| Error::Failed("Could not send action".to_string()))
     }
 
     pub fn pause(&self) -> Result<()> {
@@ -292,13 +295,8 @@ impl SpotMprisPlayer {
 
     #[dbus_interface(property)]
     pub fn set_loop_status(&self, value: LoopStatus) -> zbus::Result<()> {
-        let mode = match value {
-            LoopStatus::None => RepeatMode::None,
-            LoopStatus::Track => RepeatMode::Song,
-            LoopStatus::Playlist => RepeatMode::Playlist,
-        };
         self.sender
-            .unbounded_send(PlaybackAction::SetRepeatMode(mode).into())
+            .unbounded_send(PlaybackAction::SetRepeatMode(value.into()).into())
             .map_err(|_| Error::Failed("Could not send action".to_string()))?;
         Ok(())
     }
@@ -324,7 +322,7 @@ impl SpotMprisPlayer {
     #[dbus_interface(property)]
     pub fn set_shuffle(&self, value: bool) -> zbus::Result<()> {
         self.sender
-            .unbounded_send(PlaybackAction::ToggleShuffle.into())
+            .unbounded_send(PlaybackAction::SetShuffled(value).into())
             .map_err(|_| Error::Failed("Could not send action".to_string()))?;
         Ok(())
     }
@@ -3,6 +3,8 @@ use std::time::Instant;
 use zvariant::Type;
 use zvariant::{Dict, Signature, Str, Value};
 
+use crate::app::models::RepeatMode;
+
 fn boxed_value<'a, V: Into<Value<'a>>>(v: V) -> Value<'a> {
     Value::new(v.into())
 }
@@ -34,6 +36,26 @@ impl TryFrom<&Value<'_>> for LoopStatus {
     }
 }
 
+impl From<RepeatMode> for LoopStatus {
+    fn from(mode: RepeatMode) -> Self {
+        match mode {
+            RepeatMode::Off => LoopStatus::None,
+            RepeatMode::Track => LoopStatus::Track,
+            RepeatMode::Context => LoopStatus::Playlist,
+        }
+    }
+}
+
+impl From<LoopStatus> for RepeatMode {
+    fn from(status: LoopStatus) -> Self {
+        match status {
+            LoopStatus::None => RepeatMode::Off,
+            LoopStatus::Track => RepeatMode::Track,
+            LoopStatus::Playlist => RepeatMode::Context,
+        }
+    }
+}
+
 impl From<LoopStatus> for Value<'_> {
     fn from(status: LoopStatus) -> Self {
         match status {
@@ -86,6 +86,7 @@ fn main() {
         gtk_app.run();
     });
 
+    api::log_cache_stats();
     std::process::exit(0);
 }
 
@@ -0,0 +1,191 @@
+use librespot::playback::mixer::Mixer;
+use std::cell::Cell;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+// How often the volume gets adjusted while fading
+const FADE_STEP: Duration = Duration::from_millis(50);
+// Skipping shouldn't keep the user waiting for long
+const MAX_SKIP_FADE: Duration = Duration::from_secs(1);
+
+#[derive(Clone, Copy)]
+struct TrackPosition {
+    position_ms: u32,
+    duration_ms: u32,
+    // when the position was reported, None if paused
+    since: Option<Instant>,
+}
+
+impl TrackPosition {
+    fn current_ms(&self) -> u32 {
+        let elapsed = self
+            .since
+            .map(|since| since.elapsed().as_millis())
+            .unwrap_or(0);
+        self.position_ms.saturating_add(elapsed as u32)
+    }
+}
+
+// Rising over the first `fade` of a track, falling over the last
+fn envelope(position_ms: u32, duration_ms: u32, fade: Duration) -> f64 {
+    let fade = fade.as_millis() as f64;
+    if fade == 0.0 {
+        return 1.0;
+    }
+    let fade_in = position_ms as f64 / fade;
+    let fade_out = duration_ms.saturating_sub(position_ms) as f64 / fade;
+    fade_in.min(fade_out).clamp(0.0, 1.0)
+}
+
+// Fades tracks out as they end and the next ones in, through the mixer. librespot plays a
+// single stream, so the two follow each other rather than overlap.
+// With no fade, the volume is passed through as is.
+pub struct Fader {
+    mixer: Box<dyn Mixer>,
+    fade: Cell<Duration>,
+    // as set by the user, fades are relative to it
+    volume: Cell<u16>,
+    track: Cell<Option<TrackPosition>>,
+    // overrides the envelope while fading out ahead of a skip
+    skipping: Cell<Option<f64>>,
+    // counts skips, a fade out gives up on its own once a later one starts
+    skips: Cell<usize>,
+    following: Cell<bool>,
+}
+
+impl Fader {
+    pub fn new(mixer: Box<dyn Mixer>) -> Self {
+        let volume = mixer.volume();
+        Self {
+            mixer,
+            fade: Cell::new(Duration::ZERO),
+            volume: Cell::new(volume),
+            track: Cell::new(None),
+            skipping: Cell::new(None),
+            skips: Cell::new(0),
+            following: Cell::new(false),
+        }
+    }
+
+    pub fn mixer(&self) -> &dyn Mixer {
+        &*self.mixer
+    }
+
+    pub fn set_fade(&self, fade: Duration) {
+        self.fade.set(fade);
+        self.apply();
+    }
+
+    pub fn set_volume(&self, volume: u16) {
+        self.volume.set(volume);
+        self.apply();
+    }
+
+    // Returns whether follow_track needs to be started
+    pub fn track_playing(&self, position_ms: u32, duration_ms: u32) -> bool {
+        self.track.set(Some(TrackPosition {
+            position_ms,
+            duration_ms,
+            since: Some(Instant::now()),
+        }));
+        self.skipping.set(None);
+        self.apply();
+        !self.fade.get().is_zero() && !self.following.replace(true)
+    }
+
+    pub fn track_paused(&self, position_ms: u32, duration_ms: u32) {
+        self.track.set(Some(TrackPosition {
+            position_ms,
+            duration_ms,
+            since: None,
+        }));
+        self.apply();
+    }
+
+    pub fn track_stopped(&self) {
+        self.track.set(None);
+        self.skipping.set(None);
+        self.apply();
+    }
+
+    // Brings a playing track down before another one is loaded over it, the way it would at its end.
+    // The volume stays down until the next track plays and fades in.
+    // Resolves to false if another skip or a stop came in meanwhile, the track shouldn't be loaded then.
+    pub async fn fade_out(&self) -> bool {
+        let skip = self.skips.get().wrapping_add(1);
+        self.skips.set(skip);
+
+        let fade = self.fade.get().min(MAX_SKIP_FADE);
+        let mut factor = match self.track.get() {
+            Some(track) if self.fades_on_skip() => self.skipping.get().unwrap_or_else(|| {
+                envelope(track.current_ms(), track.duration_ms, self.fade.get())
+            }),
+            _ => return true,
+        };
+        let step = FADE_STEP.as_secs_f64() / fade.as_secs_f64();
+        while factor > 0.0 {
+            factor = (factor - step).max(0.0);
+            self.skipping.set(Some(factor));
+            self.apply();
+            async_std::task::sleep(FADE_STEP).await;
+            if self.skips.get() != skip {
+                return false;
+            }
+        }
+        true
+    }
+
+    // Whether a track loaded now would wait for fade_out
+    pub fn fades_on_skip(&self) -> bool {
+        self.is_advancing() && !self.fade.get().is_zero()
+    }
+
+    // Keeps a skip that's still fading out from loading its track
+    pub fn cancel_skip(&self) {
+        self.skips.set(self.skips.get().wrapping_add(1));
+    }
+
+    // Adjusts the volume for as long as the track plays
+    pub async fn follow_track(self: Rc<Self>) {
+        while self.is_advancing() && !self.fade.get().is_zero() {
+            async_std::task::sleep(FADE_STEP).await;
+            self.apply();
+        }
+        self.following.set(false);
+    }
+
+    fn is_advancing(&self) -> bool {
+        matches!(self.track.get(), Some(TrackPosition { since: Some(_), .. }))
+    }
+
+    fn apply(&self) {
+        let fade = self.fade.get();
+        let factor = self.skipping.get().unwrap_or_else(|| {
+            self.track
+                .get()
+                .map(|track| envelope(track.current_ms(), track.duration_ms, fade))
+                .unwrap_or(1.0)
+        });
+        self.mixer
+            .set_volume((self.volume.get() as f64 * factor) as u16);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_envelope() {
+        let fade = Duration::from_secs(4);
+        assert_eq!(envelope(0, 10_000, fade), 0.0);
+        assert_eq!(envelope(2_000, 10_000, fade), 0.5);
+        assert_eq!(envelope(5_000, 10_000, fade), 1.0);
+        assert_eq!(envelope(9_000, 10_000, fade), 0.25);
+        assert_eq!(envelope(11_000, 10_000, fade), 0.0);
+
+        assert_eq!(envelope(0, 10_000, Duration::ZERO), 1.0);
+        assert_eq!(envelope(10_000, 10_000, Duration::ZERO), 1.0);
+    }
+}
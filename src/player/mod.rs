@@ -9,6 +9,7 @@ use crate::app::credentials::Credentials;
 use crate::app::state::{LoginAction, PlaybackAction, SetLoginSuccessAction};
 use crate::app::AppAction;
 
+mod fader;
 mod player;
 pub use player::*;
 
@@ -18,6 +19,7 @@ pub enum Command {
     TokenLogin { username: String, token: String },
     Logout,
     PlayerLoad(SpotifyId),
+    PlayerPreload(SpotifyId),
     PlayerResume,
     PlayerPause,
     PlayerStop,
@@ -88,6 +90,20 @@ impl SpotifyPlayerDelegate for AppPlayerDelegate {
             .unwrap();
     }
 
+    fn track_unavailable(&self) {
+        self.sender
+            .borrow_mut()
+            .unbounded_send(AppAction::ReportTrackUnavailable)
+            .unwrap();
+    }
+
+    fn preload_next_track(&self) {
+        self.sender
+            .borrow_mut()
+            .unbounded_send(PlaybackAction::PreloadNext.into())
+            .unwrap();
+    }
+
     fn notify_playback_state(&self, position: u32) {
         self.sender
             .borrow_mut()
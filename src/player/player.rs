@@ -11,7 +11,9 @@ use librespot::playback::mixer::{Mixer, MixerConfig};
 use librespot::protocol::authentication::AuthenticationType;
 
 use librespot::playback::audio_backend;
-use librespot::playback::config::{AudioFormat, Bitrate, PlayerConfig, VolumeCtrl};
+use librespot::playback::config::{
+    AudioFormat, Bitrate, NormalisationType, PlayerConfig, VolumeCtrl,
+};
 use librespot::playback::player::{Player, PlayerEvent, PlayerEventChannel};
 
 use std::cell::RefCell;
@@ -20,6 +22,7 @@ use std::fmt;
 use std::rc::Rc;
 use std::time::{Duration, SystemTime};
 
+use super::fader::Fader;
 use super::Command;
 use crate::app::credentials;
 use crate::settings::SpotSettings;
@@ -49,6 +52,8 @@ pub trait SpotifyPlayerDelegate {
     fn token_login_successful(&self, username: String, token: String);
     fn refresh_successful(&self, token: String, token_expiry_time: SystemTime);
     fn report_error(&self, error: SpotifyError);
+    fn track_unavailable(&self);
+    fn preload_next_track(&self);
     fn notify_playback_state(&self, position: u32);
 }
 
@@ -63,6 +68,10 @@ pub struct SpotifyPlayerSettings {
     pub bitrate: Bitrate,
     pub backend: AudioBackend,
     pub ap_port: Option<u16>,
+    pub gapless: bool,
+    pub fade: Duration,
+    // None leaves the volume of tracks untouched
+    pub normalisation: Option<NormalisationType>,
 }
 
 impl Default for SpotifyPlayerSettings {
@@ -71,14 +80,18 @@ impl Default for SpotifyPlayerSettings {
             bitrate: Bitrate::Bitrate160,
             backend: AudioBackend::PulseAudio,
             ap_port: None,
+            gapless: true,
+            fade: Duration::ZERO,
+            normalisation: None,
         }
     }
 }
 
 pub struct SpotifyPlayer {
     settings: RefCell<SpotifyPlayerSettings>,
-    player: RefCell<Option<Player>>,
-    mixer: RefCell<Option<Box<dyn Mixer>>>,
+    // shared with loads that wait for a fade out, it's never borrowed across an await
+    player: Rc<RefCell<Option<Player>>>,
+    fader: RefCell<Option<Rc<Fader>>>,
     session: RefCell<Option<Session>>,
     delegate: Rc<dyn SpotifyPlayerDelegate>,
 }
@@ -87,46 +100,75 @@ impl SpotifyPlayer {
     pub fn new(settings: SpotifyPlayerSettings, delegate: Rc<dyn SpotifyPlayerDelegate>) -> Self {
         Self {
             settings: RefCell::new(settings),
-            mixer: RefCell::new(None),
-            player: RefCell::new(None),
+            fader: RefCell::new(None),
+            player: Rc::new(RefCell::new(None)),
             session: RefCell::new(None),
             delegate,
         }
     }
 
     async fn handle(&self, action: Command) -> Result<(), SpotifyError> {
-        let mut player = self.player.borrow_mut();
         let mut session = self.session.borrow_mut();
         match action {
             Command::PlayerSetVolume(volume) => {
-                if let Some(mixer) = self.mixer.borrow_mut().as_mut() {
-                    mixer.set_volume((VolumeCtrl::MAX_VOLUME as f64 * volume) as u16);
+                if let Some(fader) = self.fader.borrow().as_ref() {
+                    fader.set_volume((VolumeCtrl::MAX_VOLUME as f64 * volume) as u16);
                 }
                 Ok(())
             }
             Command::PlayerResume => {
+                let player = self.player.borrow();
                 let player = player.as_ref().ok_or(SpotifyError::PlayerNotReady)?;
                 player.play();
                 Ok(())
             }
             Command::PlayerPause => {
+                let player = self.player.borrow();
                 let player = player.as_ref().ok_or(SpotifyError::PlayerNotReady)?;
                 player.pause();
                 Ok(())
             }
             Command::PlayerStop => {
+                let player = self.player.borrow();
                 let player = player.as_ref().ok_or(SpotifyError::PlayerNotReady)?;
+                self.fader().cancel_skip();
                 player.stop();
                 Ok(())
             }
             Command::PlayerSeek(position) => {
+                let player = self.player.borrow();
                 let player = player.as_ref().ok_or(SpotifyError::PlayerNotReady)?;
                 player.seek(position);
                 Ok(())
             }
             Command::PlayerLoad(track) => {
+                let mut player = self.player.borrow_mut();
                 let player = player.as_mut().ok_or(SpotifyError::PlayerNotReady)?;
-                player.load(track, true, 0);
+                let fader = self.fader();
+                if !fader.fades_on_skip() {
+                    fader.cancel_skip();
+                    player.load(track, true, 0);
+                    return Ok(());
+                }
+                // skipping fades the current track out first, other commands are handled meanwhile
+                let shared_player = Rc::clone(&self.player);
+                tokio::task::spawn_local(async move {
+                    if fader.fade_out().await {
+                        if let Some(player) = shared_player.borrow_mut().as_mut() {
+                            player.load(track, true, 0);
+                        }
+                    }
+                });
+                Ok(())
+            }
+            // The next track is loaded ahead of time so that the player can go over to it
+            // without a gap, loading it later on picks up the preloaded one
+            Command::PlayerPreload(track) => {
+                let player = self.player.borrow();
+                let player = player.as_ref().ok_or(SpotifyError::PlayerNotReady)?;
+                if self.settings.borrow().gapless {
+                    player.preload(track);
+                }
                 Ok(())
             }
             Command::RefreshToken => {
@@ -140,7 +182,7 @@ impl SpotifyPlayer {
                     .take()
                     .ok_or(SpotifyError::PlayerNotReady)?
                     .shutdown();
-                let _ = player.take();
+                let _ = self.player.borrow_mut().take();
                 Ok(())
             }
             Command::PasswordLogin { username, password } => {
@@ -159,8 +201,12 @@ impl SpotifyPlayer {
                 self.delegate.password_login_successful(credentials);
 
                 let (new_player, channel) = self.create_player(new_session.clone());
-                tokio::task::spawn_local(player_setup_delegate(channel, Rc::clone(&self.delegate)));
-                player.replace(new_player);
+                tokio::task::spawn_local(player_setup_delegate(
+                    channel,
+                    Rc::clone(&self.delegate),
+                    self.fader(),
+                ));
+                self.player.borrow_mut().replace(new_player);
                 session.replace(new_session);
 
                 Ok(())
@@ -177,8 +223,12 @@ impl SpotifyPlayer {
                     .token_login_successful(new_session.username(), token);
 
                 let (new_player, channel) = self.create_player(new_session.clone());
-                tokio::task::spawn_local(player_setup_delegate(channel, Rc::clone(&self.delegate)));
-                player.replace(new_player);
+                tokio::task::spawn_local(player_setup_delegate(
+                    channel,
+                    Rc::clone(&self.delegate),
+                    self.fader(),
+                ));
+                self.player.borrow_mut().replace(new_player);
                 session.replace(new_session);
 
                 Ok(())
@@ -189,8 +239,12 @@ impl SpotifyPlayer {
 
                 let session = session.as_ref().ok_or(SpotifyError::PlayerNotReady)?;
                 let (new_player, channel) = self.create_player(session.clone());
-                tokio::task::spawn_local(player_setup_delegate(channel, Rc::clone(&self.delegate)));
-                player.replace(new_player);
+                tokio::task::spawn_local(player_setup_delegate(
+                    channel,
+                    Rc::clone(&self.delegate),
+                    self.fader(),
+                ));
+                self.player.borrow_mut().replace(new_player);
 
                 Ok(())
             }
@@ -203,25 +257,16 @@ impl SpotifyPlayer {
 
         let player_config = PlayerConfig {
             bitrate: settings.bitrate,
+            gapless: settings.gapless,
+            normalisation: settings.normalisation.is_some(),
+            normalisation_type: settings.normalisation.unwrap_or_default(),
             ..Default::default()
         };
         info!("bitrate: {:?}", &player_config.bitrate);
 
-        let filter = self
-            .mixer
-            .borrow_mut()
-            .get_or_insert_with(|| {
-                let mix = Box::new(SoftMixer::open(MixerConfig {
-                    // This value feels reasonable to me. Feel free to change it
-                    volume_ctrl: VolumeCtrl::Log(VolumeCtrl::DEFAULT_DB_RANGE / 2.0),
-                    ..Default::default()
-                }));
-                // TODO: Should read volume from somewhere instead of hard coding.
-                // Sets volume to 100%
-                mix.set_volume(VolumeCtrl::MAX_VOLUME);
-                mix
-            })
-            .get_audio_filter();
+        let fader = self.fader();
+        fader.set_fade(settings.fade);
+        let filter = fader.mixer().get_audio_filter();
         Player::new(player_config, session, filter, move || match backend {
             AudioBackend::PulseAudio => {
                 info!("using pulseaudio");
@@ -236,6 +281,22 @@ impl SpotifyPlayer {
         })
     }
 
+    fn fader(&self) -> Rc<Fader> {
+        let mut fader = self.fader.borrow_mut();
+        let fader = fader.get_or_insert_with(|| {
+            let mix = Box::new(SoftMixer::open(MixerConfig {
+                // This value feels reasonable to me. Feel free to change it
+                volume_ctrl: VolumeCtrl::Log(VolumeCtrl::DEFAULT_DB_RANGE / 2.0),
+                ..Default::default()
+            }));
+            // TODO: Should read volume from somewhere instead of hard coding.
+            // Sets volume to 100%
+            mix.set_volume(VolumeCtrl::MAX_VOLUME);
+            Rc::new(Fader::new(mix))
+        });
+        Rc::clone(fader)
+    }
+
     pub async fn start(self, receiver: UnboundedReceiver<Command>) -> Result<(), ()> {
         let _self = &self;
         receiver
@@ -257,8 +318,12 @@ playlist-read-private,\
 playlist-read-collaborative,\
 user-library-read,\
 user-library-modify,\
+user-follow-read,\
+user-follow-modify,\
 user-top-read,\
 user-read-recently-played,\
+user-read-playback-state,\
+user-modify-playback-state,\
 playlist-modify-public,\
 playlist-modify-private,\
 streaming";
@@ -319,14 +384,41 @@ async fn create_session(
 async fn player_setup_delegate(
     mut channel: PlayerEventChannel,
     delegate: Rc<dyn SpotifyPlayerDelegate>,
+    fader: Rc<Fader>,
 ) {
     while let Some(event) = channel.recv().await {
         match event {
-            PlayerEvent::EndOfTrack { .. } | PlayerEvent::Stopped { .. } => {
+            PlayerEvent::EndOfTrack { .. } => {
+                delegate.end_of_track_reached();
+            }
+            PlayerEvent::Stopped { .. } => {
+                fader.track_stopped();
                 delegate.end_of_track_reached();
             }
-            PlayerEvent::Playing { position_ms, .. } => {
+            PlayerEvent::Playing {
+                position_ms,
+                duration_ms,
+                ..
+            } => {
                 delegate.notify_playback_state(position_ms);
+                if fader.track_playing(position_ms, duration_ms) {
+                    tokio::task::spawn_local(Rc::clone(&fader).follow_track());
+                }
+            }
+            PlayerEvent::Paused {
+                position_ms,
+                duration_ms,
+                ..
+            } => {
+                fader.track_paused(position_ms, duration_ms);
+            }
+            PlayerEvent::Unavailable { .. } => {
+                // a skip may have left the volume down for it
+                fader.track_stopped();
+                delegate.track_unavailable();
+            }
+            PlayerEvent::TimeToPreloadNextTrack { .. } => {
+                delegate.preload_next_track();
             }
             _ => {}
         }
@@ -1,6 +1,7 @@
 use crate::player::{AudioBackend, SpotifyPlayerSettings};
 use gio::prelude::SettingsExt;
-use librespot::playback::config::Bitrate;
+use librespot::playback::config::{Bitrate, NormalisationType};
+use std::time::Duration;
 
 const SETTINGS: &str = "dev.alextren.Spot";
 
@@ -50,6 +51,11 @@ impl SpotifyPlayerSettings {
             )),
             _ => None,
         }?;
+        let normalisation = match settings.enum_("volume-normalisation") {
+            1 => Some(NormalisationType::Track),
+            2 => Some(NormalisationType::Album),
+            _ => None,
+        };
         let ap_port_val = settings.uint("ap-port");
         if ap_port_val > 65535 {
             panic!("Invalid access point port");
@@ -67,6 +73,9 @@ impl SpotifyPlayerSettings {
             bitrate,
             backend,
             ap_port,
+            gapless: settings.boolean("gapless-playback"),
+            fade: Duration::from_secs(settings.uint("fade-duration").into()),
+            normalisation,
         })
     }
 }
@@ -76,16 +85,23 @@ pub struct SpotSettings {
     pub prefers_dark_theme: bool,
     pub player_settings: SpotifyPlayerSettings,
     pub window: WindowGeometry,
+    pub prefetch_pages: bool,
+    pub content_language: Option<String>,
 }
 
 impl SpotSettings {
     pub fn new_from_gsettings() -> Option<Self> {
         let settings = gio::Settings::new(SETTINGS);
         let prefers_dark_theme = settings.boolean("prefers-dark-theme");
+        let prefetch_pages = settings.boolean("prefetch-pages");
+        let content_language = Some(settings.string("content-language").as_str().to_string())
+            .filter(|language| !language.is_empty());
         Some(Self {
             prefers_dark_theme,
             player_settings: SpotifyPlayerSettings::new_from_gsettings()?,
             window: WindowGeometry::new_from_gsettings(),
+            prefetch_pages,
+            content_language,
         })
     }
 }
@@ -96,6 +112,8 @@ impl Default for SpotSettings {
             prefers_dark_theme: true,
             player_settings: Default::default(),
             window: Default::default(),
+            prefetch_pages: false,
+            content_language: None,
         }
     }
 }